@@ -1,4 +1,9 @@
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use opencode_pm_core::ko_resolver::KoError;
 use opencode_pm_core::laio_service::ServiceRegistry;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -77,3 +82,269 @@ pub async fn validate_proxy(
         ),
     }
 }
+
+/// GET /v1/ko/{id}: `id` is a KO URI (`ko:specbundle/<uuid>`, or `ko://docs/adr/0001`),
+/// percent-encoded or not, or just `<namespace>/<path>`.
+pub async fn ko_get(Path(id): Path<String>) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::ko_resolve::{KoResolveReq, KoService};
+
+    let id = match id.strip_prefix("ko:") {
+        // Some clients collapse the `//` of an unencoded URI in a path
+        Some(rest) if rest.starts_with('/') && !rest.starts_with("//") => format!("ko:/{rest}"),
+        Some(_) => id,
+        None => format!("ko:{id}"),
+    };
+    match KoService::resolve(KoResolveReq { id }).await {
+        Ok(ko) => (StatusCode::OK, Json(serde_json::to_value(ko).unwrap())),
+        Err(e) => {
+            let (code, error) = match e.downcast_ref::<KoError>() {
+                Some(KoError::NotFound(_)) => (StatusCode::NOT_FOUND, "ko_not_found"),
+                Some(KoError::InvalidUri(_) | KoError::UnknownNamespace(_)) => (StatusCode::BAD_REQUEST, "invalid_ko_id"),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, "ko_resolve_failed"),
+            };
+            (code, Json(serde_json::json!({ "ok": false, "error": error, "msg": e.to_string() })))
+        }
+    }
+}
+
+fn runlog_error(e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    let msg = e.to_string();
+    let code = msg.split(':').next().unwrap_or_default().to_string();
+    let status = match code.as_str() {
+        "bad_request" => StatusCode::BAD_REQUEST,
+        "attempt_not_found" => StatusCode::NOT_FOUND,
+        "runlog_finalized" => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(serde_json::json!({ "ok": false, "error": code, "msg": msg })))
+}
+
+/// POST /v1/runlog/{task_id}/{attempt}/entries: NDJSON body, one entry per line. Entries
+/// are appended as their lines arrive, so an agent can keep the request open for the whole
+/// attempt; a bad line, or one over `MAX_ENTRY_BYTES`, ends the request with everything
+/// before it kept.
+pub async fn runlog_append_stream(
+    Path((task_id, attempt)): Path<(uuid::Uuid, i32)>,
+    body: axum::body::Body,
+) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::runlog::{RunlogAppendReq, RunlogEntryIn, RunlogService, MAX_ENTRY_BYTES};
+    use futures_util::StreamExt;
+
+    let too_long = |n: usize| anyhow::anyhow!("bad_request: entry {n}: line longer than {MAX_ENTRY_BYTES} bytes");
+    let mut stream = body.into_data_stream();
+    let mut buf: Vec<u8> = Vec::new();
+    let mut appended = 0;
+    let mut next_seq = 0;
+    let mut done = false;
+    while !done {
+        match stream.next().await {
+            Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+            Some(Err(e)) => return runlog_error(anyhow::anyhow!("bad_request: {e}")),
+            None => {
+                // A final line without a trailing newline still counts
+                if !buf.is_empty() {
+                    buf.push(b'\n');
+                }
+                done = true;
+            }
+        }
+        let Some(end) = buf.iter().rposition(|b| *b == b'\n') else {
+            if buf.len() > MAX_ENTRY_BYTES {
+                return runlog_error(too_long(appended));
+            }
+            continue;
+        };
+        let lines: Vec<u8> = buf.drain(..=end).collect();
+        let mut entries = Vec::new();
+        for line in lines.split(|b| *b == b'\n').filter(|l| !l.iter().all(u8::is_ascii_whitespace)) {
+            if line.len() > MAX_ENTRY_BYTES {
+                return runlog_error(too_long(appended + entries.len()));
+            }
+            match serde_json::from_slice::<RunlogEntryIn>(line) {
+                Ok(e) => entries.push(e),
+                Err(e) => return runlog_error(anyhow::anyhow!("bad_request: entry {}: {e}", appended + entries.len())),
+            }
+        }
+        if entries.is_empty() {
+            continue;
+        }
+        match RunlogService::append(RunlogAppendReq { task_id, attempt, entries }).await {
+            Ok(resp) => {
+                appended += resp.appended;
+                next_seq = resp.next_seq;
+            }
+            Err(e) => return runlog_error(e),
+        }
+    }
+    (StatusCode::OK, Json(serde_json::json!({ "ok": true, "appended": appended, "next_seq": next_seq })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FollowQuery {
+    /// Skip entries with `seq` below this.
+    pub after: Option<usize>,
+}
+
+/// GET /v1/runlog/{task_id}/{attempt}/follow: server-sent `entry` events as the log grows,
+/// then one `end` event once the attempt is finalized.
+pub async fn runlog_follow(
+    Path((task_id, attempt)): Path<(uuid::Uuid, i32)>,
+    axum::extract::Query(q): axum::extract::Query<FollowQuery>,
+) -> axum::response::Response {
+    use crate::services::runlog::{read_entries_from, RunlogService, SeqEntry};
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use axum::response::IntoResponse;
+    use std::collections::VecDeque;
+
+    let dir = match RunlogService::attempt_dir(task_id, attempt).await {
+        Ok(d) => d,
+        Err(e) => return runlog_error(e).into_response(),
+    };
+    // (byte offset, next seq, pending events, ended)
+    let state = (0u64, 0usize, VecDeque::<Event>::new(), false);
+    let after = q.after.unwrap_or(0);
+    let stream = futures_util::stream::unfold(state, move |(mut offset, mut seq, mut pending, mut ended)| {
+        let dir = dir.clone();
+        async move {
+            loop {
+                if let Some(ev) = pending.pop_front() {
+                    return Some((Ok::<_, std::convert::Infallible>(ev), (offset, seq, pending, ended)));
+                }
+                if ended {
+                    return None;
+                }
+                // Check before reading so entries written just before finalization are sent
+                let sealed = RunlogService::is_finalized(&dir);
+                match read_entries_from(&dir, offset).await {
+                    Ok((entries, next)) => {
+                        offset = next;
+                        for entry in entries {
+                            if seq >= after {
+                                let data = serde_json::to_string(&SeqEntry { seq, entry }).unwrap_or_default();
+                                pending.push_back(Event::default().event("entry").id(seq.to_string()).data(data));
+                            }
+                            seq += 1;
+                        }
+                    }
+                    Err(e) => {
+                        pending.push_back(Event::default().event("error").data(e.to_string()));
+                        ended = true;
+                        continue;
+                    }
+                }
+                if sealed {
+                    pending.push_back(Event::default().event("end").data(seq.to_string()));
+                    ended = true;
+                } else if pending.is_empty() {
+                    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+                }
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GateDecisionBody {
+    pub approve: bool,
+    pub reason: Option<String>,
+    pub actor: Option<String>,
+}
+
+/// POST /v1/gates/{gate_id}/decision: an approver approves or rejects a gate, identified by
+/// `actor` and their token in `Authorization: Bearer <token>`.
+pub async fn gate_decide(
+    Path(gate_id): Path<uuid::Uuid>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<GateDecisionBody>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::gate::{GateDecideReq, GateService};
+
+    let token = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string());
+    let req = GateDecideReq { gate_id, approve: body.approve, reason: body.reason, actor: body.actor, token };
+    match GateService::decide(req).await {
+        Ok(resp) => (StatusCode::OK, Json(serde_json::to_value(resp).unwrap())),
+        Err(e) => {
+            let msg = e.to_string();
+            let code = msg.split(':').next().unwrap_or_default().to_string();
+            let status = match code.as_str() {
+                "bad_request" => StatusCode::BAD_REQUEST,
+                "forbidden" => StatusCode::FORBIDDEN,
+                "gate_not_found" => StatusCode::NOT_FOUND,
+                "invalid_transition" => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(serde_json::json!({ "ok": false, "error": code, "msg": msg })))
+        }
+    }
+}
+
+fn project_view_error(e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    let msg = e.to_string();
+    let code = msg.split(':').next().unwrap_or_default().to_string();
+    let status = match code.as_str() {
+        "bad_request" => StatusCode::BAD_REQUEST,
+        "project_not_found" | "task_not_found" => StatusCode::NOT_FOUND,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, Json(serde_json::json!({ "ok": false, "error": code, "msg": msg })))
+}
+
+fn json_or_error<T: Serialize>(r: anyhow::Result<T>) -> (StatusCode, Json<serde_json::Value>) {
+    match r {
+        Ok(v) => (StatusCode::OK, Json(serde_json::to_value(v).unwrap())),
+        Err(e) => project_view_error(e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueQuery {
+    pub queue_id: Option<uuid::Uuid>,
+}
+
+/// GET /v1/projects/{project_id}/ready: tasks whose dependency condition is met.
+pub async fn project_ready(
+    Path(project_id): Path<uuid::Uuid>,
+    axum::extract::Query(q): axum::extract::Query<QueueQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::scheduler::{SchedulerService, TaskReadyReq};
+    json_or_error(SchedulerService::ready(TaskReadyReq { project_id, queue_id: q.queue_id }).await)
+}
+
+/// GET /v1/projects/{project_id}/blocked: pending tasks with what they wait on.
+pub async fn project_blocked(
+    Path(project_id): Path<uuid::Uuid>,
+    axum::extract::Query(q): axum::extract::Query<QueueQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::task_graph::{TaskBlockedReq, TaskGraphService};
+    json_or_error(TaskGraphService::blocked(TaskBlockedReq { project_id, queue_id: q.queue_id }).await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CriticalPathQuery {
+    pub target: String,
+}
+
+/// GET /v1/projects/{project_id}/critical-path?target=GATE-A-READY
+pub async fn project_critical_path(
+    Path(project_id): Path<uuid::Uuid>,
+    axum::extract::Query(q): axum::extract::Query<CriticalPathQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    use crate::services::task_graph::{CriticalPathReq, TaskGraphService};
+    json_or_error(TaskGraphService::critical_path(CriticalPathReq { project_id, target: q.target }).await)
+}
+
+/// GET /v1/projects/{project_id}/graph.mmd: Mermaid source of the task graph.
+pub async fn project_graph(Path(project_id): Path<uuid::Uuid>) -> axum::response::Response {
+    use crate::services::task_graph::{TaskGraphReq, TaskGraphService};
+    use axum::response::IntoResponse;
+
+    match TaskGraphService::graph(TaskGraphReq { project_id }).await {
+        Ok(g) => ([(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")], g.mermaid).into_response(),
+        Err(e) => project_view_error(e).into_response(),
+    }
+}
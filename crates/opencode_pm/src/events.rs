@@ -8,7 +8,7 @@ pub struct QueueTaskCreated {
     pub ko_refs: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicyDecision {
     pub task_id: String,
     pub decisions: Vec<String>,
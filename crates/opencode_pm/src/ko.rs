@@ -1,13 +1,46 @@
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct KoRef {
-    pub id: String,
+pub use opencode_pm_core::context_index::KoRef;
+
+/// One KO in a pack, its content redacted and trimmed to the pack's budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextPackItem {
+    #[serde(flatten)]
+    pub ko: KoRef,
+    pub content: String,
+    /// Estimated tokens of `content`.
+    pub tokens: usize,
+    pub truncated: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContextPack {
     pub ko_id: String,
-    pub items: Vec<KoRef>,
-    pub redactions: Vec<String>,
+    pub task: Option<String>,
+    pub path: Option<String>,
+    pub token_budget: usize,
+    /// Estimated tokens used, item headers included.
+    pub tokens: usize,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub items: Vec<ContextPackItem>,
+    /// Number of caller-supplied redaction patterns applied. The patterns themselves are not
+    /// kept: they are usually the very strings the caller wanted hidden.
+    #[serde(deserialize_with = "count_or_list")]
+    pub redactions: usize,
+}
+
+/// Packs built before redactions were counted listed the patterns; read those as their count.
+fn count_or_list<'de, D: serde::Deserializer<'de>>(d: D) -> Result<usize, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CountOrList {
+        Count(usize),
+        List(Vec<serde::de::IgnoredAny>),
+    }
+    Ok(match CountOrList::deserialize(d)? {
+        CountOrList::Count(n) => n,
+        CountOrList::List(l) => l.len(),
+    })
 }
@@ -11,6 +11,7 @@ use axum::{
     response::{Html, IntoResponse},
 };
 use opencode_pm_core::{
+    embedding::HashEmbedder,
     context_service,
     laio_service::{ServiceRegistry, VosMessage},
 };
@@ -23,8 +24,61 @@ async fn main() {
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
+    // `opencode_pm audit-verify` checks the audit log as it is on disk and exits, before
+    // anything opens (and possibly repairs) it
+    if std::env::args().nth(1).as_deref() == Some("audit-verify") {
+        let path = services::audit::default_path().expect("no genesis home");
+        let report = services::audit::AuditLog::verify_file(&path).await.expect("audit verify failed");
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        std::process::exit(if report.ok { 0 } else { 1 });
+    }
+
+    // Ingestion queue + knowledge store (worker is started below)
+    // Without it ingestion and knowledge search are unavailable; everything else still serves
+    let ingest_queue = match services::ingest::IngestQueue::from_env() {
+        Ok(q) => {
+            let q = Arc::new(q);
+            services::ingest::init_queue(q.clone());
+            Some(q)
+        }
+        Err(e) => {
+            tracing::error!("ingest queue unavailable: {e}");
+            None
+        }
+    };
+
+    // Context index for context.fetch: workspace docs, contracts, schemas and policies, ingested
+    // KOs, SpecBundle heads and sealed runlogs
+    let mut context_index =
+        services::context_pack::new_index(ingest_queue.as_ref().map(|q| q.knowledge())).expect("no workspace root");
+    // CONTEXT_EMBEDDER: "hash" (default, local), "none", "local:<model dir>" (ONNX on the CPU,
+    // needs the local-embeddings feature), or "<provider>:<model>"
+    match std::env::var("CONTEXT_EMBEDDER").as_deref().unwrap_or("hash") {
+        "none" => {}
+        "hash" => context_index = context_index.with_embedder(Arc::new(HashEmbedder::default())),
+        #[cfg(feature = "local-embeddings")]
+        spec if spec.starts_with("local:") => {
+            let embedder = services::model_manager::LocalEmbedder::open(&spec["local:".len()..])
+                .unwrap_or_else(|e| panic!("CONTEXT_EMBEDDER={spec}: {e}"));
+            context_index = context_index.with_embedder(Arc::new(embedder));
+        }
+        #[cfg(not(feature = "local-embeddings"))]
+        spec if spec.starts_with("local:") => panic!("CONTEXT_EMBEDDER={spec} needs a build with --features local-embeddings"),
+        spec => match spec.split_once(':') {
+            Some((provider, model)) => {
+                let embedder = services::model_manager::ProviderEmbedder::new(provider, model);
+                context_index = context_index.with_embedder(Arc::new(embedder));
+            }
+            None => panic!("CONTEXT_EMBEDDER must be hash, none, local:<model dir> or <provider>:<model>, got '{spec}'"),
+        },
+    }
+    let context_index = Arc::new(context_index);
+    services::context_pack::init_index(context_index.clone());
+    // Indexing and embedding run in the background; queries only read the index
+    services::context_pack::spawn_indexer(context_index.clone(), services::context_pack::index_interval_from_env());
+
     // minimal service registry (Core↔Core)
-    let registry: Arc<ServiceRegistry> = context_service::default_registry();
+    let registry: Arc<ServiceRegistry> = context_service::default_registry(context_index);
     let sidecar_base =
         std::env::var("SIDECAR_BASE").unwrap_or_else(|_| "http://127.0.0.1:8079".into());
     let app_state = Arc::new(api::AppState {
@@ -33,6 +87,58 @@ async fn main() {
         sidecar_base,
     });
 
+    // Hash-chained audit log
+    let audit_log = services::audit::log().await.expect("cannot open audit log");
+    services::audit::init_log(audit_log);
+
+    // SpecBundle storage backend (local fs or S3-compatible), chosen by env
+    // A bad config does not stop the server; SpecBundle ops report it as `config_invalid:`
+    match services::storage::bundle_store_from_env() {
+        Ok(bundle_store) => {
+            tracing::info!("specbundle store: {}", bundle_store.describe());
+            services::specbundle::init_store(bundle_store);
+        }
+        Err(e) => tracing::error!("specbundle store unavailable: {e}"),
+    }
+
+    // Per-project orchestration ledgers, snapshotted periodically when they change
+    let ledger_store = services::ledger::LedgerStore::open(
+        services::storage::genesis_home().expect("no genesis home").join("ledger"),
+    )
+    .await
+    .expect("cannot open ledger store");
+    services::ledger::init_store(Arc::new(ledger_store));
+    if let Some((interval, keep)) = services::snapshot::periodic_from_env() {
+        services::snapshot::spawn_periodic(interval, keep);
+    }
+    if let Some(interval) = services::lease::expiry_from_env() {
+        services::lease::spawn_expiry(interval);
+    }
+
+    // Ingestion worker: persistent job queue feeding the knowledge store
+    if let Some(q) = ingest_queue {
+        q.spawn_worker();
+    }
+
+    // Report half-written or tampered SpecBundles left over from a previous run, then offer the
+    // rest as context
+    tokio::spawn(async {
+        use crate::services::specbundle::{SpecbundleService, SpecbundleVerifyReq};
+        match SpecbundleService::verify(SpecbundleVerifyReq::default()).await {
+            Ok(r) => {
+                for p in &r.problems {
+                    tracing::warn!(ko_id = ?p.ko_id, path = %p.path, status = ?p.status, "specbundle verify: {}", p.detail);
+                }
+                tracing::info!("specbundle verify: {} bundle(s) checked, {} problem(s)", r.checked, r.problems.len());
+            }
+            Err(e) => tracing::warn!("specbundle verify failed: {e}"),
+        }
+        match services::specbundle::load_context_docs().await {
+            Ok(n) => tracing::info!("context index: {n} specbundle(s) offered"),
+            Err(e) => tracing::warn!("context index: specbundles not offered: {e}"),
+        }
+    });
+
     let cors = tower_http::cors::CorsLayer::new()
         .allow_methods([Method::GET, Method::POST])
         .allow_origin(tower_http::cors::Any)
@@ -53,6 +159,14 @@ async fn main() {
         .route("/v1/health", get(api::health))
         .route("/v1/validate", post(api::validate_proxy))
         .route("/v1/vos_dispatch", post(vos_dispatch))
+        .route("/v1/ko/*id", get(api::ko_get))
+        .route("/v1/runlog/:task_id/:attempt/entries", post(api::runlog_append_stream))
+        .route("/v1/runlog/:task_id/:attempt/follow", get(api::runlog_follow))
+        .route("/v1/gates/:gate_id/decision", post(api::gate_decide))
+        .route("/v1/projects/:project_id/ready", get(api::project_ready))
+        .route("/v1/projects/:project_id/blocked", get(api::project_blocked))
+        .route("/v1/projects/:project_id/critical-path", get(api::project_critical_path))
+        .route("/v1/projects/:project_id/graph.mmd", get(api::project_graph))
         .layer(cors)
         .with_state(app_state);
 
@@ -208,6 +322,1776 @@ async fn vos_dispatch(
         }
     }
 
+    // Handle guardian.scan directly
+    if msg.target == "opencode_pm" && msg.op == "guardian.scan" {
+        use crate::services::guardian::{GuardianScanReq, GuardianService};
+        match serde_json::from_value::<GuardianScanReq>(msg.payload.clone()) {
+            Ok(req) => match GuardianService::scan(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "guardian.findings".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"guardian_scan_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle guardian.allowlist.get directly
+    if msg.target == "opencode_pm" && msg.op == "guardian.allowlist.get" {
+        use crate::services::guardian::{AllowlistGetReq, GuardianService};
+        match serde_json::from_value::<AllowlistGetReq>(msg.payload.clone()) {
+            Ok(req) => match GuardianService::allowlist_get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "guardian.allowlist".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"guardian_allowlist_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle guardian.allowlist.set directly
+    if msg.target == "opencode_pm" && msg.op == "guardian.allowlist.set" {
+        use crate::services::guardian::{AllowlistSetReq, GuardianService};
+        match serde_json::from_value::<AllowlistSetReq>(msg.payload.clone()) {
+            Ok(req) => match GuardianService::allowlist_set(req).await {
+                Ok(_) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "guardian.ok".into(),
+                        payload: serde_json::json!({}),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"guardian_allowlist_set_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.get directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.get" {
+        use crate::services::specbundle::{SpecbundleGetReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleGetReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.value".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.list directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.list" {
+        use crate::services::specbundle::{SpecbundleListReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleListReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::list(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.items".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_list_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.search directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.search" {
+        use crate::services::specbundle::{SpecbundleSearchReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleSearchReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::search(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.hits".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_search_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.delete directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.delete" {
+        use crate::services::specbundle::{SpecbundleDeleteReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleDeleteReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::delete(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.deleted".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_delete_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.revise directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.revise" {
+        use crate::services::specbundle::{SpecbundleReviseReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleReviseReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::revise(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.revised".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_revise_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.history directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.history" {
+        use crate::services::specbundle::{SpecbundleHistoryReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleHistoryReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::history(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.revisions".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_history_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.diff directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.diff" {
+        use crate::services::specbundle::{SpecbundleDiffReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleDiffReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::diff(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.diff".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_diff_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.verify directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.verify" {
+        use crate::services::specbundle::{SpecbundleVerifyReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleVerifyReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::verify(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.verified".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_verify_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.export directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.export" {
+        use crate::services::specbundle::{SpecbundleExportReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleExportReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::export(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.exported".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_export_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.import directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.import" {
+        use crate::services::specbundle::{SpecbundleImportReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleImportReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::import(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.imported".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_import_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle specbundle.render directly
+    if msg.target == "opencode_pm" && msg.op == "specbundle.render" {
+        use crate::services::specbundle::{SpecbundleRenderReq, SpecbundleService};
+        match serde_json::from_value::<SpecbundleRenderReq>(msg.payload.clone()) {
+            Ok(req) => match SpecbundleService::render(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "specbundle.rendered".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"specbundle_render_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle ingest.status directly
+    if msg.target == "opencode_pm" && msg.op == "ingest.status" {
+        use crate::services::ingest::{IngestStatusReq, IngestService};
+        match serde_json::from_value::<IngestStatusReq>(msg.payload.clone()) {
+            Ok(req) => match IngestService::status(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "ingest.status.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"ingest_status_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle audit.verify directly
+    if msg.target == "opencode_pm" && msg.op == "audit.verify" {
+        use crate::services::audit::{AuditVerifyReq, AuditService};
+        match serde_json::from_value::<AuditVerifyReq>(msg.payload.clone()) {
+            Ok(req) => match AuditService::verify(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "audit.verified".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"audit_verify_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle audit.query directly
+    if msg.target == "opencode_pm" && msg.op == "audit.query" {
+        use crate::services::audit::{AuditQueryReq, AuditService};
+        match serde_json::from_value::<AuditQueryReq>(msg.payload.clone()) {
+            Ok(req) => match AuditService::query(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "audit.query.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"audit_query_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle embed directly
+    if msg.target == "model_manager" && msg.op == "embed" {
+        use crate::services::model_manager::{EmbedReq, ModelManagerService};
+        match serde_json::from_value::<EmbedReq>(msg.payload.clone()) {
+            Ok(req) => match ModelManagerService::embed(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "model_manager".into(),
+                        op: "embed.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"embed_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle context.pack.build directly
+    if msg.target == "context_service" && msg.op == "context.pack.build" {
+        use crate::services::context_pack::{ContextPackBuildReq, ContextPackService};
+        match serde_json::from_value::<ContextPackBuildReq>(msg.payload.clone()) {
+            Ok(req) => match ContextPackService::build(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "context_service".into(),
+                        op: "context.pack.build.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"context_pack_build_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle context.pack.get directly
+    if msg.target == "context_service" && msg.op == "context.pack.get" {
+        use crate::services::context_pack::{ContextPackGetReq, ContextPackService};
+        match serde_json::from_value::<ContextPackGetReq>(msg.payload.clone()) {
+            Ok(req) => match ContextPackService::get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "context_service".into(),
+                        op: "context.pack.get.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"context_pack_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle ko.resolve directly
+    if msg.target == "opencode_pm" && msg.op == "ko.resolve" {
+        use crate::services::ko_resolve::{KoResolveReq, KoService};
+        match serde_json::from_value::<KoResolveReq>(msg.payload.clone()) {
+            Ok(req) => match KoService::resolve(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "ko.resolved".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"ko_resolve_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle graph.link directly
+    if msg.target == "opencode_pm" && msg.op == "graph.link" {
+        use crate::services::graph::{GraphLinkReq, GraphService};
+        match serde_json::from_value::<GraphLinkReq>(msg.payload.clone()) {
+            Ok(req) => match GraphService::link(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "graph.linked".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"graph_link_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle graph.ancestors directly
+    if msg.target == "opencode_pm" && msg.op == "graph.ancestors" {
+        use crate::services::graph::{GraphWalkReq, GraphService};
+        match serde_json::from_value::<GraphWalkReq>(msg.payload.clone()) {
+            Ok(req) => match GraphService::ancestors(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "graph.ancestors.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"graph_walk_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle graph.descendants directly
+    if msg.target == "opencode_pm" && msg.op == "graph.descendants" {
+        use crate::services::graph::{GraphWalkReq, GraphService};
+        match serde_json::from_value::<GraphWalkReq>(msg.payload.clone()) {
+            Ok(req) => match GraphService::descendants(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "graph.descendants.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"graph_walk_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle graph.export directly
+    if msg.target == "opencode_pm" && msg.op == "graph.export" {
+        use crate::services::graph::{GraphExportReq, GraphService};
+        match serde_json::from_value::<GraphExportReq>(msg.payload.clone()) {
+            Ok(req) => match GraphService::export(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "graph.exported".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"graph_export_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle runlog.start directly
+    if msg.target == "opencode_pm" && msg.op == "runlog.start" {
+        use crate::services::runlog::{RunlogStartReq, RunlogService};
+        match serde_json::from_value::<RunlogStartReq>(msg.payload.clone()) {
+            Ok(req) => match RunlogService::start(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "runlog.started".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"runlog_start_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle runlog.append directly
+    if msg.target == "opencode_pm" && msg.op == "runlog.append" {
+        use crate::services::runlog::{RunlogAppendReq, RunlogService};
+        match serde_json::from_value::<RunlogAppendReq>(msg.payload.clone()) {
+            Ok(req) => match RunlogService::append(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "runlog.appended".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"runlog_append_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle runlog.finalize directly
+    if msg.target == "opencode_pm" && msg.op == "runlog.finalize" {
+        use crate::services::runlog::{RunlogFinalizeReq, RunlogService};
+        match serde_json::from_value::<RunlogFinalizeReq>(msg.payload.clone()) {
+            Ok(req) => match RunlogService::finalize(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "runlog.finalized".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"runlog_finalize_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle runlog.tail directly
+    if msg.target == "opencode_pm" && msg.op == "runlog.tail" {
+        use crate::services::runlog::{RunlogTailReq, RunlogService};
+        match serde_json::from_value::<RunlogTailReq>(msg.payload.clone()) {
+            Ok(req) => match RunlogService::tail(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "runlog.tailed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"runlog_tail_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle project.create directly
+    if msg.target == "opencode_pm" && msg.op == "project.create" {
+        use crate::services::ledger::{ProjectCreateReq, LedgerService};
+        match serde_json::from_value::<ProjectCreateReq>(msg.payload.clone()) {
+            Ok(req) => match LedgerService::project_create(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "project.created".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"project_create_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle project.get directly
+    if msg.target == "opencode_pm" && msg.op == "project.get" {
+        use crate::services::ledger::{ProjectGetReq, LedgerService};
+        match serde_json::from_value::<ProjectGetReq>(msg.payload.clone()) {
+            Ok(req) => match LedgerService::project_get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "project.got".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"project_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle queue.create directly
+    if msg.target == "opencode_pm" && msg.op == "queue.create" {
+        use crate::services::ledger::{QueueCreateReq, LedgerService};
+        match serde_json::from_value::<QueueCreateReq>(msg.payload.clone()) {
+            Ok(req) => match LedgerService::queue_create(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "queue.created".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"queue_create_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.create directly
+    if msg.target == "opencode_pm" && msg.op == "task.create" {
+        use crate::services::ledger::{TaskCreateReq, LedgerService};
+        match serde_json::from_value::<TaskCreateReq>(msg.payload.clone()) {
+            Ok(req) => match LedgerService::task_create(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.created".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_create_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle snapshot.create directly
+    if msg.target == "opencode_pm" && msg.op == "snapshot.create" {
+        use crate::services::snapshot::{SnapshotCreateReq, SnapshotService};
+        match serde_json::from_value::<SnapshotCreateReq>(msg.payload.clone()) {
+            Ok(req) => match SnapshotService::create(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "snapshot.created".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"snapshot_create_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle snapshot.list directly
+    if msg.target == "opencode_pm" && msg.op == "snapshot.list" {
+        use crate::services::snapshot::{SnapshotListReq, SnapshotService};
+        match serde_json::from_value::<SnapshotListReq>(msg.payload.clone()) {
+            Ok(req) => match SnapshotService::list(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "snapshot.listed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"snapshot_list_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle snapshot.get directly
+    if msg.target == "opencode_pm" && msg.op == "snapshot.get" {
+        use crate::services::snapshot::{SnapshotGetReq, SnapshotService};
+        match serde_json::from_value::<SnapshotGetReq>(msg.payload.clone()) {
+            Ok(req) => match SnapshotService::get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "snapshot.got".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"snapshot_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle snapshot.restore directly
+    if msg.target == "opencode_pm" && msg.op == "snapshot.restore" {
+        use crate::services::snapshot::{SnapshotRestoreReq, SnapshotService};
+        match serde_json::from_value::<SnapshotRestoreReq>(msg.payload.clone()) {
+            Ok(req) => match SnapshotService::restore(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "snapshot.restored".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"snapshot_restore_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.depend directly
+    if msg.target == "opencode_pm" && msg.op == "task.depend" {
+        use crate::services::scheduler::{TaskDependReq, SchedulerService};
+        match serde_json::from_value::<TaskDependReq>(msg.payload.clone()) {
+            Ok(req) => match SchedulerService::depend(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.depended".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_depend_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.status directly
+    if msg.target == "opencode_pm" && msg.op == "task.status" {
+        use crate::services::scheduler::{TaskStatusReq, SchedulerService};
+        match serde_json::from_value::<TaskStatusReq>(msg.payload.clone()) {
+            Ok(req) => match SchedulerService::status(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.status.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_status_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.ready directly
+    if msg.target == "opencode_pm" && msg.op == "task.ready" {
+        use crate::services::scheduler::{TaskReadyReq, SchedulerService};
+        match serde_json::from_value::<TaskReadyReq>(msg.payload.clone()) {
+            Ok(req) => match SchedulerService::ready(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.ready.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_ready_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle gate.create directly
+    if msg.target == "opencode_pm" && msg.op == "gate.create" {
+        use crate::services::gate::{GateCreateReq, GateService};
+        match serde_json::from_value::<GateCreateReq>(msg.payload.clone()) {
+            Ok(req) => match GateService::create(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "gate.created".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"gate_create_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle gate.get directly
+    if msg.target == "opencode_pm" && msg.op == "gate.get" {
+        use crate::services::gate::{GateGetReq, GateService};
+        match serde_json::from_value::<GateGetReq>(msg.payload.clone()) {
+            Ok(req) => match GateService::get(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "gate.got".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"gate_get_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle gate.list directly
+    if msg.target == "opencode_pm" && msg.op == "gate.list" {
+        use crate::services::gate::{GateListReq, GateService};
+        match serde_json::from_value::<GateListReq>(msg.payload.clone()) {
+            Ok(req) => match GateService::list(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "gate.listed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"gate_list_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle gate.validate directly
+    if msg.target == "opencode_pm" && msg.op == "gate.validate" {
+        use crate::services::gate::{GateValidateReq, GateService};
+        match serde_json::from_value::<GateValidateReq>(msg.payload.clone()) {
+            Ok(req) => match GateService::revalidate(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "gate.validated".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"gate_validate_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle gate.decide directly
+    if msg.target == "opencode_pm" && msg.op == "gate.decide" {
+        use crate::services::gate::{GateDecideReq, GateService};
+        match serde_json::from_value::<GateDecideReq>(msg.payload.clone()) {
+            Ok(req) => match GateService::decide(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "gate.decided".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"gate_decide_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle plan.import directly
+    if msg.target == "opencode_pm" && msg.op == "plan.import" {
+        use crate::services::plan::{PlanImportReq, PlanService};
+        match serde_json::from_value::<PlanImportReq>(msg.payload.clone()) {
+            Ok(req) => match PlanService::import(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "plan.imported".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"plan_import_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle plan.export directly
+    if msg.target == "opencode_pm" && msg.op == "plan.export" {
+        use crate::services::plan::{PlanExportReq, PlanService};
+        match serde_json::from_value::<PlanExportReq>(msg.payload.clone()) {
+            Ok(req) => match PlanService::export(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "plan.exported".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"plan_export_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.blocked directly
+    if msg.target == "opencode_pm" && msg.op == "task.blocked" {
+        use crate::services::task_graph::{TaskBlockedReq, TaskGraphService};
+        match serde_json::from_value::<TaskBlockedReq>(msg.payload.clone()) {
+            Ok(req) => match TaskGraphService::blocked(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.blocked.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_blocked_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.critical_path directly
+    if msg.target == "opencode_pm" && msg.op == "task.critical_path" {
+        use crate::services::task_graph::{CriticalPathReq, TaskGraphService};
+        match serde_json::from_value::<CriticalPathReq>(msg.payload.clone()) {
+            Ok(req) => match TaskGraphService::critical_path(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.critical_path.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_critical_path_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle task.graph directly
+    if msg.target == "opencode_pm" && msg.op == "task.graph" {
+        use crate::services::task_graph::{TaskGraphReq, TaskGraphService};
+        match serde_json::from_value::<TaskGraphReq>(msg.payload.clone()) {
+            Ok(req) => match TaskGraphService::graph(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "task.graph.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"task_graph_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle queue.claim directly
+    if msg.target == "opencode_pm" && msg.op == "queue.claim" {
+        use crate::services::lease::{LeaseService, QueueClaimReq};
+        match serde_json::from_value::<QueueClaimReq>(msg.payload.clone()) {
+            Ok(req) => match LeaseService::claim(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "queue.claimed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"queue_claim_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle lease.heartbeat directly
+    if msg.target == "opencode_pm" && msg.op == "lease.heartbeat" {
+        use crate::services::lease::{LeaseHeartbeatReq, LeaseService};
+        match serde_json::from_value::<LeaseHeartbeatReq>(msg.payload.clone()) {
+            Ok(req) => match LeaseService::heartbeat(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "lease.extended".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"lease_heartbeat_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle lease.complete directly
+    if msg.target == "opencode_pm" && msg.op == "lease.complete" {
+        use crate::services::lease::{LeaseCompleteReq, LeaseService};
+        match serde_json::from_value::<LeaseCompleteReq>(msg.payload.clone()) {
+            Ok(req) => match LeaseService::complete(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "lease.completed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"lease_complete_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle lease.fail directly
+    if msg.target == "opencode_pm" && msg.op == "lease.fail" {
+        use crate::services::lease::{LeaseFailReq, LeaseService};
+        match serde_json::from_value::<LeaseFailReq>(msg.payload.clone()) {
+            Ok(req) => match LeaseService::fail(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "lease.failed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"lease_fail_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle policy.list directly
+    if msg.target == "opencode_pm" && msg.op == "policy.list" {
+        use crate::services::policy::{PolicyListReq, PolicyService};
+        match serde_json::from_value::<PolicyListReq>(msg.payload.clone()) {
+            Ok(req) => match PolicyService::list(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "policy.listed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"policy_list_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle policy.pending directly
+    if msg.target == "opencode_pm" && msg.op == "policy.pending" {
+        use crate::services::policy::{PolicyPendingReq, PolicyService};
+        match serde_json::from_value::<PolicyPendingReq>(msg.payload.clone()) {
+            Ok(req) => match PolicyService::pending(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "policy.pending.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"policy_pending_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle policy.review directly
+    if msg.target == "opencode_pm" && msg.op == "policy.review" {
+        use crate::services::policy::{PolicyReviewReq, PolicyService};
+        match serde_json::from_value::<PolicyReviewReq>(msg.payload.clone()) {
+            Ok(req) => match PolicyService::review(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "policy.reviewed".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"policy_review_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle originality.check directly
+    if msg.target == "opencode_pm" && msg.op == "originality.check" {
+        use crate::services::originality::{OriginalityCheckReq, OriginalityService};
+        match serde_json::from_value::<OriginalityCheckReq>(msg.payload.clone()) {
+            Ok(req) => match OriginalityService::check(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "originality.check.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"originality_check_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
+    // Handle license.check directly
+    if msg.target == "opencode_pm" && msg.op == "license.check" {
+        use crate::services::license::{LicenseCheckReq, LicenseService};
+        match serde_json::from_value::<LicenseCheckReq>(msg.payload.clone()) {
+            Ok(req) => match LicenseService::check(req).await {
+                Ok(resp) => {
+                    return Json(VosMessage {
+                        target: "opencode_pm".into(),
+                        op: "license.check.ok".into(),
+                        payload: serde_json::to_value(resp).unwrap(),
+                    });
+                }
+                Err(e) => {
+                    return Json(VosMessage {
+                        target: "error".into(),
+                        op: "error".into(),
+                        payload: serde_json::json!({"err":"license_check_failed","msg":e.to_string()}),
+                    });
+                }
+            },
+            Err(e) => {
+                return Json(VosMessage {
+                    target: "error".into(),
+                    op: "error".into(),
+                    payload: serde_json::json!({"err":"bad_request","msg":e.to_string()}),
+                });
+            }
+        }
+    }
+
     let resp = st.reg.dispatch(msg).await.unwrap_or_else(|e| VosMessage {
         target: "error".into(),
         op: "error".into(),
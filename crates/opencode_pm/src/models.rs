@@ -16,6 +16,40 @@ pub struct Queue {
     pub name: String,
 }
 
+/// One entry of a task's dependency condition (TES-2025 `Predecessors`). Ids name tasks,
+/// which must be done, or gates, which must be approved.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Predecessor {
+    Task(Uuid),
+    /// At least one of these must be met (`Either: X or Y`).
+    Either { either: Vec<Uuid> },
+}
+
+impl Predecessor {
+    pub fn ids(&self) -> &[Uuid] {
+        match self {
+            Predecessor::Task(id) => std::slice::from_ref(id),
+            Predecessor::Either { either } => either,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Waiting on predecessors.
+    #[default]
+    Pending,
+    /// Predecessors satisfied; can be picked up.
+    Ready,
+    Running,
+    Done,
+    Failed,
+    /// Branch not taken (the other side of an either/or was chosen).
+    Skipped,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Task {
     pub id: Uuid,
@@ -23,6 +57,120 @@ pub struct Task {
     pub kind: String,
     pub ko_refs: Vec<String>,
     pub created_at: OffsetDateTime,
+    /// Plan identifier, e.g. `TASK-A1`; unique within a project.
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// All entries must be satisfied before the task becomes ready.
+    #[serde(default)]
+    pub predecessors: Vec<Predecessor>,
+    #[serde(default)]
+    pub status: TaskStatus,
+    /// Deliverables that are not KOs (those are in `ko_refs`), e.g. `CI/CD pipeline`.
+    #[serde(default)]
+    pub deliverables: Vec<String>,
+    /// TES `Validation`: how the work is checked.
+    #[serde(default)]
+    pub validation: Option<String>,
+    /// Claimed before lower priorities; equal priorities go oldest-ready first.
+    #[serde(default)]
+    pub priority: i32,
+    /// When the task last became ready. Kept when a lease expires, so the task does not
+    /// lose its place in the queue.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub ready_since: Option<OffsetDateTime>,
+    /// The agent working on the task while it runs (see `services::lease`).
+    #[serde(default)]
+    pub lease: Option<TaskLease>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TaskLease {
+    pub lease_id: Uuid,
+    pub agent: String,
+    /// Attempt opened by the claim; its runlog takes the agent's output.
+    pub attempt: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub claimed_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GateStatus {
+    /// Trigger tasks not all done yet.
+    #[default]
+    Waiting,
+    /// Automated validations running.
+    Validating,
+    ValidationFailed,
+    /// Validations passed; blocked on a human decision.
+    AwaitingApproval,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GateValidation {
+    pub name: String,
+    /// None when the check is not automated and is left to the reviewer.
+    pub passed: Option<bool>,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GateDecision {
+    pub approved: bool,
+    pub actor: String,
+    pub reason: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+}
+
+/// TES gate `Actions` item, e.g. `IP Review: Check for novel architectural patterns`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GateAction {
+    pub name: String,
+    pub text: String,
+}
+
+/// TES-2025 quality gate: a HITL checkpoint between groups of tasks.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Gate {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Plan identifier, e.g. `GATE-A-READY`; unique within a project.
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Tasks whose completion triggers the gate.
+    pub trigger: Vec<Uuid>,
+    /// Automated validation requirements.
+    #[serde(default)]
+    pub automated: Vec<String>,
+    /// What the reviewer is asked to assess (HITL review, IP review, FTO analysis, ...).
+    #[serde(default)]
+    pub human: Vec<String>,
+    /// TES `Actions`: what is produced for the review (POE report, IP review, ...).
+    #[serde(default)]
+    pub actions: Vec<GateAction>,
+    /// TES `Post-Gate` actions, announced when the gate is approved.
+    #[serde(default)]
+    pub post_gate: Vec<String>,
+    #[serde(default)]
+    pub status: GateStatus,
+    #[serde(default)]
+    pub validations: Vec<GateValidation>,
+    /// POE report generated for the reviewer.
+    #[serde(default)]
+    pub report_ko: Option<String>,
+    #[serde(default)]
+    pub decision: Option<GateDecision>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,6 +182,18 @@ pub struct Attempt {
     pub finished_at: Option<OffsetDateTime>,
     pub runlog_ko: Option<String>,
     pub confidence: Option<f32>,
+    #[serde(default)]
+    pub outcome: Option<AttemptOutcome>,
+}
+
+/// How a leased attempt ended.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    Completed,
+    Failed,
+    /// The agent stopped heartbeating and the task went back to the queue.
+    Expired,
 }
 
 #[cfg(test)]
@@ -76,6 +236,15 @@ mod tests {
             kind: "analysis".to_string(),
             ko_refs: vec!["ko://test/1".to_string()],
             created_at: datetime!(2025-09-18 12:00:00 UTC),
+            key: Some("TASK-A2".to_string()),
+            title: None,
+            predecessors: vec![Predecessor::Task(Uuid::nil()), Predecessor::Either { either: vec![Uuid::nil()] }],
+            status: TaskStatus::Pending,
+            deliverables: vec!["CI/CD pipeline".to_string()],
+            validation: None,
+            priority: 0,
+            ready_since: None,
+            lease: None,
         };
         let json = serde_json::to_string(&task).unwrap();
         let deserialized: Task = serde_json::from_str(&json).unwrap();
@@ -83,6 +252,8 @@ mod tests {
         assert_eq!(task.queue_id, deserialized.queue_id);
         assert_eq!(task.kind, deserialized.kind);
         assert_eq!(task.ko_refs, deserialized.ko_refs);
+        assert_eq!(task.predecessors, deserialized.predecessors);
+        assert!(json.contains(r#""predecessors":["00000000-0000-0000-0000-000000000000",{"either":["#));
     }
 
     #[test]
@@ -93,8 +264,9 @@ mod tests {
             n: 1,
             started_at: datetime!(2025-09-18 12:00:00 UTC),
             finished_at: Some(datetime!(2025-09-18 12:05:00 UTC)),
-            runlog_ko: Some("ko://runlog/1".to_string()),
+            runlog_ko: Some("ko:runlog/1".to_string()),
             confidence: Some(0.85),
+            outcome: Some(AttemptOutcome::Completed),
         };
         let json = serde_json::to_string(&attempt).unwrap();
         let deserialized: Attempt = serde_json::from_str(&json).unwrap();
@@ -116,6 +288,7 @@ mod tests {
             finished_at: None,
             runlog_ko: None,
             confidence: None,
+            outcome: None,
         };
         assert!(attempt.finished_at.is_none());
         assert!(attempt.runlog_ko.is_none());
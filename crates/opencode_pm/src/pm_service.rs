@@ -20,7 +20,7 @@ impl LaioService for PmService {
         match message.op.as_str() {
             "specbundle.create" => {
                 // Stub: generate a KO ref for the SpecBundle
-                let ko_ref = format!("ko://specbundle/{}", Uuid::new_v4());
+                let ko_ref = format!("ko:specbundle/{}", Uuid::new_v4());
                 Ok(VosMessage {
                     target: "opencode_pm".into(),
                     op: "specbundle.created".into(),
@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Component, Path};
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::services::storage::genesis_home;
+use crate::services::util::sha256_hex;
+
+pub const ARCHIVE_FORMAT: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+/// Upper bound on the decompressed size of an archive we are willing to read.
+const MAX_UNPACKED_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveFile {
+    pub path: String,
+    pub sha256: String,
+    pub bytes: usize,
+}
+
+/// `manifest.json` at the root of every archive. `signature` is a hex HMAC-SHA256 over the
+/// manifest serialized without the signature field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// Caller-defined description of the payload (e.g. the exported index entry).
+    pub meta: serde_json::Value,
+    pub files: Vec<ArchiveFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    Valid,
+    /// Signed, but not with our key (or the manifest was altered after signing). Signatures
+    /// are HMACs, so they are tamper-evident on this host only: an archive from another
+    /// machine reports `Invalid` unless both share `SPECBUNDLE_SIGNING_KEY`.
+    Invalid,
+    Unsigned,
+}
+
+pub struct Unpacked {
+    pub manifest: ArchiveManifest,
+    pub files: Vec<(String, Vec<u8>)>,
+    pub signature: SignatureStatus,
+}
+
+fn sign(manifest: &ArchiveManifest, key: &[u8]) -> Result<String> {
+    let unsigned = ArchiveManifest { signature: None, ..manifest.clone() };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow!(e.to_string()))?;
+    mac.update(&serde_json::to_vec(&unsigned)?);
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Key used to sign and verify archives: `SPECBUNDLE_SIGNING_KEY`, or a per-install key
+/// generated under `<genesis home>/keys/` on first use. Machines that exchange archives
+/// should share the env key.
+pub async fn signing_key() -> Result<Vec<u8>> {
+    if let Ok(k) = std::env::var("SPECBUNDLE_SIGNING_KEY")
+        && !k.trim().is_empty()
+    {
+        return Ok(k.trim().as_bytes().to_vec());
+    }
+    let path = genesis_home()?.join("keys").join("archive-signing.key");
+    match fs::read_to_string(&path).await {
+        Ok(k) => return Ok(k.trim().as_bytes().to_vec()),
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        Err(_) => {}
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let key = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    fs::write(&path, &key).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    Ok(key.into_bytes())
+}
+
+/// Only plain relative paths may appear in an archive.
+fn check_path(path: &str) -> Result<()> {
+    let p = Path::new(path);
+    if path.is_empty() || path == MANIFEST || !p.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(anyhow!("invalid archive path '{path}'"));
+    }
+    Ok(())
+}
+
+/// Build a signed `.tar.zst`: `manifest.json` first, then `files` in order.
+pub fn pack(files: &[(String, Vec<u8>)], meta: serde_json::Value, key: &[u8]) -> Result<Vec<u8>> {
+    let mut manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT,
+        created_at: OffsetDateTime::now_utc(),
+        meta,
+        files: Vec::with_capacity(files.len()),
+        signature: None,
+    };
+    for (path, data) in files {
+        check_path(path)?;
+        manifest.files.push(ArchiveFile { path: path.clone(), sha256: sha256_hex(data), bytes: data.len() });
+    }
+    manifest.signature = Some(sign(&manifest, key)?);
+
+    let mut tar = tar::Builder::new(Vec::new());
+    let mut append = |path: &str, data: &[u8]| -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.created_at.unix_timestamp().max(0) as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, path, data)?;
+        Ok(())
+    };
+    append(MANIFEST, &serde_json::to_vec_pretty(&manifest)?)?;
+    for (path, data) in files {
+        append(path, data)?;
+    }
+    let tar = tar.into_inner()?;
+    Ok(zstd::encode_all(tar.as_slice(), 3)?)
+}
+
+/// Read an archive and check every file against the manifest. Hash mismatches, missing or
+/// unlisted files are errors; the signature status is reported for the caller to enforce.
+pub fn unpack(data: &[u8], key: &[u8]) -> Result<Unpacked> {
+    let decoder = zstd::stream::Decoder::new(data)?;
+    let mut archive = tar::Archive::new(decoder.take(MAX_UNPACKED_BYTES));
+    let mut entries: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    let mut manifest: Option<ArchiveManifest> = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            return Err(anyhow!("archive contains a non-file entry"));
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut buf = Vec::new();
+        entry.read_to_end(&mut buf)?;
+        if path == MANIFEST {
+            manifest = Some(serde_json::from_slice(&buf).map_err(|e| anyhow!("invalid manifest.json: {e}"))?);
+            continue;
+        }
+        check_path(&path)?;
+        if entries.insert(path.clone(), buf).is_some() {
+            return Err(anyhow!("duplicate archive entry '{path}'"));
+        }
+    }
+    let manifest = manifest.ok_or_else(|| anyhow!("archive has no manifest.json"))?;
+    if manifest.format != ARCHIVE_FORMAT {
+        return Err(anyhow!("unsupported archive format {}", manifest.format));
+    }
+
+    let mut files = Vec::with_capacity(manifest.files.len());
+    for f in &manifest.files {
+        let data = entries.remove(&f.path).ok_or_else(|| anyhow!("archive is missing '{}'", f.path))?;
+        if sha256_hex(&data) != f.sha256 {
+            return Err(anyhow!("hash mismatch for '{}'", f.path));
+        }
+        files.push((f.path.clone(), data));
+    }
+    if let Some(extra) = entries.keys().next() {
+        return Err(anyhow!("'{extra}' is not listed in the manifest"));
+    }
+
+    let signature = match &manifest.signature {
+        None => SignatureStatus::Unsigned,
+        Some(sig) if *sig == sign(&manifest, key)? => SignatureStatus::Valid,
+        Some(_) => SignatureStatus::Invalid,
+    };
+    Ok(Unpacked { manifest, files, signature })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<(String, Vec<u8>)> {
+        vec![("bundle/bundle.json".into(), b"{}".to_vec()), ("attachments/0002/a.txt".into(), b"hi".to_vec())]
+    }
+
+    #[test]
+    fn test_pack_unpack_roundtrip_and_signature() {
+        let data = pack(&files(), serde_json::json!({ "ko_id": "x" }), b"k1").unwrap();
+        let out = unpack(&data, b"k1").unwrap();
+        assert_eq!(out.signature, SignatureStatus::Valid);
+        assert_eq!(out.files, files());
+        assert_eq!(out.manifest.meta["ko_id"], "x");
+        assert_eq!(unpack(&data, b"other").unwrap().signature, SignatureStatus::Invalid);
+    }
+
+    #[test]
+    fn test_rejects_unsafe_paths_and_tampering() {
+        assert!(pack(&[("../etc/passwd".into(), vec![])], serde_json::Value::Null, b"k").is_err());
+        assert!(pack(&[("/abs".into(), vec![])], serde_json::Value::Null, b"k").is_err());
+
+        // Swap a file's content while keeping the original manifest
+        let raw = zstd::decode_all(pack(&files(), serde_json::Value::Null, b"k").unwrap().as_slice()).unwrap();
+        let mut out = tar::Builder::new(Vec::new());
+        for entry in tar::Archive::new(raw.as_slice()).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().into_owned();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            if path == Path::new("bundle/bundle.json") {
+                data = b"{\"tampered\":1}".to_vec();
+            }
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            out.append_data(&mut header, path, data.as_slice()).unwrap();
+        }
+        let repacked = zstd::encode_all(out.into_inner().unwrap().as_slice(), 3).unwrap();
+        let err = unpack(&repacked, b"k").err().unwrap();
+        assert!(err.to_string().contains("hash mismatch for 'bundle/bundle.json'"));
+    }
+}
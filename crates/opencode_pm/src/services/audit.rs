@@ -0,0 +1,400 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::ko_resolver::KoUri;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::services::storage::genesis_home;
+use crate::services::util::sha256_hex;
+
+/// `prev_hash` of the first record.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Actor recorded for work the service does on its own (workers, startup checks).
+pub const SYSTEM_ACTOR: &str = "system";
+/// Actor recorded when a request does not name one.
+pub const ANONYMOUS_ACTOR: &str = "anonymous";
+
+static LOG: OnceLock<Arc<AuditLog>> = OnceLock::new();
+
+/// `(actor, event, ko_id, detail)` that `record_committed` could not append yet.
+type Deferred = (String, String, Option<String>, serde_json::Value);
+static DEFERRED: parking_lot::Mutex<Vec<Deferred>> = parking_lot::Mutex::new(Vec::new());
+
+/// Install the log opened at startup (see `main`).
+pub fn init_log(log: Arc<AuditLog>) {
+    if LOG.set(log).is_err() {
+        tracing::warn!("audit log already initialised; keeping the first one");
+    }
+}
+
+/// `<genesis home>/audit/audit.jsonl`
+pub fn default_path() -> Result<PathBuf> {
+    Ok(genesis_home()?.join("audit").join("audit.jsonl"))
+}
+
+pub async fn log() -> Result<Arc<AuditLog>> {
+    if let Some(l) = LOG.get() {
+        return Ok(l.clone());
+    }
+    let l = Arc::new(AuditLog::open(default_path()?).await?);
+    Ok(LOG.get_or_init(|| l).clone())
+}
+
+/// Append one record to the global log, after any deferred ones.
+pub async fn record(actor: &str, event: &str, ko_id: Option<&str>, detail: serde_json::Value) -> Result<AuditRecord> {
+    let log = log().await?;
+    let mut deferred = std::mem::take(&mut *DEFERRED.lock()).into_iter();
+    while let Some(d) = deferred.next() {
+        if let Err(e) = log.append(&d.0, &d.1, d.2.as_deref(), d.3.clone()).await {
+            DEFERRED.lock().splice(0..0, std::iter::once(d).chain(deferred));
+            return Err(e);
+        }
+    }
+    log.append(actor, event, ko_id, detail).await
+}
+
+/// `record` for work that is already committed: failing the caller then would report an
+/// error for something that exists, and its retry would repeat it. A failure is logged and
+/// the record, stamped with `deferred_at`, is appended ahead of the next one instead.
+pub async fn record_committed(actor: &str, event: &str, ko_id: Option<&str>, mut detail: serde_json::Value) {
+    let at = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+    if let Err(e) = record(actor, event, ko_id, detail.clone()).await {
+        tracing::warn!(event, error = %e, "audit record deferred");
+        if let Some(m) = detail.as_object_mut() {
+            m.insert("deferred_at".into(), at.into());
+        }
+        DEFERRED.lock().push((actor.to_string(), event.to_string(), ko_id.map(str::to_string), detail));
+    }
+}
+
+/// One line of `audit.jsonl`. `hash` is the SHA-256 of the record serialized with an empty
+/// `hash`, and `prev_hash` is the previous record's `hash`, so any edit, insertion or removal
+/// breaks the chain from that point on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub at: OffsetDateTime,
+    pub actor: String,
+    /// e.g. `SPECBUNDLE.CREATED`, `INGEST.FAILED`, `SECRET.READ`
+    pub event: String,
+    pub ko_id: Option<String>,
+    pub detail: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> Result<String> {
+        let unhashed = AuditRecord { hash: String::new(), ..self.clone() };
+        Ok(sha256_hex(&serde_json::to_vec(&unhashed)?))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditBreak {
+    /// 1-based line number in the log file.
+    pub line: usize,
+    pub seq: Option<u64>,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditVerifyReq {}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditVerifyResp {
+    pub path: String,
+    pub records: usize,
+    pub ok: bool,
+    /// First point where the chain does not hold; later records are not checked.
+    pub broken: Option<AuditBreak>,
+    /// Seqs of `AUDIT.TAIL_TRUNCATED` records: points where `open` cut off a torn final line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_tails: Vec<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuditQueryReq {
+    pub ko_id: Option<String>,
+    pub actor: Option<String>,
+    /// Event name prefix, e.g. `INGEST.`
+    pub event: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub since: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub until: Option<OffsetDateTime>,
+    /// Most recent N matches (default 200).
+    pub limit: Option<usize>,
+}
+
+impl AuditQueryReq {
+    fn matches(&self, r: &AuditRecord) -> bool {
+        // Records written before KO ids were canonical may spell them `ko://`
+        self.ko_id.as_deref().is_none_or(|k| r.ko_id.as_deref().map(KoUri::canonical) == Some(KoUri::canonical(k)))
+            && self.actor.as_ref().is_none_or(|a| *a == r.actor)
+            && self.event.as_ref().is_none_or(|e| r.event.starts_with(e.as_str()))
+            && self.since.is_none_or(|t| r.at >= t)
+            && self.until.is_none_or(|t| r.at < t)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditQueryResp {
+    pub records: Vec<AuditRecord>,
+}
+
+/// Append-only JSONL log. Each append is a single write of one line followed by fsync.
+pub struct AuditLog {
+    path: PathBuf,
+    // (next seq, hash of the last record)
+    head: Mutex<(u64, String)>,
+}
+
+impl AuditLog {
+    /// Open for appending. A final line that does not parse (a write torn by a crash) is moved
+    /// to `<log>.torn-<unix time>` and cut off, and an `AUDIT.TAIL_TRUNCATED` record notes it.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let torn = Self::cut_torn_tail(&path).await?;
+        let head = match Self::read_all(&path).await?.last() {
+            Some(Ok(r)) => (r.seq + 1, r.hash.clone()),
+            Some(Err(e)) => return Err(anyhow!("audit log {} ends with an unreadable record: {e}", path.display())),
+            None => (1, GENESIS_HASH.to_string()),
+        };
+        let log = Self { path, head: Mutex::new(head) };
+        if let Some(detail) = torn {
+            log.append(SYSTEM_ACTOR, "AUDIT.TAIL_TRUNCATED", None, detail).await?;
+        }
+        Ok(log)
+    }
+
+    async fn cut_torn_tail(path: &PathBuf) -> Result<Option<serde_json::Value>> {
+        let raw = match fs::read(path).await {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let body = raw.trim_ascii_end();
+        let start = body.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let last = &body[start..];
+        let tail_ok = last.is_empty() || serde_json::from_slice::<AuditRecord>(last).is_ok();
+        if tail_ok {
+            // A complete record missing only its newline: restore it so the next append starts a line
+            if !raw.is_empty() && !raw.ends_with(b"\n") {
+                let mut f = OpenOptions::new().append(true).open(path).await?;
+                f.write_all(b"\n").await?;
+                f.sync_data().await?;
+            }
+            return Ok(None);
+        }
+        let line = body[..start].iter().filter(|&&b| b == b'\n').count() + 1;
+        let mut saved = path.clone().into_os_string();
+        saved.push(format!(".torn-{}", OffsetDateTime::now_utc().unix_timestamp()));
+        let saved = PathBuf::from(saved);
+        fs::write(&saved, &raw[start..]).await?;
+        let f = OpenOptions::new().write(true).open(path).await?;
+        f.set_len(start as u64).await?;
+        f.sync_all().await?;
+        tracing::warn!("audit log {}: cut torn line {line}, kept as {}", path.display(), saved.display());
+        Ok(Some(serde_json::json!({ "line": line, "bytes": raw.len() - start, "saved_as": saved.display().to_string() })))
+    }
+
+    async fn read_all(path: &PathBuf) -> Result<Vec<Result<AuditRecord, serde_json::Error>>> {
+        let raw = match fs::read_to_string(path).await {
+            Ok(s) => s,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(raw.lines().filter(|l| !l.trim().is_empty()).map(serde_json::from_str).collect())
+    }
+
+    pub async fn append(&self, actor: &str, event: &str, ko_id: Option<&str>, detail: serde_json::Value) -> Result<AuditRecord> {
+        let mut head = self.head.lock().await;
+        let mut rec = AuditRecord {
+            seq: head.0,
+            at: OffsetDateTime::now_utc(),
+            actor: actor.to_string(),
+            event: event.to_string(),
+            ko_id: ko_id.map(str::to_string),
+            detail,
+            prev_hash: head.1.clone(),
+            hash: String::new(),
+        };
+        rec.hash = rec.compute_hash()?;
+        let mut line = serde_json::to_vec(&rec)?;
+        line.push(b'\n');
+
+        let mut f = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        f.write_all(&line).await?;
+        f.sync_data().await?;
+        *head = (rec.seq + 1, rec.hash.clone());
+        Ok(rec)
+    }
+
+    /// Walk the whole chain and report the first broken link.
+    pub async fn verify(&self) -> Result<AuditVerifyResp> {
+        let _head = self.head.lock().await;
+        Self::verify_file(&self.path).await
+    }
+
+    /// `verify` without opening the log, so a log `open` would repair is reported as found.
+    pub async fn verify_file(path: &PathBuf) -> Result<AuditVerifyResp> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let mut expected_seq = 1;
+        let mut broken = None;
+        let mut truncated_tails = Vec::new();
+        let records = Self::read_all(path).await?;
+        for (i, rec) in records.iter().enumerate() {
+            let fail = |seq, reason: String| Some(AuditBreak { line: i + 1, seq, reason });
+            let rec = match rec {
+                Ok(r) => r,
+                Err(e) => {
+                    broken = fail(None, format!("unreadable record: {e}"));
+                    break;
+                }
+            };
+            if rec.seq != expected_seq {
+                broken = fail(Some(rec.seq), format!("expected seq {expected_seq}"));
+            } else if rec.prev_hash != prev_hash {
+                broken = fail(Some(rec.seq), "prev_hash does not match the previous record".into());
+            } else if rec.compute_hash()? != rec.hash {
+                broken = fail(Some(rec.seq), "record content does not match its hash".into());
+            }
+            if broken.is_some() {
+                break;
+            }
+            if rec.event == "AUDIT.TAIL_TRUNCATED" {
+                truncated_tails.push(rec.seq);
+            }
+            prev_hash = rec.hash.clone();
+            expected_seq += 1;
+        }
+        Ok(AuditVerifyResp {
+            path: path.display().to_string(),
+            records: records.len(),
+            ok: broken.is_none(),
+            broken,
+            truncated_tails,
+        })
+    }
+
+    pub async fn query(&self, req: &AuditQueryReq) -> Result<Vec<AuditRecord>> {
+        let mut out: Vec<AuditRecord> = Self::read_all(&self.path)
+            .await?
+            .into_iter()
+            .filter_map(|r| r.ok())
+            .filter(|r| req.matches(r))
+            .collect();
+        let limit = req.limit.unwrap_or(200);
+        if out.len() > limit {
+            out.drain(..out.len() - limit);
+        }
+        Ok(out)
+    }
+}
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Handler for op="audit.verify"
+    pub async fn verify(_req: AuditVerifyReq) -> Result<AuditVerifyResp> {
+        log().await?.verify().await
+    }
+
+    /// Handler for op="audit.query": filter by KO id, actor, event prefix and time range.
+    pub async fn query(req: AuditQueryReq) -> Result<AuditQueryResp> {
+        Ok(AuditQueryResp { records: log().await?.query(&req).await? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sample(dir: &std::path::Path) -> AuditLog {
+        let log = AuditLog::open(dir.join("audit.jsonl")).await.unwrap();
+        log.append("alice", "SPECBUNDLE.CREATED", Some("ko:specbundle/a"), serde_json::json!({ "parts": 2 })).await.unwrap();
+        log.append(SYSTEM_ACTOR, "INGEST.COMPLETED", Some("ko:specbundle/a"), serde_json::json!({})).await.unwrap();
+        log.append("bob", "SECRET.READ", None, serde_json::json!({ "provider": "openai" })).await.unwrap();
+        log
+    }
+
+    #[tokio::test]
+    async fn test_chain_verifies_and_survives_reopen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = sample(tmp.path()).await;
+        assert!(log.verify().await.unwrap().ok);
+
+        let reopened = AuditLog::open(tmp.path().join("audit.jsonl")).await.unwrap();
+        let rec = reopened.append("carol", "SPECBUNDLE.DELETED", None, serde_json::json!({})).await.unwrap();
+        assert_eq!(rec.seq, 4);
+        let report = reopened.verify().await.unwrap();
+        assert_eq!((report.ok, report.records), (true, 4));
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_edits_and_removals() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        let log = sample(tmp.path()).await;
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        std::fs::write(&path, original.replace("\"actor\":\"bob\"", "\"actor\":\"mallory\"")).unwrap();
+        let report = log.verify().await.unwrap();
+        let broken = report.broken.unwrap();
+        assert_eq!((broken.line, broken.seq), (3, Some(3)));
+        assert!(broken.reason.contains("hash"));
+
+        let without_first: Vec<&str> = original.lines().skip(1).collect();
+        std::fs::write(&path, without_first.join("\n")).unwrap();
+        let broken = log.verify().await.unwrap().broken.unwrap();
+        assert_eq!(broken.reason, "expected seq 1");
+    }
+
+    #[tokio::test]
+    async fn test_open_cuts_torn_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("audit.jsonl");
+        drop(sample(tmp.path()).await);
+        let mut f = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut f, b"{\"seq\":4,\"at\":").unwrap();
+
+        let before = AuditLog::verify_file(&path).await.unwrap();
+        assert_eq!(before.broken.unwrap().line, 4);
+
+        let log = AuditLog::open(&path).await.unwrap();
+        let report = log.verify().await.unwrap();
+        assert_eq!((report.ok, report.records, report.truncated_tails), (true, 4, vec![4]));
+        let saved = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .find(|e| e.file_name().to_string_lossy().contains(".torn-"))
+            .unwrap();
+        assert_eq!(std::fs::read(saved.path()).unwrap(), b"{\"seq\":4,\"at\":");
+    }
+
+    #[tokio::test]
+    async fn test_query_filters() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log = sample(tmp.path()).await;
+        let q = |req: AuditQueryReq| {
+            let log = &log;
+            async move { log.query(&req).await.unwrap().into_iter().map(|r| r.seq).collect::<Vec<_>>() }
+        };
+        assert_eq!(q(AuditQueryReq { ko_id: Some("ko:specbundle/a".into()), ..Default::default() }).await, vec![1, 2]);
+        assert_eq!(q(AuditQueryReq { actor: Some("bob".into()), ..Default::default() }).await, vec![3]);
+        assert_eq!(q(AuditQueryReq { event: Some("INGEST.".into()), ..Default::default() }).await, vec![2]);
+        assert_eq!(q(AuditQueryReq { limit: Some(1), ..Default::default() }).await, vec![3]);
+        let later = OffsetDateTime::now_utc() + time::Duration::hours(1);
+        assert!(q(AuditQueryReq { since: Some(later), ..Default::default() }).await.is_empty());
+    }
+}
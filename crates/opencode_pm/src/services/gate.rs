@@ -0,0 +1,547 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::{KoError, KoHandler, KoUri, ResolvedKo};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::events::PolicyDecision;
+use crate::models::{Gate, GateAction, GateDecision, GateStatus, GateValidation, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::context_pack;
+use crate::services::graph;
+use crate::services::ko_resolve;
+use crate::services::ledger::{self, task_ko, Ledger};
+use crate::services::runlog::RunlogService;
+use crate::services::scheduler::{self, StatusChange};
+use crate::services::storage::genesis_home;
+use crate::services::util::sha256_hex;
+
+pub fn gate_ko(gate_id: Uuid) -> String {
+    format!("ko:gate/{gate_id}")
+}
+
+fn report_ko(gate_id: Uuid) -> String {
+    format!("ko:gate/{gate_id}/poe")
+}
+
+/// One entry of `policies/approvers.yml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Approver {
+    pub name: String,
+    /// Hex SHA-256 of the approver's token.
+    pub token_sha256: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApproversFile {
+    #[serde(default)]
+    approvers: Vec<Approver>,
+}
+
+pub fn parse_approvers(yaml: &str) -> Result<Vec<Approver>> {
+    let file: ApproversFile = serde_yaml::from_str(yaml).map_err(|e| anyhow!("policy_invalid: policies/approvers.yml: {e}"))?;
+    Ok(file.approvers)
+}
+
+/// The workspace's gate approvers, read afresh; none when the file is missing.
+pub async fn approvers() -> Result<Vec<Approver>> {
+    let path = context_pack::workspace_root()?.join("policies").join("approvers.yml");
+    match fs::read_to_string(&path).await {
+        Ok(yaml) => parse_approvers(&yaml),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `actor` must be a listed approver presenting their token.
+fn authorize(approvers: &[Approver], actor: &str, token: Option<&str>) -> Result<()> {
+    if approvers.is_empty() {
+        return Err(anyhow!("forbidden: no gate approvers are configured in policies/approvers.yml"));
+    }
+    let approver = approvers.iter().find(|a| a.name == actor).ok_or_else(|| anyhow!("forbidden: {actor} is not a gate approver"))?;
+    let token = token.filter(|t| !t.is_empty()).ok_or_else(|| anyhow!("forbidden: a gate decision needs the approver's token"))?;
+    if !sha256_hex(token.as_bytes()).eq_ignore_ascii_case(approver.token_sha256.trim()) {
+        return Err(anyhow!("forbidden: token does not match approver {actor}"));
+    }
+    Ok(())
+}
+
+fn gate_dir(gate_id: Uuid) -> Result<PathBuf> {
+    Ok(genesis_home()?.join("gates").join(gate_id.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GateChange {
+    pub gate_id: Uuid,
+    pub from: GateStatus,
+    pub to: GateStatus,
+}
+
+/// Move gates along with their trigger tasks: a waiting gate whose triggers are all done
+/// starts validating; a gate not yet approved goes back to waiting when a trigger task is
+/// reopened or held for policy review, dropping validations and any decision made on the
+/// old work. Returns the changes and the gates that now need validating.
+pub fn sync(ledger: &mut Ledger) -> (Vec<GateChange>, Vec<Uuid>) {
+    let done: Vec<bool> = ledger
+        .gates
+        .iter()
+        .map(|g| g.trigger.iter().all(|t| ledger.task(*t).is_some_and(|t| t.status == TaskStatus::Done) && !ledger.held(*t)))
+        .collect();
+    let mut changes = Vec::new();
+    let mut to_validate = Vec::new();
+    for (gate, all_done) in ledger.gates.iter_mut().zip(done) {
+        let from = gate.status;
+        let to = match (from, all_done) {
+            (GateStatus::Waiting, true) => GateStatus::Validating,
+            (GateStatus::Waiting | GateStatus::Approved, _) => from,
+            (_, false) => GateStatus::Waiting,
+            (_, true) => from,
+        };
+        if to == from {
+            continue;
+        }
+        gate.status = to;
+        if to == GateStatus::Waiting {
+            gate.validations.clear();
+            gate.decision = None;
+        } else {
+            to_validate.push(gate.id);
+        }
+        changes.push(GateChange { gate_id: gate.id, from, to });
+    }
+    (changes, to_validate)
+}
+
+async fn record_gate_changes(actor: &str, changes: &[GateChange]) {
+    for c in changes {
+        let detail = serde_json::json!({ "from": c.from, "to": c.to });
+        audit::record_committed(actor, "GATE.STATUS_CHANGED", Some(&gate_ko(c.gate_id)), detail).await;
+    }
+}
+
+/// Called after task status changes in a project: trigger and validate gates accordingly.
+pub async fn after_task_changes(project_id: Uuid, actor: &str) -> Result<Vec<GateChange>> {
+    let store = ledger::store().await?;
+    let (mut changes, to_validate) = store.update(project_id, |l| Ok(sync(l))).await?;
+    record_gate_changes(actor, &changes).await;
+    for gate_id in to_validate {
+        changes.extend(validate(project_id, gate_id, actor).await?);
+    }
+    Ok(changes)
+}
+
+/// Automated checks a gate can name in `automated`:
+/// - `tasks_done`: every trigger task is done
+/// - `runlogs_finalized`: every trigger task's latest attempt has a finalized runlog
+/// - `deliverables_resolve`: every KO a trigger task references resolves
+///
+/// None for anything else; those requirements are listed for the reviewer to confirm.
+async fn run_check(ledger: &Ledger, gate: &Gate, name: &str) -> Option<(bool, String)> {
+    let tasks: Vec<_> = gate.trigger.iter().filter_map(|t| ledger.task(*t)).collect();
+    let label = |id: Uuid| scheduler::label(ledger, id);
+    match name {
+        "tasks_done" => {
+            let open: Vec<String> = tasks.iter().filter(|t| t.status != TaskStatus::Done).map(|t| label(t.id)).collect();
+            Some(match open.is_empty() {
+                true => (true, format!("all {} trigger task(s) done", tasks.len())),
+                false => (false, format!("not done: {}", open.join(", "))),
+            })
+        }
+        "runlogs_finalized" => {
+            let mut missing = Vec::new();
+            for t in &tasks {
+                let attempts = RunlogService::attempts(t.id).await.unwrap_or_default();
+                if attempts.last().and_then(|a| a.runlog_ko.as_ref()).is_none() {
+                    missing.push(label(t.id));
+                }
+            }
+            Some(match missing.is_empty() {
+                true => (true, "every trigger task has a finalized runlog".to_string()),
+                false => (false, format!("no finalized runlog: {}", missing.join(", "))),
+            })
+        }
+        "deliverables_resolve" => {
+            let resolver = match ko_resolve::resolver() {
+                Ok(r) => r,
+                Err(e) => return Some((false, format!("resolver unavailable: {e}"))),
+            };
+            let mut broken = Vec::new();
+            let mut n = 0;
+            for r in tasks.iter().flat_map(|t| &t.ko_refs) {
+                n += 1;
+                if let Err(e) = resolver.resolve(r).await {
+                    broken.push(format!("{r} ({e})"));
+                }
+            }
+            Some(match broken.is_empty() {
+                true => (true, format!("{n} deliverable KO(s) resolve")),
+                false => (false, format!("unresolved: {}", broken.join(", "))),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn render_report(ledger: &Ledger, gate: &Gate, attempts: &[(Uuid, usize, Option<String>)]) -> String {
+    let label = |id: Uuid| scheduler::label(ledger, id);
+    let mut out = String::new();
+    let name = gate.key.clone().unwrap_or_else(|| gate.id.to_string());
+    let _ = writeln!(out, "# POE report: {name}{}\n", gate.title.as_deref().map(|t| format!(" — {t}")).unwrap_or_default());
+    let _ = writeln!(out, "- Gate: {}", gate_ko(gate.id));
+    let _ = writeln!(out, "- Project: {} ({})", ledger.project.name, ledger.project.id);
+    let _ = writeln!(out, "- Generated: {}\n", OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default());
+    out.push_str("## Trigger tasks\n\n| Task | Status | Attempts | Runlog | Deliverables |\n|---|---|---|---|---|\n");
+    for (task_id, n, runlog) in attempts {
+        let Some(t) = ledger.task(*task_id) else { continue };
+        let status = serde_json::to_value(t.status).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default();
+        let _ = writeln!(out, "| {} | {status} | {n} | {} | {} |", label(t.id), runlog.as_deref().unwrap_or("—"), t.ko_refs.join(", "));
+    }
+    out.push_str("\n## Automated validations\n\n");
+    if gate.validations.is_empty() {
+        out.push_str("None declared.\n");
+    }
+    for v in &gate.validations {
+        let mark = match v.passed {
+            Some(true) => "x",
+            Some(false) => " ",
+            None => "?",
+        };
+        let _ = writeln!(out, "- [{mark}] {}: {}", v.name, v.detail);
+    }
+    let actions: Vec<String> = gate.actions.iter().map(|a| format!("{}: {}", a.name, a.text)).collect();
+    for (title, items) in [("Human review", &gate.human), ("Actions", &actions), ("Post-gate actions", &gate.post_gate)] {
+        let _ = writeln!(out, "\n## {title}\n");
+        if items.is_empty() {
+            out.push_str("None declared.\n");
+        }
+        for item in items {
+            let _ = writeln!(out, "- {item}");
+        }
+    }
+    out
+}
+
+/// Run a gate's automated validations and write its POE report. The gate ends up awaiting
+/// approval, or failed when any automated check failed.
+async fn validate(project_id: Uuid, gate_id: Uuid, actor: &str) -> Result<Vec<GateChange>> {
+    let store = ledger::store().await?;
+    let ledger = store.get(project_id).await.ok_or_else(|| anyhow!("project_not_found: {project_id}"))?;
+    let mut gate = ledger.gate(gate_id).cloned().ok_or_else(|| anyhow!("gate_not_found: {gate_id}"))?;
+    gate.validations.clear();
+    for name in &gate.automated {
+        let v = match run_check(&ledger, &gate, name.trim()).await {
+            Some((passed, detail)) => GateValidation { name: name.clone(), passed: Some(passed), detail },
+            None => GateValidation { name: name.clone(), passed: None, detail: "not automated; left to the reviewer".into() },
+        };
+        gate.validations.push(v);
+    }
+    let mut attempts = Vec::new();
+    for t in &gate.trigger {
+        let a = RunlogService::attempts(*t).await?;
+        attempts.push((*t, a.len(), a.last().and_then(|a| a.runlog_ko.clone())));
+    }
+    let dir = gate_dir(gate_id)?;
+    fs::create_dir_all(&dir).await?;
+    fs::write(dir.join("poe.md"), render_report(&ledger, &gate, &attempts)).await?;
+
+    let failed = gate.validations.iter().any(|v| v.passed == Some(false));
+    let to = if failed { GateStatus::ValidationFailed } else { GateStatus::AwaitingApproval };
+    let validations = gate.validations.clone();
+    let change = store
+        .update(project_id, |l| {
+            let g = l.gate_mut(gate_id).ok_or_else(|| anyhow!("gate_not_found: {gate_id}"))?;
+            // A trigger task was reopened while we were validating
+            if !matches!(g.status, GateStatus::Validating | GateStatus::ValidationFailed | GateStatus::AwaitingApproval) {
+                return Ok(None);
+            }
+            let from = g.status;
+            g.validations = validations;
+            g.report_ko = Some(report_ko(gate_id));
+            g.status = to;
+            Ok((from != to).then_some(GateChange { gate_id, from, to }))
+        })
+        .await?;
+    let detail = serde_json::json!({ "status": to, "validations": gate.validations, "report_ko": report_ko(gate_id) });
+    audit::record_committed(actor, "GATE.VALIDATED", Some(&gate_ko(gate_id)), detail).await;
+    let edges = gate.trigger.iter().map(|t| (report_ko(gate_id), EdgeKind::References, task_ko(*t)));
+    graph::record_committed(actor, "gate.validate", edges).await;
+    let changes: Vec<GateChange> = change.into_iter().collect();
+    record_gate_changes(actor, &changes).await;
+    Ok(changes)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateCreateReq {
+    pub project_id: Uuid,
+    pub key: Option<String>,
+    pub title: Option<String>,
+    pub trigger: Vec<Uuid>,
+    #[serde(default)]
+    pub automated: Vec<String>,
+    #[serde(default)]
+    pub human: Vec<String>,
+    #[serde(default)]
+    pub actions: Vec<GateAction>,
+    #[serde(default)]
+    pub post_gate: Vec<String>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateGetReq {
+    pub gate_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateListReq {
+    pub project_id: Uuid,
+    pub status: Option<GateStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateValidateReq {
+    pub gate_id: Uuid,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateDecideReq {
+    pub gate_id: Uuid,
+    pub approve: bool,
+    pub reason: Option<String>,
+    /// The reviewer; required, and listed in `policies/approvers.yml`.
+    pub actor: Option<String>,
+    /// The reviewer's token.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GateDecideResp {
+    pub gate: Gate,
+    pub decision: PolicyDecision,
+    /// Task status changes the decision caused (successors becoming ready).
+    pub changes: Vec<StatusChange>,
+}
+
+pub struct GateService;
+
+impl GateService {
+    /// Handler for op="gate.create"
+    pub async fn create(req: GateCreateReq) -> Result<Gate> {
+        if req.trigger.is_empty() {
+            return Err(anyhow!("bad_request: a gate needs at least one trigger task"));
+        }
+        let gate = Gate {
+            id: Uuid::new_v4(),
+            project_id: req.project_id,
+            key: req.key.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()),
+            title: req.title,
+            trigger: req.trigger,
+            automated: req.automated,
+            human: req.human,
+            actions: req.actions,
+            post_gate: req.post_gate,
+            status: GateStatus::Waiting,
+            validations: Vec::new(),
+            report_ko: None,
+            decision: None,
+            created_at: OffsetDateTime::now_utc(),
+        };
+        let gate_id = gate.id;
+        let store = ledger::store().await?;
+        let (changes, to_validate) = store
+            .update(req.project_id, move |l| {
+                if let Some(key) = &gate.key
+                    && l.key_in_use(key)
+                {
+                    return Err(anyhow!("gate_exists: '{key}' in project {}", l.project.id));
+                }
+                if let Some(missing) = gate.trigger.iter().find(|t| l.task(**t).is_none()) {
+                    return Err(anyhow!("task_not_found: trigger {missing} is not in project {}", l.project.id));
+                }
+                l.gates.push(gate);
+                Ok(sync(l))
+            })
+            .await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let created = Self::get(GateGetReq { gate_id }).await?;
+        let detail = serde_json::json!({ "project_id": req.project_id, "key": created.key, "trigger": created.trigger });
+        audit::record_committed(actor, "GATE.CREATED", Some(&gate_ko(gate_id)), detail).await;
+        record_gate_changes(actor, &changes).await;
+        for id in to_validate {
+            validate(req.project_id, id, actor).await?;
+        }
+        Self::get(GateGetReq { gate_id }).await
+    }
+
+    /// Handler for op="gate.get"
+    pub async fn get(req: GateGetReq) -> Result<Gate> {
+        let store = ledger::store().await?;
+        let project_id = store.project_of_gate(req.gate_id).await.ok_or_else(|| anyhow!("gate_not_found: {}", req.gate_id))?;
+        let ledger = store.get(project_id).await.ok_or_else(|| anyhow!("project_not_found: {project_id}"))?;
+        ledger.gate(req.gate_id).cloned().ok_or_else(|| anyhow!("gate_not_found: {}", req.gate_id))
+    }
+
+    /// Handler for op="gate.list"
+    pub async fn list(req: GateListReq) -> Result<Vec<Gate>> {
+        let ledger = ledger::store().await?.get(req.project_id).await.ok_or_else(|| anyhow!("project_not_found: {}", req.project_id))?;
+        Ok(ledger.gates.into_iter().filter(|g| req.status.is_none_or(|s| g.status == s)).collect())
+    }
+
+    /// Handler for op="gate.validate": re-run validations, e.g. after fixing a failed check.
+    pub async fn revalidate(req: GateValidateReq) -> Result<Gate> {
+        let gate = Self::get(GateGetReq { gate_id: req.gate_id }).await?;
+        if !matches!(gate.status, GateStatus::Validating | GateStatus::ValidationFailed | GateStatus::AwaitingApproval) {
+            return Err(anyhow!("invalid_transition: gate {} is {:?}, nothing to validate", gate.id, gate.status));
+        }
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        validate(gate.project_id, gate.id, actor).await?;
+        Self::get(GateGetReq { gate_id: req.gate_id }).await
+    }
+
+    /// Handler for op="gate.decide": an approver approves or rejects a gate. Approval unlocks
+    /// the tasks waiting on the gate; the decision is recorded as a `PolicyDecision`.
+    pub async fn decide(req: GateDecideReq) -> Result<GateDecideResp> {
+        let actor = req.actor.as_deref().map(str::trim).filter(|a| !a.is_empty() && *a != ANONYMOUS_ACTOR);
+        let actor = actor.ok_or_else(|| anyhow!("bad_request: a gate decision needs the reviewer as actor"))?.to_string();
+        let store = ledger::store().await?;
+        let project_id = store.project_of_gate(req.gate_id).await.ok_or_else(|| anyhow!("gate_not_found: {}", req.gate_id))?;
+        if let Err(e) = authorize(&approvers().await?, &actor, req.token.as_deref()) {
+            let detail = serde_json::json!({ "approve": req.approve, "error": e.to_string() });
+            audit::record_committed(&actor, "GATE.DECISION_REFUSED", Some(&gate_ko(req.gate_id)), detail).await;
+            return Err(e);
+        }
+        let decision = GateDecision { approved: req.approve, actor: actor.clone(), reason: req.reason.clone(), at: OffsetDateTime::now_utc() };
+        let (gate, change, changes) = store
+            .update(project_id, |l| {
+                let g = l.gate_mut(req.gate_id).ok_or_else(|| anyhow!("gate_not_found: {}", req.gate_id))?;
+                let from = g.status;
+                let ok = match req.approve {
+                    true => from == GateStatus::AwaitingApproval,
+                    false => matches!(from, GateStatus::AwaitingApproval | GateStatus::ValidationFailed),
+                };
+                if !ok {
+                    return Err(anyhow!("invalid_transition: gate {} is {from:?}", req.gate_id));
+                }
+                g.status = if req.approve { GateStatus::Approved } else { GateStatus::Rejected };
+                g.decision = Some(decision);
+                let change = GateChange { gate_id: g.id, from, to: g.status };
+                let gate = g.clone();
+                Ok((gate, change, scheduler::refresh(l)))
+            })
+            .await?;
+
+        let outcome = if req.approve { "approve" } else { "reject" };
+        let mut decisions = vec![format!("gate:{outcome}"), format!("reviewer:{actor}")];
+        decisions.extend(req.reason.iter().map(|r| format!("reason:{r}")));
+        let policy = PolicyDecision { task_id: gate_ko(gate.id), decisions, sse_verdict_ko: gate.report_ko.clone() };
+        audit::record_committed(&actor, "POLICY.DECISION", Some(&gate_ko(gate.id)), serde_json::json!(policy)).await;
+        record_gate_changes(&actor, &[change]).await;
+        scheduler::record_changes(&actor, &changes, Some(&format!("gate {outcome}d"))).await;
+        if req.approve && !gate.post_gate.is_empty() {
+            audit::record_committed(&actor, "GATE.POST_GATE", Some(&gate_ko(gate.id)), serde_json::json!({ "actions": gate.post_gate })).await;
+        }
+        Ok(GateDecideResp { gate, decision: policy, changes })
+    }
+}
+
+/// Resolves `ko:gate/<gate_id>` to the gate and `ko:gate/<gate_id>/poe` to its POE report.
+pub struct GateKoHandler;
+
+#[async_trait]
+impl KoHandler for GateKoHandler {
+    fn namespace(&self) -> &str {
+        "gate"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> std::result::Result<ResolvedKo, KoError> {
+        let ko_id = format!("ko:gate/{}", uri.path);
+        let (id, rest) = uri.path.split_once('/').unwrap_or((&uri.path, ""));
+        let gate_id = Uuid::parse_str(id).map_err(|_| KoError::InvalidUri(format!("'{ko_id}': expected ko:gate/<gate_id>[/poe]")))?;
+        let gate = GateService::get(GateGetReq { gate_id }).await.map_err(|e| ko_resolve::ko_error(&ko_id, e))?;
+        let title = gate.key.clone().or(gate.title.clone());
+        match rest {
+            "" => Ok(ResolvedKo {
+                id: ko_id.clone(),
+                namespace: "gate".into(),
+                title,
+                media_type: "application/json".into(),
+                content: serde_json::to_string_pretty(&gate).map_err(|e| ko_resolve::ko_error(&ko_id, e.into()))?,
+                metadata: serde_json::json!({ "status": gate.status, "project_id": gate.project_id }),
+            }),
+            "poe" => {
+                let path = gate_dir(gate_id).map_err(|e| ko_resolve::ko_error(&ko_id, e))?.join("poe.md");
+                let content = fs::read_to_string(&path).await.map_err(|_| KoError::NotFound(ko_id.clone()))?;
+                Ok(ResolvedKo {
+                    id: ko_id,
+                    namespace: "gate".into(),
+                    title: title.map(|t| format!("POE report: {t}")),
+                    media_type: "text/markdown".into(),
+                    content,
+                    metadata: serde_json::json!({ "status": gate.status, "gate": gate_ko(gate_id) }),
+                })
+            }
+            _ => Err(KoError::NotFound(ko_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Predecessor;
+    use crate::services::testing;
+
+    #[test]
+    fn test_sync_follows_trigger_tasks() {
+        let mut l = testing::ledger();
+        let (a, b) = (testing::task(None, TaskStatus::Done, vec![]), testing::task(None, TaskStatus::Running, vec![]));
+        let (a_id, b_id) = (a.id, b.id);
+        l.tasks.extend([a, b]);
+        let gate = testing::gate(l.project.id, Some("GATE-A-READY"), vec![a_id, b_id]);
+        let gate_id = gate.id;
+        l.gates.push(gate);
+        let next = testing::task(None, TaskStatus::Pending, vec![Predecessor::Task(gate_id)]);
+        let next_id = next.id;
+        l.tasks.push(next);
+
+        assert_eq!(sync(&mut l), (vec![], vec![]));
+        l.task_mut(b_id).unwrap().status = TaskStatus::Done;
+        let (changes, to_validate) = sync(&mut l);
+        assert_eq!(changes, vec![GateChange { gate_id, from: GateStatus::Waiting, to: GateStatus::Validating }]);
+        assert_eq!(to_validate, vec![gate_id]);
+
+        // Reopening a trigger task sends the gate back to waiting
+        l.gate_mut(gate_id).unwrap().status = GateStatus::AwaitingApproval;
+        l.task_mut(a_id).unwrap().status = TaskStatus::Pending;
+        assert_eq!(sync(&mut l).0[0].to, GateStatus::Waiting);
+
+        // Only approval unlocks the successor
+        scheduler::refresh(&mut l);
+        assert_eq!(l.task(next_id).unwrap().status, TaskStatus::Pending);
+        l.gate_mut(gate_id).unwrap().status = GateStatus::Approved;
+        scheduler::refresh(&mut l);
+        assert_eq!(l.task(next_id).unwrap().status, TaskStatus::Ready);
+    }
+
+    #[test]
+    fn test_decisions_need_a_listed_approver_and_token() {
+        assert!(parse_approvers(include_str!("../../../../policies/approvers.yml")).unwrap().is_empty());
+        let yaml = "approvers:\n  - name: alice\n    token_sha256: 2C26B46B68FFC68FF99B453C1D30413413422D706483BFA0F98A5E886266E7AE\n";
+        let approvers = parse_approvers(yaml).unwrap();
+        let err = |actor, token| authorize(&approvers, actor, token).unwrap_err().to_string();
+
+        authorize(&approvers, "alice", Some("foo")).unwrap();
+        assert_eq!(err("alice", Some("bar")), "forbidden: token does not match approver alice");
+        assert_eq!(err("alice", None), "forbidden: a gate decision needs the approver's token");
+        assert_eq!(err("bob", Some("foo")), "forbidden: bob is not a gate approver");
+        assert!(authorize(&[], "alice", Some("foo")).unwrap_err().to_string().starts_with("forbidden: no gate approvers"));
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::ko_graph::{self, Direction, EdgeKind, GraphFormat, KoEdge, KoGraph, Subgraph};
+use opencode_pm_core::ko_resolver::KoUri;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use parking_lot::Mutex;
+use std::sync::{Arc, OnceLock};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::specbundle::SpecBundle;
+use crate::services::specpart::SpecPart;
+use crate::services::storage::genesis_home;
+
+static GRAPH: OnceLock<Arc<KoGraph>> = OnceLock::new();
+
+/// Edges `record_committed` could not write yet; retried on the next record.
+static DEFERRED: Mutex<Vec<KoEdge>> = Mutex::new(Vec::new());
+
+pub fn graph() -> Result<Arc<KoGraph>> {
+    if let Some(g) = GRAPH.get() {
+        return Ok(g.clone());
+    }
+    let g = Arc::new(KoGraph::open(genesis_home()?.join("graph").join("edges.jsonl"))?);
+    if !g.skipped().is_empty() {
+        tracing::warn!(path = %g.path().display(), lines = ?g.skipped(), "skipped unreadable graph edges");
+    }
+    Ok(GRAPH.get_or_init(|| g).clone())
+}
+
+fn edges_of(actor: &str, via: &str, edges: impl IntoIterator<Item = (String, EdgeKind, String)>) -> Result<Vec<KoEdge>> {
+    let at = OffsetDateTime::now_utc().format(&Rfc3339)?;
+    Ok(edges
+        .into_iter()
+        .map(|(from, kind, to)| KoEdge {
+            from,
+            kind,
+            to,
+            actor: actor.to_string(),
+            via: via.to_string(),
+            at: at.clone(),
+        })
+        .collect())
+}
+
+/// Write any deferred edges, then `edges`, off the async threads. Returns how many of
+/// `edges` were new; whatever was not written stays deferred.
+async fn add(edges: Vec<KoEdge>) -> Result<usize> {
+    let deferred = std::mem::take(&mut *DEFERRED.lock());
+    let g = match graph() {
+        Ok(g) => g,
+        Err(e) => {
+            DEFERRED.lock().extend(deferred.into_iter().chain(edges));
+            return Err(e);
+        }
+    };
+    let (unwritten, result) = tokio::task::spawn_blocking(move || {
+        if !deferred.is_empty() {
+            match g.add(deferred.iter().cloned()) {
+                Ok(n) => tracing::info!(retried = deferred.len(), added = n, "wrote deferred graph edges"),
+                Err(e) => return (deferred.into_iter().chain(edges).collect(), Err(e)),
+            }
+        }
+        match g.add(edges.iter().cloned()) {
+            Ok(n) => (Vec::new(), Ok(n)),
+            Err(e) => (edges, Err(e)),
+        }
+    })
+    .await?;
+    DEFERRED.lock().extend(unwritten);
+    Ok(result?)
+}
+
+/// Record `(from, kind, to)` edges created by operation `via`. Returns how many were new.
+pub fn record(
+    actor: &str,
+    via: &str,
+    edges: impl IntoIterator<Item = (String, EdgeKind, String)>,
+) -> impl Future<Output = Result<usize>> + Send + 'static {
+    // Collected up front so the future does not hold the caller's iterator
+    let edges = edges_of(actor, via, edges);
+    async move { add(edges?).await }
+}
+
+/// `record` for edges of an object that is already committed: failing the caller then
+/// would report an error for something that exists, and its retry would duplicate it. A
+/// failure is logged and the edges are written with the next record instead.
+pub fn record_committed(
+    actor: &str,
+    via: &str,
+    edges: impl IntoIterator<Item = (String, EdgeKind, String)>,
+) -> impl Future<Output = ()> + Send + 'static {
+    let edges = edges_of(actor, via, edges);
+    let via = via.to_string();
+    async move {
+        let result = match edges {
+            Ok(edges) => add(edges).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!(via, error = %e, "graph edges deferred");
+        }
+    }
+}
+
+/// KO id for an agent session, e.g. `ko:session/claude/abc123`.
+pub fn session_ko(agent: &str, session_id: &str) -> String {
+    format!("ko:session/{agent}/{session_id}")
+}
+
+/// Provenance edges a bundle carries itself: the sessions it came from and KO link parts.
+pub fn bundle_edges(ko_id: &str, bundle: &SpecBundle) -> Vec<(String, EdgeKind, String)> {
+    let sessions = bundle
+        .source_sessions
+        .iter()
+        .map(|s| (ko_id.to_string(), EdgeKind::ProducedBy, session_ko(&s.agent, &s.session_id)));
+    let links = bundle.parts.iter().filter_map(|p| match p {
+        SpecPart::Link { url, .. } if url.starts_with("ko:") => Some((ko_id.to_string(), EdgeKind::References, KoUri::canonical(url))),
+        _ => None,
+    });
+    sessions.chain(links).collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphLinkReq {
+    pub from: String,
+    pub kind: EdgeKind,
+    pub to: String,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphLinkResp {
+    pub added: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphWalkReq {
+    pub id: String,
+    /// Edge kinds to follow; all when empty.
+    #[serde(default)]
+    pub kinds: Vec<EdgeKind>,
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphWalkResp {
+    pub id: String,
+    #[serde(flatten)]
+    pub graph: Subgraph,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphExportReq {
+    /// Whole graph when absent.
+    pub root: Option<String>,
+    pub direction: Option<Direction>,
+    #[serde(default)]
+    pub kinds: Vec<EdgeKind>,
+    pub depth: Option<usize>,
+    pub format: GraphFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GraphExportResp {
+    pub format: GraphFormat,
+    pub nodes: usize,
+    pub edges: usize,
+    pub content: String,
+}
+
+pub struct GraphService;
+
+impl GraphService {
+    fn walk(req: GraphWalkReq, direction: Direction) -> Result<GraphWalkResp> {
+        let id = ko_graph::node_id(&req.id);
+        let graph = graph()?.walk(&id, direction, &req.kinds, req.depth);
+        Ok(GraphWalkResp { id, graph })
+    }
+
+    /// Handler for op="graph.link": record an edge created outside this service.
+    pub async fn link(req: GraphLinkReq) -> Result<GraphLinkResp> {
+        if req.from.trim().is_empty() || req.to.trim().is_empty() {
+            return Err(anyhow!("bad_request: from and to are required"));
+        }
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let added = record(actor, "graph.link", [(req.from.clone(), req.kind, req.to.clone())]).await? > 0;
+        if added {
+            let detail = serde_json::json!({ "kind": req.kind, "to": ko_graph::node_id(&req.to) });
+            audit::record_committed(actor, "KO.LINKED", Some(&ko_graph::node_id(&req.from)), detail).await;
+        }
+        Ok(GraphLinkResp { added })
+    }
+
+    /// Handler for op="graph.ancestors": what a KO was derived from, transitively.
+    pub async fn ancestors(req: GraphWalkReq) -> Result<GraphWalkResp> {
+        Self::walk(req, Direction::Ancestors)
+    }
+
+    /// Handler for op="graph.descendants": what was derived from a KO, transitively.
+    pub async fn descendants(req: GraphWalkReq) -> Result<GraphWalkResp> {
+        Self::walk(req, Direction::Descendants)
+    }
+
+    /// Handler for op="graph.export": Graphviz or Mermaid source for a subgraph or everything.
+    pub async fn export(req: GraphExportReq) -> Result<GraphExportResp> {
+        let graph = graph()?;
+        let sub = match &req.root {
+            Some(root) => graph.walk(root, req.direction.unwrap_or(Direction::Both), &req.kinds, req.depth),
+            None => {
+                let mut all = graph.all();
+                if !req.kinds.is_empty() {
+                    all.edges.retain(|e| req.kinds.contains(&e.kind));
+                }
+                all
+            }
+        };
+        let mut nodes: HashSet<&str> = sub.edges.iter().flat_map(|e| [e.from.as_str(), e.to.as_str()]).collect();
+        let root = req.root.as_deref().map(ko_graph::node_id);
+        nodes.extend(root.as_deref());
+        Ok(GraphExportResp {
+            format: req.format,
+            nodes: nodes.len(),
+            edges: sub.edges.len(),
+            content: ko_graph::render(&sub.edges, req.format),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::specbundle::SourceSession;
+
+    #[test]
+    fn test_bundle_edges() {
+        let bundle = SpecBundle {
+            title: "Queue".into(),
+            created_by: "alice".into(),
+            source_sessions: vec![SourceSession { agent: "claude".into(), session_id: "s1".into() }],
+            parts: vec![
+                SpecPart::Link { url: "ko://docs/adr/0002-queue-leasing".into(), title: None },
+                SpecPart::Link { url: "https://example.com".into(), title: None },
+            ],
+            tags: vec![],
+            redactions: vec![],
+        };
+        let edges = bundle_edges("ko:specbundle/x", &bundle);
+        assert_eq!(
+            edges,
+            vec![
+                ("ko:specbundle/x".to_string(), EdgeKind::ProducedBy, "ko:session/claude/s1".to_string()),
+                ("ko:specbundle/x".to_string(), EdgeKind::References, "ko:docs/adr/0002-queue-leasing".to_string()),
+            ]
+        );
+    }
+}
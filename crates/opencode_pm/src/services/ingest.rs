@@ -0,0 +1,555 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::knowledge::{Heading, KnowledgeChunk, KnowledgeDoc, KnowledgeStore};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs;
+use tokio::sync::{Mutex, Notify};
+use uuid::Uuid;
+
+use crate::services::audit::{self, SYSTEM_ACTOR};
+use crate::services::license;
+use crate::services::specbundle::{self, SpecBundle, SpecbundleGetReq, SpecbundleService};
+use crate::services::specpart::SpecPart;
+use crate::services::storage::genesis_home;
+use crate::services::util::replace_file;
+
+/// Sections longer than this are split further at paragraph breaks.
+const MAX_CHUNK_CHARS: usize = 2000;
+const MAX_ATTEMPTS: u32 = 3;
+/// Idle poll interval; enqueue also wakes the worker directly.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+static QUEUE: OnceLock<Arc<IngestQueue>> = OnceLock::new();
+
+/// Install the queue created at startup (see `main`).
+pub fn init_queue(queue: Arc<IngestQueue>) {
+    if QUEUE.set(queue).is_err() {
+        tracing::warn!("ingest queue already initialised; keeping the first one");
+    }
+}
+
+pub fn queue() -> Result<Arc<IngestQueue>> {
+    if let Some(q) = QUEUE.get() {
+        return Ok(q.clone());
+    }
+    let q = Arc::new(IngestQueue::from_env()?);
+    Ok(QUEUE.get_or_init(|| q).clone())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    /// Gave up after `max_attempts`.
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestJob {
+    pub job_id: String,
+    pub ko_id: String,
+    pub revision: u32,
+    pub state: JobState,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    pub last_error: Option<String>,
+    /// Chunks written on success; `None` when the job was skipped as stale or deleted.
+    pub chunks: Option<usize>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub next_attempt_at: OffsetDateTime,
+}
+
+struct Section {
+    heading_path: Vec<String>,
+    start: usize,
+    text: String,
+    links: Vec<String>,
+}
+
+/// Headings and heading-delimited sections (with the links they contain) of a markdown text.
+fn markdown_sections(md: &str) -> (Vec<Heading>, Vec<Section>) {
+    let mut headings = Vec::new();
+    let mut stack: Vec<(u8, String)> = Vec::new();
+    let mut sections = vec![Section { heading_path: vec![], start: 0, text: String::new(), links: vec![] }];
+    let mut open: Option<(u8, String, usize)> = None;
+
+    for (ev, range) in Parser::new_ext(md, Options::ENABLE_TABLES).into_offset_iter() {
+        match ev {
+            Event::Start(Tag::Heading { level, .. }) => open = Some((level as u8, String::new(), range.start)),
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, text, _)) = open.as_mut() {
+                    text.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((level, text, start)) = open.take() else { continue };
+                while stack.last().is_some_and(|(l, _)| *l >= level) {
+                    stack.pop();
+                }
+                stack.push((level, text.clone()));
+                headings.push(Heading { level, text });
+                sections.push(Section {
+                    heading_path: stack.iter().map(|(_, t)| t.clone()).collect(),
+                    start,
+                    text: String::new(),
+                    links: vec![],
+                });
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                if let Some(s) = sections.last_mut() {
+                    s.links.push(dest_url.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let ends: Vec<usize> = sections.iter().skip(1).map(|s| s.start).chain([md.len()]).collect();
+    let out = sections
+        .into_iter()
+        .zip(ends)
+        .filter_map(|(s, end)| {
+            let text = md[s.start..end].trim();
+            (!text.is_empty()).then(|| Section { text: text.to_string(), ..s })
+        })
+        .collect();
+    (headings, out)
+}
+
+/// Split `text` at blank lines into pieces of at most ~`MAX_CHUNK_CHARS`. A paragraph that is
+/// longer on its own is cut at whitespace, or mid-word when it has none.
+fn split_long(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    for para in text.split("\n\n").flat_map(split_paragraph) {
+        if !cur.is_empty() && cur.len() + para.len() + 2 > MAX_CHUNK_CHARS {
+            out.push(std::mem::take(&mut cur));
+        }
+        if !cur.is_empty() {
+            cur.push_str("\n\n");
+        }
+        cur.push_str(para);
+    }
+    if !cur.trim().is_empty() {
+        out.push(cur);
+    }
+    out
+}
+
+fn split_paragraph(mut para: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    while para.len() > MAX_CHUNK_CHARS {
+        let mut end = MAX_CHUNK_CHARS;
+        while !para.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(ws) = para[..end].rfind(char::is_whitespace).filter(|&ws| ws > 0) {
+            end = ws;
+        }
+        out.push(para[..end].trim_end());
+        para = para[end..].trim_start();
+    }
+    out.push(para);
+    out
+}
+
+/// Chunk the markdown parts of `bundle`, except the `withheld` part numbers (1-based), and
+/// replace its entry in the knowledge store. `None` when a newer revision is already stored.
+pub fn ingest_bundle(knowledge: &KnowledgeStore, ko_id: &str, revision: u32, bundle: &SpecBundle, withheld: &[usize]) -> Result<Option<usize>> {
+    let mut headings = Vec::new();
+    let mut links = Vec::new();
+    let mut chunks = Vec::new();
+    for (i, part) in bundle.parts.iter().enumerate() {
+        match part {
+            SpecPart::Markdown { content } if !withheld.contains(&(i + 1)) => {
+                let (h, sections) = markdown_sections(content);
+                headings.extend(h);
+                for section in sections {
+                    links.extend(section.links.iter().cloned());
+                    for piece in split_long(&section.text) {
+                        let ordinal = chunks.len() + 1;
+                        chunks.push(KnowledgeChunk {
+                            id: format!("{ko_id}#{ordinal}"),
+                            ko_id: ko_id.to_string(),
+                            ordinal,
+                            part: i + 1,
+                            heading_path: section.heading_path.clone(),
+                            text: piece,
+                            links: section.links.clone(),
+                        });
+                    }
+                }
+            }
+            SpecPart::Link { url, .. } => links.push(url.clone()),
+            _ => {}
+        }
+    }
+    links.sort();
+    links.dedup();
+
+    let count = chunks.len();
+    let doc = KnowledgeDoc {
+        ko_id: ko_id.to_string(),
+        revision,
+        title: bundle.title.clone(),
+        tags: bundle.tags.clone(),
+        headings,
+        links,
+        indexed_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+    };
+    Ok(knowledge.upsert(doc, chunks)?.then_some(count))
+}
+
+/// Markdown parts under licenses the policy rejects, going by their SPDX headers. They are left
+/// out of the knowledge store rather than failing the job; findings are audited either way.
+async fn withheld_parts(ko_id: &str, bundle: &SpecBundle) -> Result<Vec<usize>> {
+    let Some(policy) = license::policy().await? else { return Ok(Vec::new()) };
+    let items: Vec<(String, &str)> = bundle
+        .parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, p)| match p {
+            SpecPart::Markdown { content } => Some((format!("part {}", i + 1), content.as_str())),
+            _ => None,
+        })
+        .collect();
+    let findings = license::check(&policy, &items, true);
+    license::record(SYSTEM_ACTOR, Some(ko_id), &findings).await;
+    let withheld = (1..=bundle.parts.len()).filter(|n| findings.iter().any(|f| f.rejected && f.item == format!("part {n}")));
+    Ok(withheld.collect())
+}
+
+/// Persistent ingestion queue: one JSON file per job under `dir`, drained by a single worker.
+pub struct IngestQueue {
+    dir: PathBuf,
+    knowledge: Arc<KnowledgeStore>,
+    notify: Notify,
+    // Serializes job file read-modify-write cycles
+    lock: Mutex<()>,
+    retry_base: Duration,
+}
+
+impl IngestQueue {
+    pub fn new(dir: impl Into<PathBuf>, knowledge: Arc<KnowledgeStore>) -> Self {
+        Self { dir: dir.into(), knowledge, notify: Notify::new(), lock: Mutex::new(()), retry_base: Duration::from_secs(5) }
+    }
+
+    /// Queue under `<genesis home>/ingest/jobs`, knowledge under `<genesis home>/knowledge`.
+    pub fn from_env() -> Result<Self> {
+        let home = genesis_home()?;
+        let knowledge = KnowledgeStore::open(home.join("knowledge"))?;
+        for (path, e) in knowledge.skipped() {
+            tracing::warn!("knowledge store: skipping unreadable {}: {e}", path.display());
+        }
+        Ok(Self::new(home.join("ingest").join("jobs"), Arc::new(knowledge)))
+    }
+
+    pub fn knowledge(&self) -> Arc<KnowledgeStore> {
+        self.knowledge.clone()
+    }
+
+    fn job_path(&self, job_id: &str) -> Result<PathBuf> {
+        Uuid::parse_str(job_id).map_err(|_| anyhow!("invalid job_id '{job_id}'"))?;
+        Ok(self.dir.join(format!("{job_id}.json")))
+    }
+
+    async fn save(&self, job: &IngestJob) -> Result<()> {
+        replace_file(&self.job_path(&job.job_id)?, &serde_json::to_vec_pretty(job)?).await
+    }
+
+    pub async fn enqueue(&self, ko_id: &str, revision: u32) -> Result<IngestJob> {
+        let now = OffsetDateTime::now_utc();
+        let job = IngestJob {
+            job_id: Uuid::new_v4().to_string(),
+            ko_id: ko_id.to_string(),
+            revision,
+            state: JobState::Queued,
+            attempts: 0,
+            max_attempts: MAX_ATTEMPTS,
+            last_error: None,
+            chunks: None,
+            created_at: now,
+            updated_at: now,
+            next_attempt_at: now,
+        };
+        {
+            let _guard = self.lock.lock().await;
+            self.save(&job).await?;
+        }
+        self.notify.notify_one();
+        Ok(job)
+    }
+
+    /// All jobs, oldest first.
+    pub async fn jobs(&self) -> Result<Vec<IngestJob>> {
+        let mut jobs = Vec::new();
+        let mut rd = match fs::read_dir(&self.dir).await {
+            Ok(rd) => rd,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(jobs),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(item) = rd.next_entry().await? {
+            let path = item.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match serde_json::from_slice::<IngestJob>(&fs::read(&path).await?) {
+                Ok(job) => jobs.push(job),
+                Err(e) => tracing::warn!("skipping unreadable ingest job {}: {e}", path.display()),
+            }
+        }
+        jobs.sort_by_key(|j| j.created_at);
+        Ok(jobs)
+    }
+
+    /// Jobs left `running` by a previous process go back to the queue.
+    pub async fn recover(&self) -> Result<usize> {
+        let _guard = self.lock.lock().await;
+        let mut n = 0;
+        for mut job in self.jobs().await? {
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+                job.updated_at = OffsetDateTime::now_utc();
+                self.save(&job).await?;
+                n += 1;
+            }
+        }
+        Ok(n)
+    }
+
+    /// Take the oldest due job and mark it running.
+    async fn claim(&self) -> Result<Option<IngestJob>> {
+        let _guard = self.lock.lock().await;
+        let now = OffsetDateTime::now_utc();
+        let Some(mut job) = self
+            .jobs()
+            .await?
+            .into_iter()
+            .find(|j| j.state == JobState::Queued && j.next_attempt_at <= now)
+        else {
+            return Ok(None);
+        };
+        job.state = JobState::Running;
+        job.attempts += 1;
+        job.updated_at = now;
+        self.save(&job).await?;
+        Ok(Some(job))
+    }
+
+    /// Record the outcome of an attempt; failures are retried with exponential backoff.
+    async fn finish(&self, mut job: IngestJob, outcome: Result<Option<usize>>) -> Result<IngestJob> {
+        let _guard = self.lock.lock().await;
+        let now = OffsetDateTime::now_utc();
+        job.updated_at = now;
+        match outcome {
+            Ok(chunks) => {
+                job.state = JobState::Completed;
+                job.chunks = chunks;
+                job.last_error = None;
+            }
+            Err(e) => {
+                job.last_error = Some(e.to_string());
+                if job.attempts >= job.max_attempts {
+                    job.state = JobState::Failed;
+                } else {
+                    job.state = JobState::Queued;
+                    job.next_attempt_at = now + self.retry_base * 2u32.pow(job.attempts - 1);
+                }
+            }
+        }
+        self.save(&job).await?;
+        Ok(job)
+    }
+
+    async fn audit(job: &IngestJob, event: &str) {
+        let detail = serde_json::json!({
+            "job_id": job.job_id,
+            "revision": job.revision,
+            "attempt": job.attempts,
+            "chunks": job.chunks,
+            "error": job.last_error,
+        });
+        audit::record_committed(SYSTEM_ACTOR, event, Some(&job.ko_id), detail).await;
+    }
+
+    /// Process one due job. Returns false when there was nothing to do.
+    pub async fn run_once(&self) -> Result<bool> {
+        let Some(job) = self.claim().await? else { return Ok(false) };
+        Self::audit(&job, "INGEST.STARTED").await;
+        let outcome = async {
+            let got = SpecbundleService::get(SpecbundleGetReq {
+                ko_id: job.ko_id.clone(),
+                revision: Some(job.revision),
+                include_deleted: true,
+            })
+            .await?;
+            let withheld = withheld_parts(&job.ko_id, &got.bundle).await?;
+            // A bundle deleted since the job was queued is skipped rather than re-added
+            let Some(_live) = specbundle::lock_live(&job.ko_id).await? else { return Ok(None) };
+            let (knowledge, ko_id, revision) = (self.knowledge.clone(), job.ko_id.clone(), job.revision);
+            tokio::task::spawn_blocking(move || ingest_bundle(&knowledge, &ko_id, revision, &got.bundle, &withheld)).await?
+        }
+        .await;
+        let job = self.finish(job, outcome).await?;
+        match job.state {
+            JobState::Completed if job.chunks.is_none() => Self::audit(&job, "INGEST.SKIPPED").await,
+            JobState::Completed => Self::audit(&job, "INGEST.COMPLETED").await,
+            _ => {
+                tracing::warn!(ko_id = %job.ko_id, job_id = %job.job_id, "ingest attempt {} failed: {:?}", job.attempts, job.last_error);
+                Self::audit(&job, "INGEST.FAILED").await;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Background worker: drains due jobs, then sleeps until woken by `enqueue` or the poll interval.
+    pub fn spawn_worker(self: Arc<Self>) {
+        tokio::spawn(async move {
+            match self.recover().await {
+                Ok(n) if n > 0 => tracing::info!("ingest: requeued {n} interrupted job(s)"),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("ingest recovery failed: {e}"),
+            }
+            loop {
+                match self.run_once().await {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("ingest worker: {e}"),
+                }
+                tokio::select! {
+                    _ = self.notify.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IngestStatusReq {
+    pub job_id: Option<String>,
+    pub ko_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestStatusResp {
+    pub jobs: Vec<IngestJob>,
+}
+
+pub struct IngestService;
+
+impl IngestService {
+    /// Handler for op="ingest.status": jobs filtered by id and/or KO.
+    pub async fn status(req: IngestStatusReq) -> Result<IngestStatusResp> {
+        let jobs = queue()?
+            .jobs()
+            .await?
+            .into_iter()
+            .filter(|j| req.job_id.as_ref().is_none_or(|id| *id == j.job_id))
+            .filter(|j| req.ko_id.as_ref().is_none_or(|id| *id == j.ko_id))
+            .collect::<Vec<_>>();
+        if let Some(id) = &req.job_id
+            && jobs.is_empty()
+        {
+            return Err(anyhow!("ingest_job_not_found: {id}"));
+        }
+        Ok(IngestStatusResp { jobs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(parts: Vec<SpecPart>) -> SpecBundle {
+        SpecBundle {
+            title: "Queue Engine".into(),
+            created_by: "alice".into(),
+            source_sessions: vec![],
+            parts,
+            tags: vec!["queue".into()],
+            redactions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_markdown_sections() {
+        let md = "intro\n\n# Design\nsee [ADR](docs/adr.md)\n\n## Leasing\nleases expire\n\n# Risks\nnone";
+        let (headings, sections) = markdown_sections(md);
+        let names: Vec<_> = headings.iter().map(|h| (h.level, h.text.as_str())).collect();
+        assert_eq!(names, vec![(1, "Design"), (2, "Leasing"), (1, "Risks")]);
+        let paths: Vec<_> = sections.iter().map(|s| s.heading_path.join(" > ")).collect();
+        assert_eq!(paths, vec!["", "Design", "Design > Leasing", "Risks"]);
+        assert_eq!(sections[1].links, vec!["docs/adr.md".to_string()]);
+        assert!(sections[2].text.starts_with("## Leasing"));
+    }
+
+    #[test]
+    fn test_ingest_bundle_writes_knowledge() {
+        let tmp = tempfile::tempdir().unwrap();
+        let knowledge = KnowledgeStore::open(tmp.path()).unwrap();
+        let long = format!("# Big\n{}\n\n{}", vec!["word ".repeat(100); 10].join("\n\n"), "x".repeat(5000));
+        let b = bundle(vec![
+            SpecPart::Markdown { content: long },
+            SpecPart::Mermaid { content: "graph TD".into() },
+            SpecPart::Link { url: "https://example.com".into(), title: None },
+        ]);
+        let n = ingest_bundle(&knowledge, "ko:specbundle/x", 2, &b, &[]).unwrap().unwrap();
+        assert!(n > 1);
+        // A retried job for an older revision must not replace the newer one
+        assert_eq!(ingest_bundle(&knowledge, "ko:specbundle/x", 1, &bundle(vec![]), &[]).unwrap(), None);
+        let chunks = knowledge.chunks("ko:specbundle/x");
+        assert!(chunks.iter().all(|c| c.heading_path == vec!["Big".to_string()] && c.text.len() <= MAX_CHUNK_CHARS));
+        assert_eq!(chunks[1].id, "ko:specbundle/x#2");
+
+        std::fs::write(tmp.path().join("torn.json"), "{\"doc\":").unwrap();
+        let reopened = KnowledgeStore::open(tmp.path()).unwrap();
+        let doc = reopened.doc("ko:specbundle/x").unwrap();
+        assert_eq!((doc.revision, doc.links.clone()), (2, vec!["https://example.com".to_string()]));
+        assert_eq!(reopened.skipped().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_retries_then_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let knowledge = Arc::new(KnowledgeStore::open(tmp.path().join("k")).unwrap());
+        let mut q = IngestQueue::new(tmp.path().join("jobs"), knowledge);
+        q.retry_base = Duration::ZERO;
+        let job = q.enqueue("ko:specbundle/x", 1).await.unwrap();
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let claimed = q.claim().await.unwrap().unwrap();
+            assert_eq!((claimed.job_id.as_str(), claimed.attempts), (job.job_id.as_str(), attempt));
+            assert!(q.claim().await.unwrap().is_none(), "running job must not be claimed twice");
+            q.finish(claimed, Err(anyhow!("boom"))).await.unwrap();
+        }
+        let jobs = q.jobs().await.unwrap();
+        assert_eq!((jobs[0].state, jobs[0].last_error.as_deref()), (JobState::Failed, Some("boom")));
+        assert!(q.claim().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_queue_recovers_running_jobs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let knowledge = Arc::new(KnowledgeStore::open(tmp.path().join("k")).unwrap());
+        let q = IngestQueue::new(tmp.path().join("jobs"), knowledge);
+        q.enqueue("ko:specbundle/x", 1).await.unwrap();
+        let claimed = q.claim().await.unwrap().unwrap();
+        assert_eq!(q.recover().await.unwrap(), 1);
+        let again = q.claim().await.unwrap().unwrap();
+        assert_eq!((again.job_id, again.attempts), (claimed.job_id.clone(), 2));
+        let done = q.finish(claimed, Ok(Some(3))).await.unwrap();
+        assert_eq!((done.state, done.chunks), (JobState::Completed, Some(3)));
+    }
+}
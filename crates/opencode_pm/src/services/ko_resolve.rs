@@ -0,0 +1,65 @@
+use anyhow::Result;
+use opencode_pm_core::ko_resolver::{FileKoHandler, KoError, KoResolver, ResolvedKo, SchemaKoHandler};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use crate::services::context_pack::{self, ContextPackKoHandler};
+use crate::services::gate::GateKoHandler;
+use crate::services::policy::VerdictKoHandler;
+use crate::services::runlog::RunlogKoHandler;
+use crate::services::snapshot::SnapshotKoHandler;
+use crate::services::specbundle::SpecbundleKoHandler;
+
+/// Workspace directories served as file KOs (`ko://docs/...`, `ko://policies/...`).
+const FILE_NAMESPACES: &[&str] = &["docs", "contracts", "policies"];
+
+static RESOLVER: OnceLock<Arc<KoResolver>> = OnceLock::new();
+
+/// Every namespace this service knows how to dereference.
+pub fn default_resolver(workspace_root: &Path) -> KoResolver {
+    let resolver = KoResolver::new();
+    for ns in FILE_NAMESPACES {
+        resolver.register(Arc::new(FileKoHandler::new(*ns, workspace_root)));
+    }
+    resolver.register(Arc::new(SchemaKoHandler::new(workspace_root)));
+    resolver.register(Arc::new(SpecbundleKoHandler));
+    resolver.register(Arc::new(ContextPackKoHandler));
+    resolver.register(Arc::new(RunlogKoHandler));
+    resolver.register(Arc::new(SnapshotKoHandler));
+    resolver.register(Arc::new(GateKoHandler));
+    resolver.register(Arc::new(VerdictKoHandler));
+    resolver
+}
+
+pub fn resolver() -> Result<Arc<KoResolver>> {
+    if let Some(r) = RESOLVER.get() {
+        return Ok(r.clone());
+    }
+    let r = Arc::new(default_resolver(&context_pack::workspace_root()?));
+    Ok(RESOLVER.get_or_init(|| r).clone())
+}
+
+/// Map a service error onto the resolver's by its `code:` prefix; deleted KOs are not found.
+pub fn ko_error(id: &str, e: anyhow::Error) -> KoError {
+    let message = e.to_string();
+    match message.split(':').next().unwrap_or_default() {
+        code if code.ends_with("not_found") || code.ends_with("_deleted") => KoError::NotFound(id.to_string()),
+        "invalid_ko_id" => KoError::InvalidUri(message),
+        _ => KoError::Handler { id: id.to_string(), message },
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KoResolveReq {
+    pub id: String,
+}
+
+pub struct KoService;
+
+impl KoService {
+    /// Handler for op="ko.resolve"
+    pub async fn resolve(req: KoResolveReq) -> Result<ResolvedKo> {
+        Ok(resolver()?.resolve(&req.id).await?)
+    }
+}
@@ -0,0 +1,369 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{Attempt, AttemptOutcome, Task, TaskLease, TaskStatus};
+use crate::services::audit::{self, SYSTEM_ACTOR};
+use crate::services::gate;
+use crate::services::ledger::{self, task_ko, Ledger};
+use crate::services::runlog::{RunlogAppendReq, RunlogEntryIn, RunlogFinalizeReq, RunlogService, RunlogStartReq};
+use crate::services::scheduler::{self, StatusChange};
+
+const DEFAULT_TTL_SECS: u64 = 300;
+const MAX_TTL_SECS: u64 = 3600;
+/// A ready task gains one priority level for every this many seconds it waits, so a steady
+/// stream of urgent work cannot starve the rest of the queue.
+const AGING_SECS: i64 = 600;
+
+fn ttl(secs: Option<u64>) -> Result<time::Duration> {
+    match secs.unwrap_or(DEFAULT_TTL_SECS) {
+        s @ 1..=MAX_TTL_SECS => Ok(time::Duration::seconds(s as i64)),
+        s => Err(anyhow!("bad_request: ttl_secs {s} is not within 1..={MAX_TTL_SECS}")),
+    }
+}
+
+/// The task a claim on `queue_id` gets: highest priority first, counting the levels a task
+/// has gained by waiting (see [`AGING_SECS`]); within a priority the one ready longest, so
+/// work is handed out in the order it became available.
+fn next_ready(l: &Ledger, queue_id: Uuid, now: OffsetDateTime) -> Option<Uuid> {
+    let effective = |t: &Task| {
+        let waited = (now - t.ready_since.unwrap_or(t.created_at)).whole_seconds().max(0);
+        i64::from(t.priority) + waited / AGING_SECS
+    };
+    l.tasks
+        .iter()
+        .filter(|t| t.queue_id == queue_id && t.status == TaskStatus::Ready && t.lease.is_none())
+        .min_by_key(|t| (Reverse(effective(t)), t.ready_since.unwrap_or(t.created_at), t.created_at))
+        .map(|t| t.id)
+}
+
+/// The task `lease_id` holds, if the lease is still live.
+fn leased(l: &mut Ledger, task_id: Uuid, lease_id: Uuid, now: OffsetDateTime) -> Result<&mut Task> {
+    let task = l.task_mut(task_id).ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+    match &task.lease {
+        Some(lease) if lease.lease_id == lease_id && lease.expires_at > now => Ok(task),
+        _ => Err(anyhow!("lease_lost: task {task_id} is no longer held by lease {lease_id}")),
+    }
+}
+
+/// Drop leases that ran out and put their tasks back in the queue. A task keeps its
+/// `ready_since`, so it is claimed again ahead of tasks that became ready after it.
+fn expire(l: &mut Ledger, now: OffsetDateTime) -> (Vec<(Uuid, TaskLease)>, Vec<StatusChange>) {
+    let mut expired = Vec::new();
+    let mut changes = Vec::new();
+    for t in l.tasks.iter_mut() {
+        let Some(lease) = t.lease.take_if(|x| x.expires_at <= now) else { continue };
+        if t.status == TaskStatus::Running {
+            t.status = TaskStatus::Ready;
+            changes.push(StatusChange { task_id: t.id, from: TaskStatus::Running, to: TaskStatus::Ready, reason: None });
+        }
+        expired.push((t.id, lease));
+    }
+    // Ready again only if its predecessors still hold
+    changes.extend(scheduler::refresh(l));
+    (expired, changes)
+}
+
+/// Close an attempt with a last runlog line saying why.
+async fn close_attempt(task_id: Uuid, n: i32, outcome: AttemptOutcome, note: Option<(&str, String)>, confidence: Option<f32>, actor: &str) -> Result<Attempt> {
+    if let Some((level, msg)) = note {
+        let entry = RunlogEntryIn { ts: None, msg, level: Some(level.to_string()) };
+        match RunlogService::append(RunlogAppendReq { task_id, attempt: n, entries: vec![entry] }).await {
+            Err(e) if !e.to_string().starts_with("runlog_finalized:") => return Err(e),
+            _ => {}
+        }
+    }
+    let req = RunlogFinalizeReq { task_id, attempt: n, confidence, outcome: Some(outcome), actor: Some(actor.to_string()) };
+    RunlogService::close(req).await
+}
+
+/// The task as it is now; closing the attempt may have changed it (policy).
+async fn current(project_id: Uuid, task: Task) -> Result<Task> {
+    let ledger = ledger::store().await?.get(project_id).await;
+    Ok(ledger.and_then(|l| l.task(task.id).cloned()).unwrap_or(task))
+}
+
+/// Expire the project's overdue leases. Returns how many there were. The leases are gone
+/// once the ledger is updated, so a lease whose attempt cannot be closed is logged and
+/// audited with the error rather than stopping the others.
+pub async fn expire_project(project_id: Uuid) -> Result<usize> {
+    let store = ledger::store().await?;
+    let now = OffsetDateTime::now_utc();
+    let overdue = |l: &Ledger| l.tasks.iter().any(|t| t.lease.as_ref().is_some_and(|x| x.expires_at <= now));
+    if !store.get(project_id).await.is_some_and(|l| overdue(&l)) {
+        return Ok(0);
+    }
+    let (expired, changes) = store.update(project_id, |l| Ok(expire(l, now))).await?;
+    for (task_id, lease) in &expired {
+        let note = format!("lease {} held by {} expired", lease.lease_id, lease.agent);
+        let mut detail = serde_json::json!({ "lease_id": lease.lease_id, "agent": lease.agent, "attempt": lease.attempt });
+        if let Err(e) = close_attempt(*task_id, lease.attempt, AttemptOutcome::Expired, Some(("warn", note)), None, SYSTEM_ACTOR).await {
+            tracing::warn!(%task_id, attempt = lease.attempt, "lease expiry: attempt not closed: {e}");
+            detail["close_error"] = e.to_string().into();
+        }
+        audit::record_committed(SYSTEM_ACTOR, "LEASE.EXPIRED", Some(&task_ko(*task_id)), detail).await;
+    }
+    scheduler::record_changes(SYSTEM_ACTOR, &changes, Some("lease expired")).await;
+    Ok(expired.len())
+}
+
+/// Sweep every project for expired leases every `interval`. Claims also sweep their own
+/// project first, so this only bounds how long an abandoned task sits unclaimed.
+pub fn spawn_expiry(interval: Duration) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let store = match ledger::store().await {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("lease expiry: no ledger store: {e}");
+                    continue;
+                }
+            };
+            for project_id in store.project_ids().await {
+                match expire_project(project_id).await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!(%project_id, "lease expiry: {n} task(s) back in their queue"),
+                    Err(e) => tracing::warn!(%project_id, "lease expiry failed: {e}"),
+                }
+            }
+        }
+    });
+}
+
+/// `LEASE_SWEEP_SECS` (default 5; 0 disables the sweeper).
+pub fn expiry_from_env() -> Option<Duration> {
+    let secs = std::env::var("LEASE_SWEEP_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(5u64);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueClaimReq {
+    pub queue_id: Uuid,
+    /// The claiming agent; recorded on the lease and as the actor.
+    pub agent: String,
+    /// Lease length; 300 when absent, at most 3600.
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaseGrant {
+    pub task: Task,
+    pub lease: TaskLease,
+    /// Opened for this claim; stream output to its runlog.
+    pub attempt: Attempt,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaseHeartbeatReq {
+    pub task_id: Uuid,
+    pub lease_id: Uuid,
+    /// New lease length from now; 300 when absent.
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaseCompleteReq {
+    pub task_id: Uuid,
+    pub lease_id: Uuid,
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaseFailReq {
+    pub task_id: Uuid,
+    pub lease_id: Uuid,
+    pub reason: Option<String>,
+    /// Reopen the task right away so it can be claimed again.
+    #[serde(default)]
+    pub requeue: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaseDoneResp {
+    pub task: Task,
+    pub attempt: Attempt,
+    pub changes: Vec<StatusChange>,
+}
+
+pub struct LeaseService;
+
+impl LeaseService {
+    /// Handler for op="queue.claim": lease the next ready task of a queue, or null when
+    /// there is none.
+    pub async fn claim(req: QueueClaimReq) -> Result<Option<LeaseGrant>> {
+        let agent = req.agent.trim().to_string();
+        if agent.is_empty() {
+            return Err(anyhow!("bad_request: agent is required"));
+        }
+        let ttl = ttl(req.ttl_secs)?;
+        let store = ledger::store().await?;
+        let project_id = store.project_of_queue(req.queue_id).await.ok_or_else(|| anyhow!("queue_not_found: {}", req.queue_id))?;
+        expire_project(project_id).await?;
+
+        let now = OffsetDateTime::now_utc();
+        let lease = TaskLease { lease_id: Uuid::new_v4(), agent: agent.clone(), attempt: 0, claimed_at: now, expires_at: now + ttl };
+        let l2 = lease.clone();
+        let claimed = store
+            .update(project_id, move |l| {
+                let Some(task_id) = next_ready(l, req.queue_id, now) else { return Ok(None) };
+                let changes = scheduler::transition(l, task_id, TaskStatus::Running)?;
+                let task = l.task_mut(task_id).ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+                task.lease = Some(l2);
+                Ok(Some((task_id, changes)))
+            })
+            .await?;
+        let Some((task_id, changes)) = claimed else { return Ok(None) };
+
+        let attempt = match RunlogService::start(RunlogStartReq { task_id, actor: Some(agent.clone()) }).await {
+            Ok(a) => a,
+            Err(e) => {
+                // Hand the task back rather than leave it held by a lease with no attempt
+                let lease_id = lease.lease_id;
+                store
+                    .update(project_id, |l| {
+                        let task = leased(l, task_id, lease_id, now)?;
+                        task.lease = None;
+                        task.status = TaskStatus::Ready;
+                        Ok(())
+                    })
+                    .await?;
+                return Err(e);
+            }
+        };
+        let task = store
+            .update(project_id, |l| {
+                let task = leased(l, task_id, lease.lease_id, OffsetDateTime::now_utc())?;
+                if let Some(x) = task.lease.as_mut() {
+                    x.attempt = attempt.n;
+                }
+                Ok(task.clone())
+            })
+            .await?;
+        let lease = task.lease.clone().ok_or_else(|| anyhow!("lease_lost: task {task_id}"))?;
+        let detail = serde_json::json!({ "lease_id": lease.lease_id, "attempt": lease.attempt, "expires_at": lease.expires_at.format(&Rfc3339)? });
+        audit::record_committed(&agent, "LEASE.CLAIMED", Some(&task_ko(task_id)), detail).await;
+        scheduler::record_changes(&agent, &changes, Some("claimed")).await;
+        Ok(Some(LeaseGrant { task, lease, attempt }))
+    }
+
+    /// Handler for op="lease.heartbeat": extend a live lease.
+    pub async fn heartbeat(req: LeaseHeartbeatReq) -> Result<TaskLease> {
+        let ttl = ttl(req.ttl_secs)?;
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(req.task_id).await.ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        store
+            .update(project_id, |l| {
+                let now = OffsetDateTime::now_utc();
+                let task = leased(l, req.task_id, req.lease_id, now)?;
+                let lease = task.lease.as_mut().ok_or_else(|| anyhow!("lease_lost: task {}", req.task_id))?;
+                lease.expires_at = now + ttl;
+                Ok(lease.clone())
+            })
+            .await
+    }
+
+    /// Finish a leased task: `Done`, or `Failed` and possibly reopened.
+    async fn finish(task_id: Uuid, lease_id: Uuid, to: TaskStatus, requeue: bool) -> Result<(Uuid, TaskLease, Task, Vec<StatusChange>)> {
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(task_id).await.ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+        let (lease, task, changes) = store
+            .update(project_id, |l| {
+                let lease = leased(l, task_id, lease_id, OffsetDateTime::now_utc())?.lease.clone();
+                let mut changes = scheduler::transition(l, task_id, to)?;
+                if requeue {
+                    changes.extend(scheduler::transition(l, task_id, TaskStatus::Pending)?);
+                }
+                let task = l.task(task_id).cloned().ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+                Ok((lease, task, changes))
+            })
+            .await?;
+        let lease = lease.ok_or_else(|| anyhow!("lease_lost: task {task_id}"))?;
+        Ok((project_id, lease, task, changes))
+    }
+
+    /// Handler for op="lease.complete": the task is done; successors may become ready. The
+    /// attempt is closed, and its policy evaluated, while the task is still running, so a
+    /// hold lands before successors can be claimed and a denial fails the task instead.
+    pub async fn complete(req: LeaseCompleteReq) -> Result<LeaseDoneResp> {
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(req.task_id).await.ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        let mut l = store.get(project_id).await.ok_or_else(|| anyhow!("project_not_found: {project_id}"))?;
+        let lease = leased(&mut l, req.task_id, req.lease_id, OffsetDateTime::now_utc())?.lease.clone();
+        let lease = lease.ok_or_else(|| anyhow!("lease_lost: task {}", req.task_id))?;
+        let attempt = close_attempt(req.task_id, lease.attempt, AttemptOutcome::Completed, None, req.confidence, &lease.agent).await?;
+        let task = l.task(req.task_id).cloned().ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        let task = current(project_id, task).await?;
+        if task.status == TaskStatus::Failed {
+            // Policy denied the attempt and recorded why; there is nothing left to finish
+            return Ok(LeaseDoneResp { task, attempt, changes: Vec::new() });
+        }
+        let (project_id, lease, task, changes) = Self::finish(req.task_id, req.lease_id, TaskStatus::Done, false).await?;
+        let detail = serde_json::json!({ "lease_id": lease.lease_id, "attempt": lease.attempt, "confidence": req.confidence });
+        audit::record_committed(&lease.agent, "LEASE.COMPLETED", Some(&task_ko(req.task_id)), detail).await;
+        scheduler::record_changes(&lease.agent, &changes, Some("completed")).await;
+        gate::after_task_changes(project_id, &lease.agent).await?;
+        Ok(LeaseDoneResp { task: current(project_id, task).await?, attempt, changes })
+    }
+
+    /// Handler for op="lease.fail"
+    pub async fn fail(req: LeaseFailReq) -> Result<LeaseDoneResp> {
+        let (project_id, lease, task, changes) = Self::finish(req.task_id, req.lease_id, TaskStatus::Failed, req.requeue).await?;
+        let note = req.reason.clone().map(|r| ("error", r));
+        let attempt = close_attempt(req.task_id, lease.attempt, AttemptOutcome::Failed, note, None, &lease.agent).await?;
+        let detail = serde_json::json!({ "lease_id": lease.lease_id, "attempt": lease.attempt, "reason": req.reason, "requeue": req.requeue });
+        audit::record_committed(&lease.agent, "LEASE.FAILED", Some(&task_ko(req.task_id)), detail).await;
+        scheduler::record_changes(&lease.agent, &changes, req.reason.as_deref().or(Some("failed"))).await;
+        gate::after_task_changes(project_id, &lease.agent).await?;
+        Ok(LeaseDoneResp { task: current(project_id, task).await?, attempt, changes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing;
+
+    #[test]
+    fn test_claim_order_and_expiry_keeps_place() {
+        let mut l = testing::ledger();
+        let queue = Uuid::new_v4();
+        let t0 = OffsetDateTime::now_utc() - time::Duration::minutes(10);
+        let mut task = |minutes: i64, priority: i32| {
+            let ready_since = Some(t0 + time::Duration::minutes(minutes));
+            let t = Task { queue_id: queue, created_at: t0, priority, ready_since, ..testing::task(None, TaskStatus::Ready, vec![]) };
+            let id = t.id;
+            l.tasks.push(t);
+            id
+        };
+        let (early, late, urgent) = (task(1, 0), task(2, 0), task(3, 5));
+        let now = OffsetDateTime::now_utc();
+        assert_eq!(next_ready(&l, queue, now), Some(urgent));
+        l.task_mut(urgent).unwrap().status = TaskStatus::Done;
+        assert_eq!(next_ready(&l, queue, now), Some(early));
+
+        // `early` is claimed, its lease runs out and it is first in line again
+        scheduler::transition(&mut l, early, TaskStatus::Running).unwrap();
+        let lease = TaskLease { lease_id: Uuid::new_v4(), agent: "a".into(), attempt: 1, claimed_at: now, expires_at: now };
+        l.task_mut(early).unwrap().lease = Some(lease.clone());
+        assert_eq!(next_ready(&l, queue, now), Some(late));
+        assert!(leased(&mut l, early, lease.lease_id, now).unwrap_err().to_string().starts_with("lease_lost:"));
+        let (expired, changes) = expire(&mut l, now);
+        assert_eq!(expired, [(early, lease)]);
+        assert_eq!(changes, [StatusChange { task_id: early, from: TaskStatus::Running, to: TaskStatus::Ready, reason: None }]);
+        assert_eq!(next_ready(&l, queue, now), Some(early));
+        assert!(ttl(Some(0)).is_err() && ttl(Some(MAX_TTL_SECS + 1)).is_err());
+
+        // An hour on, `early` has waited long enough to go ahead of urgent work just ready
+        let mut urgent = l.task(late).unwrap().clone();
+        (urgent.id, urgent.priority, urgent.ready_since) = (Uuid::new_v4(), 5, Some(now + time::Duration::hours(1)));
+        let fresh = urgent.id;
+        l.tasks.push(urgent);
+        assert_eq!(next_ready(&l, queue, now), Some(fresh));
+        assert_eq!(next_ready(&l, queue, now + time::Duration::hours(1)), Some(early));
+    }
+}
@@ -0,0 +1,384 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::KoUri;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use time::OffsetDateTime;
+use tokio::fs;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::events::PolicyDecision;
+use crate::models::{Gate, Predecessor, Project, Queue, Task, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::graph;
+use crate::services::policy::{self, PolicyEvent, PolicyOutcome};
+use crate::services::scheduler;
+use crate::services::storage::genesis_home;
+use crate::services::util::replace_file;
+
+static LEDGERS: OnceLock<Arc<LedgerStore>> = OnceLock::new();
+
+/// Install the store opened at startup (see `main`).
+pub fn init_store(store: Arc<LedgerStore>) {
+    if LEDGERS.set(store).is_err() {
+        tracing::warn!("ledger store already initialised; keeping the first one");
+    }
+}
+
+pub async fn store() -> Result<Arc<LedgerStore>> {
+    if let Some(s) = LEDGERS.get() {
+        return Ok(s.clone());
+    }
+    let s = Arc::new(LedgerStore::open(genesis_home()?.join("ledger")).await?);
+    Ok(LEDGERS.get_or_init(|| s).clone())
+}
+
+pub fn project_ko(project_id: Uuid) -> String {
+    format!("ko:project/{project_id}")
+}
+
+pub fn task_ko(task_id: Uuid) -> String {
+    format!("ko:task/{task_id}")
+}
+
+/// Orchestration state of one project: everything a scheduler needs to pick up where it
+/// left off. Attempts live with their runlogs (see `runlog`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ledger {
+    pub project: Project,
+    #[serde(default)]
+    pub queues: Vec<Queue>,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub gates: Vec<Gate>,
+    /// Policy decisions still waiting on a reviewer.
+    #[serde(default)]
+    pub pending_decisions: Vec<PolicyDecision>,
+    /// Bumped on every change, so periodic snapshots can skip idle projects.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl Ledger {
+    pub fn new(project: Project) -> Self {
+        Self { project, queues: Vec::new(), tasks: Vec::new(), gates: Vec::new(), pending_decisions: Vec::new(), revision: 0 }
+    }
+
+    pub fn queue(&self, queue_id: Uuid) -> Option<&Queue> {
+        self.queues.iter().find(|q| q.id == queue_id)
+    }
+
+    pub fn task(&self, task_id: Uuid) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.id == task_id)
+    }
+
+    pub fn task_mut(&mut self, task_id: Uuid) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|t| t.id == task_id)
+    }
+
+    pub fn gate(&self, gate_id: Uuid) -> Option<&Gate> {
+        self.gates.iter().find(|g| g.id == gate_id)
+    }
+
+    pub fn gate_mut(&mut self, gate_id: Uuid) -> Option<&mut Gate> {
+        self.gates.iter_mut().find(|g| g.id == gate_id)
+    }
+
+    /// Whether a policy decision on the task is waiting on a reviewer. A held task is not
+    /// scheduled, and its work does not count until the decision is made.
+    pub fn held(&self, task_id: Uuid) -> bool {
+        let ko_id = task_ko(task_id);
+        self.pending_decisions.iter().any(|d| d.task_id == ko_id)
+    }
+
+    /// Whether a plan key is taken by a task or a gate.
+    pub fn key_in_use(&self, key: &str) -> bool {
+        self.tasks.iter().any(|t| t.key.as_deref() == Some(key)) || self.gates.iter().any(|g| g.key.as_deref() == Some(key))
+    }
+}
+
+/// Per-project ledgers, held in memory and written through to `<dir>/<project_id>.json`.
+pub struct LedgerStore {
+    dir: PathBuf,
+    ledgers: Mutex<BTreeMap<Uuid, Ledger>>,
+}
+
+impl LedgerStore {
+    /// Load every `<project_id>.json` under `dir`. An unreadable file is skipped with a warning
+    /// and loses that project only.
+    pub async fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).await?;
+        let mut ledgers = BTreeMap::new();
+        let mut rd = fs::read_dir(&dir).await?;
+        while let Some(item) = rd.next_entry().await? {
+            let path = item.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let parsed = fs::read(&path)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|raw| Ok(serde_json::from_slice::<Ledger>(&raw)?));
+            match parsed {
+                Ok(ledger) => {
+                    ledgers.insert(ledger.project.id, ledger);
+                }
+                Err(e) => tracing::warn!("ledger store: skipping unreadable {}: {e}", path.display()),
+            }
+        }
+        Ok(Self { dir, ledgers: Mutex::new(ledgers) })
+    }
+
+    async fn persist(&self, ledger: &Ledger) -> Result<()> {
+        replace_file(&self.dir.join(format!("{}.json", ledger.project.id)), &serde_json::to_vec_pretty(ledger)?).await
+    }
+
+    pub async fn get(&self, project_id: Uuid) -> Option<Ledger> {
+        self.ledgers.lock().await.get(&project_id).cloned()
+    }
+
+    pub async fn project_ids(&self) -> Vec<Uuid> {
+        self.ledgers.lock().await.keys().copied().collect()
+    }
+
+    /// Add a new project's ledger; fails if the project already has one.
+    pub async fn create(&self, ledger: Ledger) -> Result<()> {
+        let mut ledgers = self.ledgers.lock().await;
+        if ledgers.contains_key(&ledger.project.id) {
+            return Err(anyhow!("project_exists: {}", ledger.project.id));
+        }
+        self.persist(&ledger).await?;
+        ledgers.insert(ledger.project.id, ledger);
+        Ok(())
+    }
+
+    /// Apply `f` to a copy of the project's ledger and keep it only if `f` succeeds and the
+    /// result is written out.
+    pub async fn update<T>(&self, project_id: Uuid, f: impl FnOnce(&mut Ledger) -> Result<T>) -> Result<T> {
+        let mut ledgers = self.ledgers.lock().await;
+        let mut ledger = ledgers.get(&project_id).cloned().ok_or_else(|| anyhow!("project_not_found: {project_id}"))?;
+        let out = f(&mut ledger)?;
+        ledger.revision += 1;
+        self.persist(&ledger).await?;
+        ledgers.insert(project_id, ledger);
+        Ok(out)
+    }
+
+    /// Create or overwrite a ledger wholesale (snapshot restore). The revision continues from
+    /// the one being replaced so the change is never mistaken for an older state.
+    pub async fn replace(&self, mut ledger: Ledger) -> Result<u64> {
+        let mut ledgers = self.ledgers.lock().await;
+        if let Some(old) = ledgers.get(&ledger.project.id) {
+            ledger.revision = ledger.revision.max(old.revision) + 1;
+        }
+        self.persist(&ledger).await?;
+        let revision = ledger.revision;
+        ledgers.insert(ledger.project.id, ledger);
+        Ok(revision)
+    }
+
+    pub async fn project_of_queue(&self, queue_id: Uuid) -> Option<Uuid> {
+        self.ledgers.lock().await.values().find(|l| l.queue(queue_id).is_some()).map(|l| l.project.id)
+    }
+
+    pub async fn project_of_task(&self, task_id: Uuid) -> Option<Uuid> {
+        self.ledgers.lock().await.values().find(|l| l.task(task_id).is_some()).map(|l| l.project.id)
+    }
+
+    pub async fn project_of_gate(&self, gate_id: Uuid) -> Option<Uuid> {
+        self.ledgers.lock().await.values().find(|l| l.gate(gate_id).is_some()).map(|l| l.project.id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectCreateReq {
+    pub name: String,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectGetReq {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueCreateReq {
+    pub project_id: Uuid,
+    pub name: String,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskCreateReq {
+    pub queue_id: Uuid,
+    pub kind: String,
+    #[serde(default)]
+    pub ko_refs: Vec<String>,
+    /// Plan identifier such as `TASK-A1`; unique within the project.
+    pub key: Option<String>,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub predecessors: Vec<Predecessor>,
+    #[serde(default)]
+    pub deliverables: Vec<String>,
+    pub validation: Option<String>,
+    #[serde(default)]
+    pub priority: i32,
+    pub actor: Option<String>,
+}
+
+pub struct LedgerService;
+
+impl LedgerService {
+    /// Handler for op="project.create"
+    pub async fn project_create(req: ProjectCreateReq) -> Result<Project> {
+        if req.name.trim().is_empty() {
+            return Err(anyhow!("bad_request: name is required"));
+        }
+        let project = Project { id: Uuid::new_v4(), name: req.name.trim().to_string(), created_at: OffsetDateTime::now_utc() };
+        store().await?.create(Ledger::new(project.clone())).await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        audit::record_committed(actor, "PROJECT.CREATED", Some(&project_ko(project.id)), serde_json::json!({ "name": project.name })).await;
+        Ok(project)
+    }
+
+    /// Handler for op="project.get": the project's whole ledger.
+    pub async fn project_get(req: ProjectGetReq) -> Result<Ledger> {
+        store().await?.get(req.project_id).await.ok_or_else(|| anyhow!("project_not_found: {}", req.project_id))
+    }
+
+    /// Handler for op="queue.create"
+    pub async fn queue_create(req: QueueCreateReq) -> Result<Queue> {
+        let name = req.name.trim().to_string();
+        if name.is_empty() {
+            return Err(anyhow!("bad_request: name is required"));
+        }
+        let queue = Queue { id: Uuid::new_v4(), project_id: req.project_id, name };
+        let q = queue.clone();
+        store()
+            .await?
+            .update(req.project_id, move |l| {
+                if l.queues.iter().any(|x| x.name == q.name) {
+                    return Err(anyhow!("queue_exists: '{}' in project {}", q.name, q.project_id));
+                }
+                l.queues.push(q);
+                Ok(())
+            })
+            .await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "queue_id": queue.id, "name": queue.name });
+        audit::record_committed(actor, "QUEUE.CREATED", Some(&project_ko(req.project_id)), detail).await;
+        Ok(queue)
+    }
+
+    /// Handler for op="task.create". `task.created` policy rules may refuse the task or hold
+    /// it for review.
+    pub async fn task_create(req: TaskCreateReq) -> Result<Task> {
+        if req.kind.trim().is_empty() {
+            return Err(anyhow!("bad_request: kind is required"));
+        }
+        let store = store().await?;
+        let project_id = store.project_of_queue(req.queue_id).await.ok_or_else(|| anyhow!("queue_not_found: {}", req.queue_id))?;
+        let task = Task {
+            id: Uuid::new_v4(),
+            queue_id: req.queue_id,
+            kind: req.kind.trim().to_string(),
+            ko_refs: req.ko_refs.iter().map(|r| KoUri::canonical(r)).collect(),
+            created_at: OffsetDateTime::now_utc(),
+            key: req.key.map(|k| k.trim().to_string()).filter(|k| !k.is_empty()),
+            title: req.title,
+            predecessors: req.predecessors,
+            status: TaskStatus::Pending,
+            deliverables: req.deliverables,
+            validation: req.validation,
+            priority: req.priority,
+            ready_since: None,
+            lease: None,
+        };
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let verdict = policy::evaluate(&policy::rules().await?, PolicyEvent::TaskCreated, task_ko(task.id), policy::task_facts(project_id, &task));
+        if let Some(v) = &verdict
+            && v.outcome == PolicyOutcome::Deny
+        {
+            policy::publish(actor, v).await?;
+            return Err(anyhow!("policy_denied: {} (see {})", v.summary(), v.ko_id));
+        }
+        let hold = verdict.as_ref().filter(|v| v.outcome == PolicyOutcome::RequireReview).map(|v| v.decision());
+        let (task, changes) = store
+            .update(project_id, move |l| {
+                if let Some(key) = &task.key
+                    && l.key_in_use(key)
+                {
+                    return Err(anyhow!("task_exists: '{key}' in project {project_id}"));
+                }
+                scheduler::check_predecessors(l, task.id, &task.predecessors)?;
+                let id = task.id;
+                l.tasks.push(task);
+                l.pending_decisions.extend(hold);
+                let changes = scheduler::refresh(l);
+                let task = l.task(id).cloned().ok_or_else(|| anyhow!("task_not_found: {id}"))?;
+                Ok((task, changes))
+            })
+            .await?;
+        let ko_id = task_ko(task.id);
+        let detail = serde_json::json!({
+            "project_id": project_id,
+            "queue_id": task.queue_id,
+            "kind": task.kind,
+            "key": task.key,
+            "predecessors": task.predecessors,
+        });
+        audit::record_committed(actor, "TASK.CREATED", Some(&ko_id), detail).await;
+        if let Some(v) = &verdict {
+            policy::publish(actor, v).await?;
+        }
+        scheduler::record_changes(actor, &changes, Some("created")).await;
+        let edges = task.ko_refs.iter().map(|r| (ko_id.clone(), EdgeKind::References, r.clone()));
+        graph::record_committed(actor, "task.create", edges).await;
+        Ok(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_is_all_or_nothing_and_reopens() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = LedgerStore::open(tmp.path()).await.unwrap();
+        let project = Project { id: Uuid::new_v4(), name: "p".into(), created_at: OffsetDateTime::now_utc() };
+        store.create(Ledger::new(project.clone())).await.unwrap();
+        assert!(store.create(Ledger::new(project.clone())).await.is_err());
+
+        let queue = Queue { id: Uuid::new_v4(), project_id: project.id, name: "build".into() };
+        store
+            .update(project.id, |l| {
+                l.queues.push(queue.clone());
+                Ok(())
+            })
+            .await
+            .unwrap();
+        let failed = store
+            .update(project.id, |l| {
+                l.queues.clear();
+                Err::<(), _>(anyhow!("nope"))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(store.get(project.id).await.unwrap().queues.len(), 1);
+        assert_eq!(store.project_of_queue(queue.id).await, Some(project.id));
+
+        std::fs::write(tmp.path().join(format!("{}.json", Uuid::new_v4())), "{\"project\":").unwrap();
+        let reopened = LedgerStore::open(tmp.path()).await.unwrap();
+        assert_eq!(reopened.project_ids().await, [project.id]);
+        let ledger = reopened.get(project.id).await.unwrap();
+        assert_eq!((ledger.revision, ledger.queues[0].name.as_str()), (1, "build"));
+        assert_eq!(reopened.replace(Ledger::new(project)).await.unwrap(), 2);
+    }
+}
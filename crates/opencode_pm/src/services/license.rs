@@ -0,0 +1,347 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use tokio::fs;
+
+use crate::services::audit;
+use crate::services::context_pack;
+
+/// Attachments are scanned up to this size; headers and license files are well within it.
+pub const MAX_SCAN_BYTES: usize = 1 << 20;
+
+static SPDX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)SPDX-License-Identifier:\s*(.+)").unwrap());
+
+/// Telltale sentences of common license texts, matched against `normalize`d text. BSD-3 comes
+/// before BSD-2 so a text is only reported under the more specific one.
+static TEXTS: LazyLock<Vec<(&str, Regex)>> = LazyLock::new(|| {
+    [
+        ("AGPL-3.0", r"gnu affero general public license"),
+        ("LGPL-2.1", r"gnu (?:lesser|library) general public license.{0,200}?version 2\.1"),
+        ("LGPL-3.0", r"gnu lesser general public license.{0,200}?version 3"),
+        ("GPL-2.0", r"gnu general public license.{0,200}?version 2(?:\.0)?(?:[^.\d]|\.[^\d]|$)"),
+        ("GPL-3.0", r"gnu general public license.{0,200}?version 3"),
+        ("SSPL-1.0", r"server side public license"),
+        ("MPL-2.0", r"mozilla public license,? (?:v\. ?|version )2\.0"),
+        ("Apache-2.0", r"apache license,? version 2\.0"),
+        ("MIT", r"permission is hereby granted, free of charge, to any person obtaining a copy"),
+        ("BSD-3-Clause", r"redistribution and use in source and binary forms.{0,1500}?neither the name of"),
+        ("BSD-2-Clause", r"redistribution and use in source and binary forms, with or without modification, are permitted"),
+        ("ISC", r"permission to use, copy, modify, and/?or distribute this software for any purpose with or without fee is hereby granted"),
+        ("CC0-1.0", r"cc0 1\.0 universal"),
+        ("Unlicense", r"this is free and unencumbered software released into the public domain"),
+    ]
+    .into_iter()
+    .map(|(id, re)| (id, Regex::new(re).unwrap()))
+    .collect()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseAction {
+    /// Accept the content and record the finding.
+    #[default]
+    Flag,
+    Reject,
+}
+
+/// The `licenses` section of `policies/licenses.yml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicensePolicy {
+    pub allow: Vec<String>,
+    /// Always rejected.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// What happens to licenses on neither list.
+    #[serde(default)]
+    pub unlisted: LicenseAction,
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    licenses: LicensePolicy,
+}
+
+/// Worst last, so an `AND` takes the maximum and an `OR` the minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseStatus {
+    Allowed,
+    Unlisted,
+    Denied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Evidence {
+    /// An `SPDX-License-Identifier` header.
+    Spdx,
+    /// Wording of a known license text.
+    Text,
+    /// The item could not be read, so its license is unknown (`NOASSERTION`).
+    Unscanned,
+}
+
+/// A license found in an item that is not on the allowlist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseFinding {
+    /// What was scanned, e.g. `part 3`.
+    pub item: String,
+    /// SPDX id or expression.
+    pub license: String,
+    pub evidence: Evidence,
+    pub status: LicenseStatus,
+    /// Whether the policy refuses the item for it.
+    pub rejected: bool,
+}
+
+impl LicenseFinding {
+    fn summary(&self) -> String {
+        if self.evidence == Evidence::Unscanned {
+            return format!("{} could not be scanned, so its license is unknown", self.item);
+        }
+        let status = if self.status == LicenseStatus::Denied { "denied" } else { "not on the allowlist" };
+        let evidence = if self.evidence == Evidence::Spdx { "SPDX header" } else { "license text" };
+        format!("{} is under {} ({evidence}), which is {status}", self.item, self.license)
+    }
+}
+
+/// `GPL-3.0-or-later`, `gpl-3.0+` and `GPL-3.0-only` all name `gpl-3.0`.
+fn base_id(id: &str) -> String {
+    let id = id.trim().to_ascii_lowercase();
+    let id = id.strip_suffix('+').unwrap_or(&id);
+    let id = id.strip_suffix("-or-later").or_else(|| id.strip_suffix("-only")).unwrap_or(id);
+    id.to_string()
+}
+
+impl LicensePolicy {
+    fn id_status(&self, id: &str) -> LicenseStatus {
+        let listed = |list: &[String]| list.iter().any(|l| base_id(l) == base_id(id));
+        if listed(&self.deny) {
+            LicenseStatus::Denied
+        } else if listed(&self.allow) {
+            LicenseStatus::Allowed
+        } else {
+            LicenseStatus::Unlisted
+        }
+    }
+
+    /// Status of an SPDX expression: `OR` takes the best alternative, `AND` the worst part;
+    /// `WITH` exceptions do not change the license. Anything unparsable is unlisted.
+    pub fn status(&self, expr: &str) -> LicenseStatus {
+        let spaced = expr.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut pos = 0;
+        match self.or_expr(&tokens, &mut pos) {
+            Some(s) if pos == tokens.len() => s,
+            _ => LicenseStatus::Unlisted,
+        }
+    }
+
+    fn or_expr(&self, t: &[&str], pos: &mut usize) -> Option<LicenseStatus> {
+        let mut s = self.and_expr(t, pos)?;
+        while t.get(*pos).is_some_and(|x| x.eq_ignore_ascii_case("or")) {
+            *pos += 1;
+            s = s.min(self.and_expr(t, pos)?);
+        }
+        Some(s)
+    }
+
+    fn and_expr(&self, t: &[&str], pos: &mut usize) -> Option<LicenseStatus> {
+        let mut s = self.atom(t, pos)?;
+        while t.get(*pos).is_some_and(|x| x.eq_ignore_ascii_case("and")) {
+            *pos += 1;
+            s = s.max(self.atom(t, pos)?);
+        }
+        Some(s)
+    }
+
+    fn atom(&self, t: &[&str], pos: &mut usize) -> Option<LicenseStatus> {
+        let tok = *t.get(*pos)?;
+        *pos += 1;
+        if tok == "(" {
+            let s = self.or_expr(t, pos)?;
+            (t.get(*pos) == Some(&")")).then(|| *pos += 1)?;
+            return Some(s);
+        }
+        if tok == ")" || ["and", "or", "with"].iter().any(|k| tok.eq_ignore_ascii_case(k)) {
+            return None;
+        }
+        if t.get(*pos).is_some_and(|x| x.eq_ignore_ascii_case("with")) {
+            t.get(*pos + 1)?;
+            *pos += 2;
+        }
+        Some(self.id_status(tok))
+    }
+
+    fn rejects(&self, status: LicenseStatus) -> bool {
+        match status {
+            LicenseStatus::Allowed => false,
+            LicenseStatus::Unlisted => self.unlisted == LicenseAction::Reject,
+            LicenseStatus::Denied => true,
+        }
+    }
+}
+
+pub fn parse_policy(yaml: &str) -> Result<LicensePolicy> {
+    let file: PolicyFile = serde_yaml::from_str(yaml).map_err(|e| anyhow!("policy_invalid: policies/licenses.yml: {e}"))?;
+    Ok(file.licenses)
+}
+
+/// The workspace's policy, read afresh; `None` when there is none.
+pub async fn policy() -> Result<Option<LicensePolicy>> {
+    let path = context_pack::workspace_root()?.join("policies").join("licenses.yml");
+    match fs::read_to_string(&path).await {
+        Ok(yaml) => Ok(Some(parse_policy(&yaml)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Comment markers and layout stripped, lowercased, on one line.
+fn normalize(text: &str) -> String {
+    let lines = text.lines().map(|l| {
+        l.trim_start_matches(|c: char| c.is_whitespace() || "/*#;!-".contains(c))
+            .trim_end_matches(|c: char| c.is_whitespace() || c == '*' || c == '/')
+    });
+    lines.flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Licenses a text declares or contains: SPDX headers as written, known texts by SPDX id.
+pub fn detect(text: &str) -> Vec<(String, Evidence)> {
+    let mut found: Vec<(String, Evidence)> = Vec::new();
+    for cap in SPDX.captures_iter(text) {
+        let expr = cap[1].trim().trim_end_matches("*/").trim_end_matches("-->").trim().to_string();
+        if !expr.is_empty() && !found.iter().any(|(l, _)| *l == expr) {
+            found.push((expr, Evidence::Spdx));
+        }
+    }
+    let norm = normalize(text);
+    for (id, re) in TEXTS.iter() {
+        if *id == "BSD-2-Clause" && found.iter().any(|(l, _)| l == "BSD-3-Clause") {
+            continue;
+        }
+        if re.is_match(&norm) && !found.iter().any(|(l, _)| l == id) {
+            found.push((id.to_string(), Evidence::Text));
+        }
+    }
+    found
+}
+
+/// Licenses in `items` (label, text) that are not on the allowlist. Prose counts only its
+/// SPDX headers: a document that discusses a license is not thereby under it.
+pub fn check(policy: &LicensePolicy, items: &[(String, &str)], prose: bool) -> Vec<LicenseFinding> {
+    let mut findings = Vec::new();
+    for (item, text) in items {
+        for (license, evidence) in detect(text).into_iter().filter(|(_, e)| !prose || *e == Evidence::Spdx) {
+            let status = policy.status(&license);
+            if status != LicenseStatus::Allowed {
+                let rejected = policy.rejects(status);
+                findings.push(LicenseFinding { item: item.clone(), license, evidence, status, rejected });
+            }
+        }
+    }
+    findings
+}
+
+/// An item that could not be read: its license is unknown, so it is treated as unlisted.
+pub fn unscanned(policy: &LicensePolicy, item: &str) -> LicenseFinding {
+    let status = LicenseStatus::Unlisted;
+    LicenseFinding { item: item.to_string(), license: "NOASSERTION".into(), evidence: Evidence::Unscanned, status, rejected: policy.rejects(status) }
+}
+
+/// Apply the workspace policy to code and attached files in `items`, and to the `unscanned`
+/// items that could not be read. Any rejected license refuses the lot with a
+/// `license_rejected` error; flagged findings are returned for the caller to record once
+/// the content has an id (see `record`).
+pub async fn enforce(actor: &str, ko_id: Option<&str>, items: &[(String, &str)], unscanned: &[String]) -> Result<Vec<LicenseFinding>> {
+    let Some(policy) = policy().await? else { return Ok(Vec::new()) };
+    let mut findings = check(&policy, items, false);
+    findings.extend(unscanned.iter().map(|item| self::unscanned(&policy, item)));
+    let rejected = findings.iter().filter(|f| f.rejected).count();
+    let Some(f) = findings.iter().find(|f| f.rejected) else { return Ok(findings) };
+    let first = f.summary();
+    record(actor, ko_id, &findings).await;
+    Err(anyhow!("license_rejected: {first}, {rejected} finding(s) rejected"))
+}
+
+/// `LICENSE.REJECTED` when any finding is rejected, else `LICENSE.FLAGGED`. Recorded with
+/// `audit::record_committed`, as findings are recorded once the decision is made.
+pub async fn record(actor: &str, ko_id: Option<&str>, findings: &[LicenseFinding]) {
+    if findings.is_empty() {
+        return;
+    }
+    let event = if findings.iter().any(|f| f.rejected) { "LICENSE.REJECTED" } else { "LICENSE.FLAGGED" };
+    audit::record_committed(actor, event, ko_id, serde_json::json!({ "findings": findings })).await;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LicenseCheckReq {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LicenseCheckResp {
+    /// False when the workspace has no license policy; nothing was checked then.
+    pub enabled: bool,
+    /// Every license detected, SPDX headers first.
+    pub detected: Vec<String>,
+    /// The detected licenses that are not allowed.
+    pub findings: Vec<LicenseFinding>,
+}
+
+pub struct LicenseService;
+
+impl LicenseService {
+    /// Handler for op="license.check": scan a text without enforcing anything.
+    pub async fn check(req: LicenseCheckReq) -> Result<LicenseCheckResp> {
+        let detected = detect(&req.text).into_iter().map(|(l, _)| l).collect();
+        let Some(policy) = policy().await? else { return Ok(LicenseCheckResp { enabled: false, detected, findings: Vec::new() }) };
+        let findings = check(&policy, &[("text".to_string(), req.text.as_str())], false);
+        Ok(LicenseCheckResp { enabled: true, detected, findings })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expressions_follow_the_policy_file() {
+        let p = parse_policy(include_str!("../../../../policies/licenses.yml")).unwrap();
+        assert_eq!(p.status("MIT"), LicenseStatus::Allowed);
+        assert_eq!(p.status("GPL-3.0-or-later"), LicenseStatus::Denied);
+        assert_eq!(p.status("MIT OR GPL-3.0-only"), LicenseStatus::Allowed);
+        assert_eq!(p.status("(MIT AND GPL-2.0+)"), LicenseStatus::Denied);
+        assert_eq!(p.status("Apache-2.0 WITH LLVM-exception"), LicenseStatus::Allowed);
+        assert_eq!(p.status("MPL-2.0"), LicenseStatus::Unlisted);
+        assert_eq!(p.status("MIT AND"), LicenseStatus::Unlisted);
+        assert!(p.rejects(LicenseStatus::Denied) && !p.rejects(LicenseStatus::Unlisted));
+    }
+
+    #[test]
+    fn test_detects_headers_and_texts() {
+        let code = "// SPDX-License-Identifier: GPL-2.0-only\n/*\n * This program is free software; you can redistribute it under\n \
+            * the terms of the GNU General Public License as published by the Free Software\n * Foundation, version 2.\n */\nfn main() {}\n";
+        assert_eq!(detect(code), [("GPL-2.0-only".to_string(), Evidence::Spdx), ("GPL-2.0".to_string(), Evidence::Text)]);
+        let bsd = "Redistribution and use in source and binary forms, with or without modification, are permitted provided that \
+            ... 3. Neither the name of the copyright holder nor the names of its contributors may be used";
+        assert_eq!(detect(bsd), [("BSD-3-Clause".to_string(), Evidence::Text)]);
+        assert_eq!(detect("/* SPDX-License-Identifier: MIT OR Apache-2.0 */"), [("MIT OR Apache-2.0".to_string(), Evidence::Spdx)]);
+        assert!(detect("fn main() {}").is_empty());
+    }
+
+    #[test]
+    fn test_prose_and_unscanned_items() {
+        let p = parse_policy(include_str!("../../../../policies/licenses.yml")).unwrap();
+        let prose = "We avoid the GNU General Public License, version 3, for anything we ship.";
+        let items = [("part 1".to_string(), prose)];
+        assert_eq!(check(&p, &items, false)[0].license, "GPL-3.0");
+        assert!(check(&p, &items, true).is_empty());
+        let header = [("part 2".to_string(), "<!-- SPDX-License-Identifier: GPL-3.0-only -->\n# Notes")];
+        assert!(check(&p, &header, true)[0].rejected);
+        let f = unscanned(&p, "part 3");
+        assert_eq!((f.status, f.rejected), (LicenseStatus::Unlisted, false));
+        assert_eq!(f.summary(), "part 3 could not be scanned, so its license is unknown");
+    }
+}
@@ -1,3 +1,27 @@
 pub mod specbundle;
+pub mod specpart;
 pub mod model_manager;
-pub mod secrets;
\ No newline at end of file
+pub mod secrets;
+pub mod guardian;
+pub mod storage;
+pub mod archive;
+pub mod render;
+pub mod ingest;
+pub mod audit;
+pub mod context_pack;
+pub mod ko_resolve;
+pub mod graph;
+pub mod runlog;
+pub mod ledger;
+pub mod snapshot;
+pub mod scheduler;
+pub mod gate;
+pub mod plan;
+pub mod task_graph;
+pub mod lease;
+pub mod policy;
+pub mod originality;
+pub mod license;
+pub mod util;
+#[cfg(test)]
+pub mod testing;
\ No newline at end of file
@@ -1,5 +1,13 @@
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::embedding::{EmbedError, Embedder};
+use tokio::sync::OnceCell;
+
+use crate::services::guardian::GuardianService;
+use crate::services::originality::{self, OriginalityFinding};
+use crate::services::audit::SYSTEM_ACTOR;
+use crate::services::secrets::{SecretGetReq, SecretsService};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -19,6 +27,9 @@ pub struct ChatItem {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatBatchReq {
     pub batch: Vec<ChatItem>,
+    /// Selects the Guardian allowlist to apply.
+    #[serde(default)]
+    pub project_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -34,22 +45,261 @@ pub struct ChatBatchItemResp {
     pub provider: Option<String>,
     pub model: Option<String>,
     pub latency_ms: Option<u64>,
+    /// Set when the originality policy flagged the output (`action: flag`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub originality: Option<OriginalityFinding>,
 }
 
 pub struct ModelManagerService;
 
 impl ModelManagerService {
-    pub async fn chat_batch(req: ChatBatchReq) -> Result<ChatBatchResp> {
+    pub async fn chat_batch(mut req: ChatBatchReq) -> Result<ChatBatchResp> {
+        // Nothing leaves for a provider if Guardian finds a secret; PII goes out redacted
+        let guardian = GuardianService::for_project(req.project_id.as_deref()).await?;
+        for it in &mut req.batch {
+            for (i, m) in it.messages.iter_mut().enumerate() {
+                m.content = guardian.check(&format!("session {} message {}", it.session_id, i + 1), &m.content)?;
+            }
+        }
+
         // TEMP: echo response so the UI pipeline is verifiable
-        let results = req.batch.into_iter().map(|it| ChatBatchItemResp {
+        let mut results: Vec<ChatBatchItemResp> = req.batch.into_iter().map(|it| ChatBatchItemResp {
             session_id: it.session_id,
             content: format!("(stub) {} → {} responded to: {}", it.provider, it.model, it.messages.last().map(|m| &m.content).unwrap_or(&"".into())),
             usage_tokens: None,
             provider: Some(it.provider),
             model: Some(it.model),
             latency_ms: Some(25),
+            originality: None,
         }).collect();
 
+        // Outputs that repeat indexed content are held back (or flagged) like any other
+        let items: Vec<(String, &str)> = results.iter().map(|r| (format!("session {}", r.session_id), r.content.as_str())).collect();
+        let flagged = originality::enforce(SYSTEM_ACTOR, None, &items).await?;
+        originality::record_flagged(SYSTEM_ACTOR, None, &flagged).await;
+        for f in flagged {
+            if let Some(r) = results.iter_mut().find(|r| f.item == format!("session {}", r.session_id)) {
+                r.originality = Some(f);
+            }
+        }
+
         Ok(ChatBatchResp { results })
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedReq {
+    pub provider: String,
+    pub model: String,
+    pub texts: Vec<String>,
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedResp {
+    pub vectors: Vec<Vec<f32>>,
+    pub model: String,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbeddings {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// OpenAI-compatible base URL; `EMBEDDINGS_BASE_URL` overrides the provider default.
+fn embeddings_base(provider: &str) -> Result<String> {
+    if let Ok(base) = std::env::var("EMBEDDINGS_BASE_URL") {
+        return Ok(base.trim_end_matches('/').to_string());
+    }
+    match provider {
+        "openai" => Ok("https://api.openai.com/v1".into()),
+        "ollama" => Ok("http://127.0.0.1:11434/v1".into()),
+        other => Err(anyhow!("no embeddings endpoint known for provider '{other}'; set EMBEDDINGS_BASE_URL")),
+    }
+}
+
+async fn provider_key(provider: &str) -> Result<Option<String>> {
+    let req = SecretGetReq { provider: provider.into(), account: "default".into(), actor: Some(SYSTEM_ACTOR.into()) };
+    Ok(SecretsService::get(req).await?.key)
+}
+
+impl ModelManagerService {
+    /// Provider key comes from the secret store (account `default`); none is sent if unset.
+    pub async fn embed(req: EmbedReq) -> Result<EmbedResp> {
+        let key = provider_key(&req.provider).await?;
+        Self::embed_with_key(req, key).await
+    }
+
+    async fn embed_with_key(mut req: EmbedReq, key: Option<String>) -> Result<EmbedResp> {
+        // Same rule as chat: secrets are never sent to a provider, PII only redacted
+        let guardian = GuardianService::for_project(req.project_id.as_deref()).await?;
+        for (i, t) in req.texts.iter_mut().enumerate() {
+            *t = guardian.check(&format!("embedding input {}", i + 1), t)?;
+        }
+        Self::request_embeddings(req, key).await
+    }
+
+    /// POST already-checked texts to the provider's embeddings endpoint.
+    async fn request_embeddings(req: EmbedReq, key: Option<String>) -> Result<EmbedResp> {
+        let mut http = reqwest::Client::new()
+            .post(format!("{}/embeddings", embeddings_base(&req.provider)?))
+            .json(&serde_json::json!({ "model": req.model, "input": req.texts }));
+        if let Some(k) = key {
+            http = http.bearer_auth(k);
+        }
+        let resp = http.send().await?.error_for_status()?;
+        let mut body: OpenAiEmbeddings = resp.json().await?;
+        body.data.sort_by_key(|d| d.index);
+        Ok(EmbedResp { vectors: body.data.into_iter().map(|d| d.embedding).collect(), model: req.model })
+    }
+}
+
+/// Context-index embedder backed by a provider's embeddings endpoint.
+pub struct ProviderEmbedder {
+    provider: String,
+    model: String,
+    // Read once so the index does not log a SECRET.READ per batch
+    key: OnceCell<Option<String>>,
+}
+
+impl ProviderEmbedder {
+    pub fn new(provider: impl Into<String>, model: impl Into<String>) -> Self {
+        Self { provider: provider.into(), model: model.into(), key: OnceCell::new() }
+    }
+}
+
+#[async_trait]
+impl Embedder for ProviderEmbedder {
+    fn id(&self) -> String {
+        format!("{}:{}", self.provider, self.model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> std::result::Result<Vec<Vec<f32>>, EmbedError> {
+        let err = |e: anyhow::Error| EmbedError { embedder: self.id(), message: e.to_string(), input: None };
+        // Checked here rather than in `embed_with_key` so a refused input is reported as such and
+        // the index can skip it instead of failing the whole batch
+        let guardian = GuardianService::for_project(None).await.map_err(err)?;
+        let mut texts = texts.to_vec();
+        for (i, t) in texts.iter_mut().enumerate() {
+            *t = guardian
+                .check(&format!("embedding input {}", i + 1), t)
+                .map_err(|e| EmbedError { embedder: self.id(), message: e.to_string(), input: Some(i) })?;
+        }
+        // Keyless endpoints (e.g. a local ollama) must work where no secret store is available
+        let key = self
+            .key
+            .get_or_init(|| async {
+                provider_key(&self.provider).await.unwrap_or_else(|e| {
+                    tracing::warn!("no {} key for embeddings: {e}", self.provider);
+                    None
+                })
+            })
+            .await;
+        let req = EmbedReq {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            texts,
+            project_id: None,
+        };
+        Ok(ModelManagerService::request_embeddings(req, key.clone()).await.map_err(err)?.vectors)
+    }
+}
+
+/// Context-index embedder running a sentence-embedding model on the CPU through ONNX Runtime,
+/// so no text leaves the machine. `dir` holds `model.onnx` and `tokenizer.json` (a
+/// sentence-transformers export such as all-MiniLM-L6-v2); vectors are the mean of the last
+/// hidden state over real tokens, L2-normalized. ONNX Runtime itself is loaded at run time from
+/// `ORT_DYLIB_PATH` (default: `libonnxruntime` on the library path).
+#[cfg(feature = "local-embeddings")]
+pub struct LocalEmbedder {
+    id: String,
+    session: std::sync::Arc<parking_lot::Mutex<ort::session::Session>>,
+    tokenizer: std::sync::Arc<tokenizers::Tokenizer>,
+}
+
+#[cfg(feature = "local-embeddings")]
+impl LocalEmbedder {
+    /// Longest input in tokens; the rest of a text is cut off.
+    const MAX_TOKENS: usize = 256;
+
+    pub fn open(dir: impl AsRef<std::path::Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let session = ort::session::Session::builder()
+            .and_then(|b| b.commit_from_file(dir.join("model.onnx")))
+            .map_err(|e| anyhow!("local embedder {}: {e}", dir.display()))?;
+        let mut tokenizer = tokenizers::Tokenizer::from_file(dir.join("tokenizer.json"))
+            .map_err(|e| anyhow!("local embedder {}: {e}", dir.display()))?;
+        tokenizer.with_padding(Some(tokenizers::PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(tokenizers::TruncationParams { max_length: Self::MAX_TOKENS, ..Default::default() }))
+            .map_err(|e| anyhow!("local embedder {}: {e}", dir.display()))?;
+        let name = dir.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Self {
+            id: format!("local:{name}"),
+            session: std::sync::Arc::new(parking_lot::Mutex::new(session)),
+            tokenizer: std::sync::Arc::new(tokenizer),
+        })
+    }
+
+    fn embed_blocking(session: &mut ort::session::Session, tokenizer: &tokenizers::Tokenizer, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        use ort::value::Tensor;
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let encodings = tokenizer.encode_batch(texts, true).map_err(|e| anyhow!("tokenize: {e}"))?;
+        let (n, len) = (encodings.len(), encodings[0].len());
+        let flat = |f: fn(&tokenizers::Encoding) -> &[u32]| -> Vec<i64> {
+            encodings.iter().flat_map(|e| f(e).iter().map(|&x| x as i64)).collect()
+        };
+        let mask = flat(tokenizers::Encoding::get_attention_mask);
+        let mut inputs = vec![
+            ("input_ids", Tensor::from_array(([n, len], flat(tokenizers::Encoding::get_ids)))?),
+            ("attention_mask", Tensor::from_array(([n, len], mask.clone()))?),
+        ];
+        // BERT-style exports take segment ids too; others reject unknown inputs
+        if session.inputs.iter().any(|i| i.name == "token_type_ids") {
+            inputs.push(("token_type_ids", Tensor::from_array(([n, len], flat(tokenizers::Encoding::get_type_ids)))?));
+        }
+        let outputs = session.run(inputs)?;
+        let (shape, hidden) = outputs[0].try_extract_tensor::<f32>()?;
+        let dim = *shape.last().ok_or_else(|| anyhow!("model output has no dimensions"))? as usize;
+        let mut vectors = Vec::with_capacity(n);
+        for row in 0..n {
+            let mut v = vec![0.0_f32; dim];
+            let mut count = 0.0_f32;
+            for tok in 0..len {
+                if mask[row * len + tok] == 0 {
+                    continue;
+                }
+                let at = (row * len + tok) * dim;
+                v.iter_mut().zip(&hidden[at..at + dim]).for_each(|(acc, x)| *acc += x);
+                count += 1.0;
+            }
+            v.iter_mut().for_each(|x| *x /= count.max(1.0));
+            opencode_pm_core::embedding::normalize(&mut v);
+            vectors.push(v);
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(feature = "local-embeddings")]
+#[async_trait]
+impl Embedder for LocalEmbedder {
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    async fn embed(&self, texts: &[String]) -> std::result::Result<Vec<Vec<f32>>, EmbedError> {
+        let (session, tokenizer, texts) = (self.session.clone(), self.tokenizer.clone(), texts.to_vec());
+        let run = tokio::task::spawn_blocking(move || Self::embed_blocking(&mut session.lock(), &tokenizer, texts));
+        let result = run.await.map_err(anyhow::Error::from).and_then(|r| r);
+        result.map_err(|e| EmbedError { embedder: self.id(), message: e.to_string(), input: None })
+    }
+}
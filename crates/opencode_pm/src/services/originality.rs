@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::context_index::ContextIndex;
+use opencode_pm_core::originality::{Fingerprint, FingerprintIndex, DEFAULT_SHINGLE_SIZE};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+
+use crate::services::audit;
+use crate::services::context_pack;
+
+/// Matches listed per item; the rest are only counted.
+const MAX_MATCHES: usize = 5;
+
+/// Fingerprints of the corpus and the context index generation they were synced at.
+static FINGERPRINTS: Mutex<Option<(Arc<FingerprintIndex>, Option<u64>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OriginalityAction {
+    /// Refuse the content.
+    #[default]
+    Block,
+    /// Accept it and record the matches.
+    Flag,
+}
+
+/// The `originality` section of `policies/originality.yml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OriginalityPolicy {
+    #[serde(default)]
+    pub check_plagiarism: bool,
+    /// Highest estimated similarity (0.0–1.0) to any indexed text that is still original.
+    pub max_similarity: f32,
+    #[serde(default)]
+    pub action: OriginalityAction,
+    /// Words per shingle; texts shorter than this are not checked.
+    #[serde(default = "default_shingle_size")]
+    pub shingle_size: usize,
+}
+
+fn default_shingle_size() -> usize {
+    DEFAULT_SHINGLE_SIZE
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    originality: OriginalityPolicy,
+}
+
+pub fn parse_policy(yaml: &str) -> Result<OriginalityPolicy> {
+    let file: PolicyFile = serde_yaml::from_str(yaml).map_err(|e| anyhow!("policy_invalid: policies/originality.yml: {e}"))?;
+    let p = file.originality;
+    if !(0.0..=1.0).contains(&p.max_similarity) || p.shingle_size == 0 {
+        return Err(anyhow!("policy_invalid: policies/originality.yml: max_similarity must be within 0..=1 and shingle_size positive"));
+    }
+    Ok(p)
+}
+
+/// The workspace's policy, read afresh; `None` when there is none or it is switched off.
+pub async fn policy() -> Result<Option<OriginalityPolicy>> {
+    let path = context_pack::workspace_root()?.join("policies").join("originality.yml");
+    let yaml = match fs::read_to_string(&path).await {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Some(parse_policy(&yaml)?).filter(|p| p.check_plagiarism))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceMatch {
+    /// Chunk id of an ingested KO (`<ko_id>#<n>`) or a workspace file KO.
+    pub source: String,
+    pub similarity: f32,
+}
+
+/// An item too similar to indexed content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OriginalityFinding {
+    /// What was checked, e.g. `part 2`.
+    pub item: String,
+    pub similarity: f32,
+    pub max_similarity: f32,
+    /// Most similar first.
+    pub matches: Vec<SourceMatch>,
+    /// Matches beyond the ones listed.
+    pub more: usize,
+}
+
+impl OriginalityFinding {
+    fn summary(&self) -> String {
+        let sources: Vec<&str> = self.matches.iter().map(|m| m.source.as_str()).collect();
+        format!("{} is {:.2} similar to {} (max {:.2})", self.item, self.similarity, sources.join(", "), self.max_similarity)
+    }
+}
+
+/// Fingerprints of the ingested corpus for `shingle_size`, started over when the policy
+/// changes it. Synced only when the context index changed since, and then only changed
+/// texts are fingerprinted again. Blocking.
+fn fingerprints(corpus: &ContextIndex, shingle_size: usize) -> Arc<FingerprintIndex> {
+    let lock = || FINGERPRINTS.lock().unwrap_or_else(|e| e.into_inner());
+    let generation = corpus.generation();
+    let index = {
+        let mut slot = lock();
+        match &*slot {
+            Some((index, synced)) if index.shingle_size() == shingle_size && *synced == Some(generation) => return index.clone(),
+            Some((index, _)) if index.shingle_size() == shingle_size => index.clone(),
+            _ => slot.insert((Arc::new(FingerprintIndex::new(shingle_size)), None)).0.clone(),
+        }
+    };
+    index.sync(corpus.ingested_texts());
+    if let Some((current, synced)) = lock().as_mut()
+        && Arc::ptr_eq(current, &index)
+    {
+        *synced = Some(generation);
+    }
+    index
+}
+
+/// Compare `items` (label, text) against the ingested KOs, leaving out `exclude_ko`'s own
+/// texts. Items too short to fingerprint pass. The corpus refresh and the comparison run on
+/// the blocking pool.
+pub async fn check(policy: &OriginalityPolicy, items: &[(String, &str)], exclude_ko: Option<&str>) -> Result<Vec<OriginalityFinding>> {
+    let corpus = context_pack::index()?;
+    corpus.refresh_if_stale_async().await;
+    let policy = policy.clone();
+    let items: Vec<(String, String)> = items.iter().map(|(item, text)| (item.clone(), text.to_string())).collect();
+    let exclude_ko = exclude_ko.map(str::to_string);
+    tokio::task::spawn_blocking(move || compare(&fingerprints(&corpus, policy.shingle_size), &policy, &items, exclude_ko.as_deref()))
+        .await
+        .map_err(|e| anyhow!("originality check failed: {e}"))
+}
+
+fn compare(index: &FingerprintIndex, policy: &OriginalityPolicy, items: &[(String, String)], exclude_ko: Option<&str>) -> Vec<OriginalityFinding> {
+    let mut findings = Vec::new();
+    for (item, text) in items {
+        let Some(fp) = Fingerprint::of(text, policy.shingle_size) else { continue };
+        let mut matches = index.matches(&fp, policy.max_similarity, exclude_ko);
+        // At the threshold is still allowed
+        matches.retain(|(_, s)| *s > policy.max_similarity);
+        let Some(&(_, similarity)) = matches.first() else { continue };
+        let more = matches.len().saturating_sub(MAX_MATCHES);
+        let matches = matches.into_iter().take(MAX_MATCHES).map(|(source, similarity)| SourceMatch { source, similarity }).collect();
+        findings.push(OriginalityFinding { item: item.clone(), similarity, max_similarity: policy.max_similarity, matches, more });
+    }
+    findings
+}
+
+/// Apply the workspace policy to `items`. Blocking refuses the lot with an
+/// `originality_violation` error; flagging returns the findings for the caller to record
+/// once the content has an id (see `record_flagged`).
+pub async fn enforce(actor: &str, ko_id: Option<&str>, items: &[(String, &str)]) -> Result<Vec<OriginalityFinding>> {
+    let Some(policy) = policy().await? else { return Ok(Vec::new()) };
+    let findings = check(&policy, items, ko_id).await?;
+    if findings.is_empty() || policy.action == OriginalityAction::Flag {
+        return Ok(findings);
+    }
+    audit::record(actor, "ORIGINALITY.BLOCKED", ko_id, serde_json::json!({ "findings": findings })).await?;
+    let first = findings[0].summary();
+    Err(anyhow!("originality_violation: {first}, {} item(s) over the limit", findings.len()))
+}
+
+/// Recorded after the content is stored, so a failure is deferred rather than returned.
+pub async fn record_flagged(actor: &str, ko_id: Option<&str>, findings: &[OriginalityFinding]) {
+    if !findings.is_empty() {
+        audit::record_committed(actor, "ORIGINALITY.FLAGGED", ko_id, serde_json::json!({ "findings": findings })).await;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OriginalityCheckReq {
+    pub text: String,
+    /// Leave this KO's own indexed texts out, e.g. when checking a revision of it.
+    pub exclude_ko: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OriginalityCheckResp {
+    /// False when the policy is missing or switched off; nothing was compared then.
+    pub enabled: bool,
+    pub finding: Option<OriginalityFinding>,
+}
+
+pub struct OriginalityService;
+
+impl OriginalityService {
+    /// Handler for op="originality.check": compare a text without enforcing anything.
+    pub async fn check(req: OriginalityCheckReq) -> Result<OriginalityCheckResp> {
+        let Some(policy) = policy().await? else { return Ok(OriginalityCheckResp { enabled: false, finding: None }) };
+        let items = [("text".to_string(), req.text.as_str())];
+        let finding = check(&policy, &items, req.exclude_ko.as_deref()).await?.pop();
+        Ok(OriginalityCheckResp { enabled: true, finding })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_file() {
+        let p = parse_policy(include_str!("../../../../policies/originality.yml")).unwrap();
+        assert!(p.check_plagiarism);
+        assert_eq!((p.max_similarity, p.action, p.shingle_size), (0.15, OriginalityAction::Block, DEFAULT_SHINGLE_SIZE));
+        assert!(parse_policy("originality:\n  max_similarity: 1.5\n").is_err());
+    }
+}
@@ -0,0 +1,530 @@
+use anyhow::{anyhow, Result};
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::KoUri;
+use opencode_pm_core::tes_plan::{self, GateBlock, Plan, PlanBlock, PlanIssue, PlanRef, PlanSection, TaskBlock};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{Gate, GateAction, GateStatus, Predecessor, Queue, Task, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::gate::{self, gate_ko};
+use crate::services::graph;
+use crate::services::ledger::{self, project_ko, task_ko, Ledger};
+use crate::services::policy::{self, PolicyEvent, PolicyOutcome};
+use crate::services::scheduler;
+
+/// Queue for blocks that come before any `Section` heading.
+const DEFAULT_QUEUE: &str = "default";
+
+/// What an import adds to a project.
+#[derive(Debug, Default)]
+struct Staged {
+    queues: Vec<Queue>,
+    tasks: Vec<Task>,
+    gates: Vec<Gate>,
+    /// Plan key → id, for the project's existing tasks and gates and the imported ones.
+    ids: HashMap<String, Uuid>,
+    /// Imported task → line of its `Predecessors`, for cycle reports.
+    lines: HashMap<Uuid, usize>,
+}
+
+/// `KO://repo/bootstrap` → `ko:repo/bootstrap`; `None` for plain deliverables.
+fn ko_ref(deliverable: &str) -> Option<Result<String, String>> {
+    let rest = deliverable.get(..3).filter(|p| p.eq_ignore_ascii_case("ko:")).map(|_| &deliverable[3..])?;
+    Some(KoUri::parse(&format!("ko:{rest}")).map(|u| u.to_string()).map_err(|e| e.to_string()))
+}
+
+/// A task's `Status` as imported. Pending and ready are left to the scheduler; done, failed
+/// and skipped are kept. A running task has no agent holding it here, so it is refused.
+fn plan_status(status: &str) -> Result<TaskStatus, String> {
+    let parsed = serde_json::from_value(serde_json::Value::String(status.trim().to_ascii_lowercase()));
+    match parsed {
+        Ok(TaskStatus::Pending | TaskStatus::Ready) => Ok(TaskStatus::Pending),
+        Ok(TaskStatus::Running) => Err("status 'running' cannot be imported; import the task as ready".into()),
+        Ok(status) => Ok(status),
+        Err(_) => Err(format!("unknown status '{status}'")),
+    }
+}
+
+fn build(l: &Ledger, plan: &Plan, errors: &mut Vec<PlanIssue>) -> Staged {
+    let mut staged = Staged::default();
+    let existing_tasks = l.tasks.iter().filter_map(|t| Some((t.key.clone()?, t.id)));
+    let existing_gates = l.gates.iter().filter_map(|g| Some((g.key.clone()?, g.id)));
+    staged.ids = existing_tasks.chain(existing_gates).collect();
+
+    // Only the first block of a key is imported; later ones are reported
+    let mut first_line: HashMap<&str, usize> = HashMap::new();
+    for block in plan.blocks() {
+        let (key, line) = (block.key(), block.line());
+        match first_line.entry(key) {
+            Entry::Occupied(e) => errors.push(PlanIssue::new(line, format!("duplicate {key} (first on line {})", e.get()))),
+            Entry::Vacant(e) if l.key_in_use(key) => {
+                e.insert(line);
+                errors.push(PlanIssue::new(line, format!("{key} already exists in project {}", l.project.id)));
+            }
+            Entry::Vacant(e) => {
+                e.insert(line);
+                staged.ids.insert(key.to_string(), Uuid::new_v4());
+            }
+        }
+    }
+    let is_gate = |key: &str| {
+        l.gates.iter().any(|g| g.key.as_deref() == Some(key))
+            || plan.blocks().any(|b| matches!(b, PlanBlock::Gate(g) if g.key == key))
+    };
+    let imported = |block: &PlanBlock| first_line.get(block.key()) == Some(&block.line()) && !l.key_in_use(block.key());
+
+    let now = OffsetDateTime::now_utc();
+    for section in &plan.sections {
+        let name = section.name.clone().unwrap_or_else(|| DEFAULT_QUEUE.to_string());
+        let mut queue_id = None;
+        for block in section.blocks.iter().filter(|b| imported(b)) {
+            let resolve = |key: &str, line: usize, what: &str, errors: &mut Vec<PlanIssue>| {
+                let id = staged.ids.get(key).copied();
+                if id.is_none() {
+                    errors.push(PlanIssue::new(line, format!("unknown {what} '{key}'")));
+                }
+                id
+            };
+            match block {
+                PlanBlock::Task(t) => {
+                    let line = t.line_of("predecessors");
+                    let mut predecessors = Vec::new();
+                    for p in &t.predecessors {
+                        match p {
+                            PlanRef::Key(k) => predecessors.extend(resolve(k, line, "predecessor", errors).map(Predecessor::Task)),
+                            PlanRef::Either(ks) => {
+                                let either: Vec<Uuid> = ks.iter().filter_map(|k| resolve(k, line, "predecessor", errors)).collect();
+                                if either.len() == ks.len() {
+                                    predecessors.push(Predecessor::Either { either });
+                                }
+                            }
+                        }
+                    }
+                    let mut ko_refs = Vec::new();
+                    let mut deliverables = Vec::new();
+                    for d in &t.deliverables {
+                        match ko_ref(d) {
+                            Some(Ok(ko)) => ko_refs.push(ko),
+                            Some(Err(e)) => errors.push(PlanIssue::new(t.line_of("deliverables"), e)),
+                            None => deliverables.push(d.clone()),
+                        }
+                    }
+                    let status = match t.status.as_deref().map(plan_status) {
+                        None => TaskStatus::Pending,
+                        Some(Ok(status)) => status,
+                        Some(Err(e)) => {
+                            errors.push(PlanIssue::new(t.line_of("status"), format!("{}: {e}", t.key)));
+                            TaskStatus::Pending
+                        }
+                    };
+                    let Some(kind) = t.kind.clone() else {
+                        errors.push(PlanIssue::new(t.line, format!("{} has no Type", t.key)));
+                        continue;
+                    };
+                    let queue_id = *queue_id.get_or_insert_with(|| {
+                        match l.queues.iter().find(|q| q.name == name) {
+                            Some(q) => q.id,
+                            None => {
+                                let q = Queue { id: Uuid::new_v4(), project_id: l.project.id, name: name.clone() };
+                                staged.queues.push(q.clone());
+                                q.id
+                            }
+                        }
+                    });
+                    let id = staged.ids[&t.key];
+                    staged.lines.insert(id, line);
+                    staged.tasks.push(Task {
+                        id,
+                        queue_id,
+                        kind,
+                        ko_refs,
+                        created_at: now,
+                        key: Some(t.key.clone()),
+                        title: t.title.clone(),
+                        predecessors,
+                        status,
+                        deliverables,
+                        validation: t.validation.clone(),
+                        priority: 0,
+                        ready_since: None,
+                        lease: None,
+                    });
+                }
+                PlanBlock::Gate(g) => {
+                    let line = g.line_of("trigger");
+                    if g.trigger.is_empty() {
+                        errors.push(PlanIssue::new(line, format!("{} has no Trigger tasks", g.key)));
+                    }
+                    let mut trigger = Vec::new();
+                    for k in &g.trigger {
+                        if is_gate(k) {
+                            errors.push(PlanIssue::new(line, format!("{} can only be triggered by tasks, not {k}", g.key)));
+                        } else {
+                            trigger.extend(resolve(k, line, "trigger task", errors));
+                        }
+                    }
+                    staged.gates.push(Gate {
+                        id: staged.ids[&g.key],
+                        project_id: l.project.id,
+                        key: Some(g.key.clone()),
+                        title: g.title.clone(),
+                        trigger,
+                        automated: g.automated.clone(),
+                        human: g.human.clone(),
+                        actions: g.actions.iter().map(|(name, text)| GateAction { name: name.clone(), text: text.clone() }).collect(),
+                        post_gate: g.post_gate.clone(),
+                        status: GateStatus::Waiting,
+                        validations: Vec::new(),
+                        report_ko: None,
+                        decision: None,
+                        created_at: now,
+                    });
+                }
+            }
+        }
+    }
+    staged
+}
+
+/// Add the staged queues, tasks and gates to a ledger.
+fn stage(l: &mut Ledger, staged: &Staged) -> Result<()> {
+    if let Some(q) = staged.queues.iter().find(|q| l.queues.iter().any(|x| x.name == q.name)) {
+        return Err(anyhow!("queue_exists: '{}' in project {}", q.name, l.project.id));
+    }
+    let keys = staged.tasks.iter().filter_map(|t| t.key.as_deref()).chain(staged.gates.iter().filter_map(|g| g.key.as_deref()));
+    for key in keys {
+        if l.key_in_use(key) {
+            return Err(anyhow!("task_exists: '{key}' in project {}", l.project.id));
+        }
+    }
+    l.queues.extend(staged.queues.iter().cloned());
+    l.tasks.extend(staged.tasks.iter().cloned());
+    l.gates.extend(staged.gates.iter().cloned());
+    Ok(())
+}
+
+/// The first dependency cycle among the staged tasks, on a ledger they were staged into.
+fn check_cycles(l: &Ledger, staged: &Staged) -> Option<PlanIssue> {
+    staged.tasks.iter().find_map(|t| {
+        let deps: Vec<Uuid> = t.predecessors.iter().flat_map(|p| p.ids()).copied().collect();
+        let err = scheduler::check_acyclic(l, t.id, &deps).err()?;
+        Some(PlanIssue::new(staged.lines.get(&t.id).copied().unwrap_or_default(), err.to_string()))
+    })
+}
+
+/// `Successors` only restate the graph; warn where they disagree with what actually waits.
+fn check_successors(l: &Ledger, plan: &Plan, staged: &Staged) -> Vec<PlanIssue> {
+    let mut warnings = Vec::new();
+    for block in plan.blocks() {
+        let PlanBlock::Task(t) = block else { continue };
+        let Some(&id) = staged.ids.get(&t.key) else { continue };
+        let line = t.line_of("successors");
+        for s in &t.successors {
+            let Some(&sid) = staged.ids.get(s) else {
+                warnings.push(PlanIssue::new(line, format!("unknown successor '{s}'")));
+                continue;
+            };
+            let waits = l.task(sid).is_some_and(|x| x.predecessors.iter().any(|p| p.ids().contains(&id)))
+                || l.gate(sid).is_some_and(|g| g.trigger.contains(&id));
+            if !waits {
+                warnings.push(PlanIssue::new(line, format!("{} lists {s} as a successor, but {s} does not wait on it", t.key)));
+            }
+        }
+    }
+    warnings
+}
+
+fn status_name(status: impl Serialize) -> Option<String> {
+    serde_json::to_value(status).ok()?.as_str().map(str::to_string)
+}
+
+fn plan_key(l: &Ledger, id: Uuid) -> String {
+    match (l.task(id), l.gate(id)) {
+        (Some(t), _) => t.key.clone().unwrap_or_else(|| format!("TASK-{id}")),
+        (None, Some(g)) => g.key.clone().unwrap_or_else(|| format!("GATE-{id}")),
+        (None, None) => id.to_string(),
+    }
+}
+
+fn task_block(l: &Ledger, t: &Task) -> TaskBlock {
+    let predecessors = t
+        .predecessors
+        .iter()
+        .map(|p| match p {
+            Predecessor::Task(id) => PlanRef::Key(plan_key(l, *id)),
+            Predecessor::Either { either } => PlanRef::Either(either.iter().map(|id| plan_key(l, *id)).collect()),
+        })
+        .collect();
+    let waiting_tasks = l.tasks.iter().filter(|x| x.predecessors.iter().any(|p| p.ids().contains(&t.id))).map(|x| x.id);
+    let waiting_gates = l.gates.iter().filter(|g| g.trigger.contains(&t.id)).map(|g| g.id);
+    TaskBlock {
+        key: plan_key(l, t.id),
+        title: t.title.clone(),
+        kind: Some(t.kind.clone()),
+        status: status_name(t.status),
+        predecessors,
+        successors: waiting_tasks.chain(waiting_gates).map(|id| plan_key(l, id)).collect(),
+        deliverables: t.ko_refs.iter().chain(&t.deliverables).cloned().collect(),
+        validation: t.validation.clone(),
+        ..Default::default()
+    }
+}
+
+fn gate_block(l: &Ledger, g: &Gate) -> GateBlock {
+    GateBlock {
+        key: plan_key(l, g.id),
+        title: g.title.clone(),
+        trigger: g.trigger.iter().map(|id| plan_key(l, *id)).collect(),
+        automated: g.automated.clone(),
+        human: g.human.clone(),
+        actions: g.actions.iter().map(|a| (a.name.clone(), a.text.clone())).collect(),
+        post_gate: g.post_gate.clone(),
+        status: status_name(g.status),
+        ..Default::default()
+    }
+}
+
+/// A project's live state as a plan: one section per queue, each gate after the section
+/// holding the last of its trigger tasks.
+fn to_plan(l: &Ledger) -> Plan {
+    let mut sections: Vec<PlanSection> = l
+        .queues
+        .iter()
+        .map(|q| PlanSection {
+            name: Some(q.name.clone()),
+            blocks: l.tasks.iter().filter(|t| t.queue_id == q.id).map(|t| PlanBlock::Task(task_block(l, t))).collect(),
+        })
+        .collect();
+    for g in &l.gates {
+        let section = g
+            .trigger
+            .iter()
+            .filter_map(|id| l.task(*id))
+            .filter_map(|t| l.queues.iter().position(|q| q.id == t.queue_id))
+            .max();
+        if sections.is_empty() {
+            sections.push(PlanSection::default());
+        }
+        let i = section.unwrap_or(sections.len() - 1);
+        sections[i].blocks.push(PlanBlock::Gate(gate_block(l, g)));
+    }
+    Plan { title: Some(l.project.name.clone()), sections }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanImportReq {
+    pub project_id: Uuid,
+    /// A TES-2025 plan document.
+    pub markdown: String,
+    /// Check the document without changing the project.
+    #[serde(default)]
+    pub dry_run: bool,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanImportResp {
+    /// False when there were errors or on a dry run; nothing was changed then.
+    pub imported: bool,
+    /// Queues created for sections the project did not have yet.
+    pub queues: Vec<Queue>,
+    /// Plan key → id of the imported tasks and gates.
+    pub tasks: BTreeMap<String, Uuid>,
+    pub gates: BTreeMap<String, Uuid>,
+    pub errors: Vec<PlanIssue>,
+    pub warnings: Vec<PlanIssue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanExportReq {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanExportResp {
+    pub markdown: String,
+}
+
+pub struct PlanService;
+
+impl PlanService {
+    /// Handler for op="plan.import": all of the document or nothing. Imported tasks go
+    /// through the `task.created` policy rules; a denied task fails the import.
+    pub async fn import(req: PlanImportReq) -> Result<PlanImportResp> {
+        let store = ledger::store().await?;
+        let current = store.get(req.project_id).await.ok_or_else(|| anyhow!("project_not_found: {}", req.project_id))?;
+        let (plan, mut errors) = tes_plan::parse(&req.markdown);
+        let mut warnings = Vec::new();
+        let staged = build(&current, &plan, &mut errors);
+        if errors.is_empty() {
+            let mut trial = current.clone();
+            stage(&mut trial, &staged)?;
+            errors.extend(check_cycles(&trial, &staged));
+            warnings.extend(check_successors(&trial, &plan, &staged));
+        }
+        let mut verdicts = Vec::new();
+        if errors.is_empty() {
+            let rules = policy::rules().await?;
+            for task in &staged.tasks {
+                let facts = policy::task_facts(req.project_id, task);
+                let Some(v) = policy::evaluate(&rules, PolicyEvent::TaskCreated, task_ko(task.id), facts) else { continue };
+                let line = plan.blocks().find(|b| Some(b.key()) == task.key.as_deref()).map(|b| b.line()).unwrap_or_default();
+                let key = task.key.as_deref().unwrap_or_default();
+                match v.outcome {
+                    PolicyOutcome::Deny => errors.push(PlanIssue::new(line, format!("policy_denied: {key}: {}", v.summary()))),
+                    PolicyOutcome::RequireReview => warnings.push(PlanIssue::new(line, format!("{key} is held: {}", v.summary()))),
+                    PolicyOutcome::Allow => {}
+                }
+                verdicts.push(v);
+            }
+        }
+        errors.sort_by_key(|i| i.line);
+        warnings.sort_by_key(|i| i.line);
+        let mut resp = PlanImportResp {
+            imported: false,
+            queues: staged.queues.clone(),
+            tasks: staged.tasks.iter().filter_map(|t| Some((t.key.clone()?, t.id))).collect(),
+            gates: staged.gates.iter().filter_map(|g| Some((g.key.clone()?, g.id))).collect(),
+            errors,
+            warnings,
+        };
+        if !resp.errors.is_empty() || req.dry_run {
+            return Ok(resp);
+        }
+
+        // Imported tasks only wait on each other or on what was there before, so the
+        // cycle check above still holds however the project changed in between
+        let changes = store
+            .update(req.project_id, |l| {
+                stage(l, &staged)?;
+                l.pending_decisions.extend(verdicts.iter().filter(|v| v.outcome == PolicyOutcome::RequireReview).map(|v| v.decision()));
+                Ok(scheduler::refresh(l))
+            })
+            .await?;
+        resp.imported = true;
+
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({
+            "queues": resp.queues.iter().map(|q| &q.name).collect::<Vec<_>>(),
+            "tasks": resp.tasks,
+            "gates": resp.gates,
+            "warnings": resp.warnings.len(),
+        });
+        audit::record_committed(actor, "PLAN.IMPORTED", Some(&project_ko(req.project_id)), detail).await;
+        for task in &staged.tasks {
+            let detail = serde_json::json!({
+                "project_id": req.project_id,
+                "queue_id": task.queue_id,
+                "kind": task.kind,
+                "key": task.key,
+                "status": task.status,
+                "predecessors": task.predecessors,
+            });
+            audit::record_committed(actor, "TASK.CREATED", Some(&task_ko(task.id)), detail).await;
+        }
+        for v in &verdicts {
+            policy::publish(actor, v).await?;
+        }
+        for g in &staged.gates {
+            let detail = serde_json::json!({ "project_id": req.project_id, "key": g.key, "trigger": g.trigger });
+            audit::record_committed(actor, "GATE.CREATED", Some(&gate_ko(g.id)), detail).await;
+        }
+        scheduler::record_changes(actor, &changes, Some("imported")).await;
+        let edges = staged
+            .tasks
+            .iter()
+            .flat_map(|t| t.ko_refs.iter().map(|r| (task_ko(t.id), EdgeKind::References, r.clone())));
+        graph::record_committed(actor, "plan.import", edges).await;
+        gate::after_task_changes(req.project_id, actor).await?;
+        Ok(resp)
+    }
+
+    /// Handler for op="plan.export": the project's live state as a TES-2025 plan document.
+    pub async fn export(req: PlanExportReq) -> Result<PlanExportResp> {
+        let ledger = ledger::LedgerService::project_get(ledger::ProjectGetReq { project_id: req.project_id }).await?;
+        Ok(PlanExportResp { markdown: tes_plan::render(&to_plan(&ledger)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Project;
+
+    fn import(l: &mut Ledger, doc: &str) -> (Vec<PlanIssue>, Vec<PlanIssue>) {
+        let (plan, mut errors) = tes_plan::parse(doc);
+        let mut warnings = Vec::new();
+        let staged = build(l, &plan, &mut errors);
+        if errors.is_empty() {
+            stage(l, &staged).unwrap();
+            errors.extend(check_cycles(l, &staged));
+            warnings.extend(check_successors(l, &plan, &staged));
+        }
+        (errors, warnings)
+    }
+
+    #[test]
+    fn test_import_reports_cycles_and_unknown_refs() {
+        let mut l = Ledger::new(Project { id: Uuid::new_v4(), name: "p".into(), created_at: OffsetDateTime::now_utc() });
+        let doc = "**TASK-A: a**\n- Type: Implementation\n- Predecessors: [TASK-B]\n\n\
+                   **TASK-B: b**\n- Type: Testing\n- Predecessors: [TASK-A]\n";
+        let (errors, _) = import(&mut l, doc);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert_eq!(errors[0].line, 3);
+        assert!(errors[0].message.starts_with("dependency_cycle: TASK-A -> TASK-B -> TASK-A"));
+
+        let mut l = Ledger::new(l.project.clone());
+        let doc = "**TASK-A: a**\n- Predecessors: [Either: TASK-Z or TASK-B]\n\n**GATE-G: g**\n- Trigger: [TASK-A.completed]\n";
+        let (errors, _) = import(&mut l, doc);
+        let lines: Vec<_> = errors.iter().map(|e| (e.line, e.message.as_str())).collect();
+        assert_eq!(lines, [(2, "unknown predecessor 'TASK-Z'"), (2, "unknown predecessor 'TASK-B'"), (1, "TASK-A has no Type")]);
+    }
+
+    #[test]
+    fn test_task_status_is_applied_or_refused() {
+        let mut l = Ledger::new(Project { id: Uuid::new_v4(), name: "p".into(), created_at: OffsetDateTime::now_utc() });
+        let doc = "**TASK-A: a**\n- Type: Implementation\n- Status: Running\n\n\
+                   **TASK-B: b**\n- Type: Testing\n- Status: Blocked\n";
+        let (errors, _) = import(&mut l, doc);
+        let lines: Vec<_> = errors.iter().map(|e| (e.line, e.message.as_str())).collect();
+        assert_eq!(
+            lines,
+            [(3, "TASK-A: status 'running' cannot be imported; import the task as ready"), (7, "TASK-B: unknown status 'Blocked'")]
+        );
+
+        let doc = "**TASK-A: a**\n- Type: Implementation\n- Status: done\n\n\
+                   **TASK-B: b**\n- Type: Testing\n- Status: Ready\n- Predecessors: [TASK-A]\n";
+        let (errors, _) = import(&mut l, doc);
+        assert!(errors.is_empty(), "{errors:?}");
+        scheduler::refresh(&mut l);
+        let status: Vec<TaskStatus> = l.tasks.iter().map(|t| t.status).collect();
+        assert_eq!(status, [TaskStatus::Done, TaskStatus::Ready]);
+    }
+
+    #[test]
+    fn test_scheduling_doc_imports_and_exports() {
+        let mut l = Ledger::new(Project { id: Uuid::new_v4(), name: "TES".into(), created_at: OffsetDateTime::now_utc() });
+        let (errors, warnings) = import(&mut l, include_str!("../../../../docs/TES-2025-SCHEDULING.md"));
+        assert!(errors.is_empty(), "{errors:?}");
+        // The document's Successors lists are looser than its Predecessors
+        assert!(warnings.iter().any(|w| w.line == 62 && w.message.contains("TASK-A3 does not wait")), "{warnings:?}");
+        assert_eq!((l.queues.len(), l.tasks.len(), l.gates.len()), (5, 14, 5));
+        let b3 = l.tasks.iter().find(|t| t.key.as_deref() == Some("TASK-B3")).unwrap();
+        assert!(matches!(&b3.predecessors[..], [Predecessor::Either { either }] if either.len() == 2));
+        assert_eq!(l.tasks[0].ko_refs, ["ko:repo/bootstrap"]);
+        assert_eq!(l.tasks[0].deliverables, ["CI/CD pipeline"]);
+
+        let markdown = tes_plan::render(&to_plan(&l));
+        assert!(markdown.contains("#### Section B: Service Integration\n\n**TASK-B1: OSM/SOAR Client**\n"));
+        assert!(markdown.contains("- Successors: [TASK-A2, TASK-A3, TASK-A4]\n"));
+        assert!(markdown.contains("  - IP Review: Check for novel architectural patterns\n"));
+        let mut copy = Ledger::new(l.project.clone());
+        let (errors, warnings) = import(&mut copy, &markdown);
+        assert!(errors.is_empty() && warnings.is_empty(), "{errors:?} {warnings:?}");
+        assert_eq!(tes_plan::render(&to_plan(&copy)), markdown);
+    }
+}
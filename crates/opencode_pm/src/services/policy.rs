@@ -0,0 +1,579 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::ko_resolver::{KoError, KoHandler, KoUri, ResolvedKo};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::events::PolicyDecision;
+use crate::models::{Attempt, Task, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::context_pack;
+use crate::services::gate;
+use crate::services::ko_resolve;
+use crate::services::ledger::{self, task_ko, Ledger};
+use crate::services::runlog::RunlogEntry;
+use crate::services::scheduler::{self, StatusChange};
+use crate::services::storage::genesis_home;
+
+pub fn verdict_ko(verdict_id: Uuid) -> String {
+    format!("ko:verdict/{verdict_id}")
+}
+
+fn verdicts_dir() -> Result<PathBuf> {
+    Ok(genesis_home()?.join("verdicts"))
+}
+
+/// When rules are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolicyEvent {
+    /// Before a task is added to its queue; facts: `project_id`, `task`.
+    #[serde(rename = "task.created")]
+    TaskCreated,
+    /// When an attempt's runlog is sealed; facts: `project_id`, `task`, `attempt` and
+    /// `runlog` (`entries`, `errors`, `warnings` counts).
+    #[serde(rename = "attempt.finished")]
+    AttemptFinished,
+}
+
+/// Ordered from most to least permissive: a verdict takes the strictest outcome of the
+/// rules that fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyOutcome {
+    Allow,
+    /// Hold the task until a reviewer decides (`policy.review`).
+    RequireReview,
+    Deny,
+}
+
+impl PolicyOutcome {
+    fn name(self) -> &'static str {
+        match self {
+            PolicyOutcome::Allow => "allow",
+            PolicyOutcome::RequireReview => "require_review",
+            PolicyOutcome::Deny => "deny",
+        }
+    }
+}
+
+/// Operators on one fact. Every operator given must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Test {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eq: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ne: Option<Value>,
+    #[serde(default, rename = "in", skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exists: Option<bool>,
+    /// An element of a list, or a substring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<Value>,
+    /// A string, or any string in a list, starting with this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+}
+
+/// A condition on one fact: a map of operators, or a plain value it must equal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Condition {
+    Test(Test),
+    Equals(Value),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub events: Vec<PolicyEvent>,
+    /// Dotted fact path → condition; the rule fires when all of them hold.
+    #[serde(default)]
+    pub when: BTreeMap<String, Condition>,
+    pub outcome: PolicyOutcome,
+    /// Why, for the verdict; defaults to the description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// File the rule was loaded from.
+    #[serde(default, skip_deserializing)]
+    pub source: String,
+}
+
+/// Strings compare case-insensitively, numbers by value.
+fn same(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(a), Value::String(b)) => a.eq_ignore_ascii_case(b),
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => actual == expected,
+    }
+}
+
+fn lookup<'a>(facts: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(facts, |v, k| v.get(k)).filter(|v| !v.is_null())
+}
+
+impl Test {
+    fn is_empty(&self) -> bool {
+        self.eq.is_none()
+            && self.ne.is_none()
+            && self.one_of.is_none()
+            && [self.lt, self.lte, self.gt, self.gte].iter().all(Option::is_none)
+            && self.exists.is_none()
+            && self.contains.is_none()
+            && self.prefix.is_none()
+    }
+
+    fn holds(&self, actual: Option<&Value>) -> bool {
+        let num = actual.and_then(Value::as_f64);
+        let cmp = |bound: Option<f64>, ok: fn(f64, f64) -> bool| bound.is_none_or(|b| num.is_some_and(|n| ok(n, b)));
+        self.exists.is_none_or(|e| e == actual.is_some())
+            && self.eq.as_ref().is_none_or(|v| actual.is_some_and(|a| same(a, v)))
+            && self.ne.as_ref().is_none_or(|v| !actual.is_some_and(|a| same(a, v)))
+            && self.one_of.as_ref().is_none_or(|vs| actual.is_some_and(|a| vs.iter().any(|v| same(a, v))))
+            && cmp(self.lt, |n, b| n < b)
+            && cmp(self.lte, |n, b| n <= b)
+            && cmp(self.gt, |n, b| n > b)
+            && cmp(self.gte, |n, b| n >= b)
+            && self.contains.as_ref().is_none_or(|v| match (actual, v) {
+                (Some(Value::Array(items)), _) => items.iter().any(|i| same(i, v)),
+                (Some(Value::String(s)), Value::String(sub)) => s.to_lowercase().contains(&sub.to_lowercase()),
+                _ => false,
+            })
+            && self.prefix.as_ref().is_none_or(|p| match actual {
+                Some(Value::String(s)) => s.starts_with(p.as_str()),
+                Some(Value::Array(items)) => items.iter().any(|i| i.as_str().is_some_and(|s| s.starts_with(p.as_str()))),
+                _ => false,
+            })
+    }
+}
+
+impl Condition {
+    fn holds(&self, actual: Option<&Value>) -> bool {
+        match self {
+            Condition::Test(t) => t.holds(actual),
+            Condition::Equals(v) => actual.is_some_and(|a| same(a, v)),
+        }
+    }
+}
+
+impl Rule {
+    fn check(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("rule without an id".into());
+        }
+        if self.events.is_empty() {
+            return Err(format!("rule {}: no events", self.id));
+        }
+        for (path, cond) in &self.when {
+            match cond {
+                // A map that is not a set of operators is almost always a misspelt one
+                Condition::Equals(Value::Object(_)) => return Err(format!("rule {}: {path}: unknown operator", self.id)),
+                Condition::Test(t) if t.is_empty() => return Err(format!("rule {}: {path}: empty condition", self.id)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn fires(&self, event: PolicyEvent, facts: &Value) -> bool {
+        self.events.contains(&event) && self.when.iter().all(|(path, cond)| cond.holds(lookup(facts, path)))
+    }
+}
+
+/// Rules of one policy file. Files without a `rules` list are other policy documents (such
+/// as `originality.yml`) and contribute none.
+pub fn parse_rules(source: &str, yaml: &str) -> Result<Vec<Rule>> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(yaml).map_err(|e| anyhow!("policy_invalid: {source}: {e}"))?;
+    let Some(rules) = doc.get("rules") else { return Ok(Vec::new()) };
+    let mut rules: Vec<Rule> = serde_yaml::from_value(rules.clone()).map_err(|e| anyhow!("policy_invalid: {source}: {e}"))?;
+    for rule in rules.iter_mut() {
+        rule.check().map_err(|e| anyhow!("policy_invalid: {source}: {e}"))?;
+        rule.source = source.to_string();
+    }
+    Ok(rules)
+}
+
+/// A policy file as last parsed, keyed by path; reparsed when its size or mtime changes.
+struct ParsedFile {
+    stamp: (Option<SystemTime>, u64),
+    rules: Result<Vec<Rule>, String>,
+}
+
+static PARSED: LazyLock<parking_lot::Mutex<HashMap<PathBuf, ParsedFile>>> = LazyLock::new(Default::default);
+
+/// The rules of one file, from the cache while the file is unchanged. A file that does not
+/// parse is logged once per version and contributes no rules.
+async fn file_rules(path: &Path, source: &str) -> Result<Vec<Rule>> {
+    let meta = fs::metadata(path).await?;
+    let stamp = (meta.modified().ok(), meta.len());
+    if let Some(parsed) = PARSED.lock().get(path).filter(|p| p.stamp == stamp) {
+        return Ok(parsed.rules.clone().unwrap_or_default());
+    }
+    let rules = parse_rules(source, &fs::read_to_string(path).await?).map_err(|e| e.to_string());
+    if let Err(e) = &rules {
+        tracing::warn!("{e}; its rules are not applied");
+    }
+    let out = rules.clone().unwrap_or_default();
+    PARSED.lock().insert(path.to_path_buf(), ParsedFile { stamp, rules });
+    Ok(out)
+}
+
+/// Every rule in `dir/*.yml`, by file name then position. A file that does not parse is
+/// skipped, as is a rule whose id an earlier file already defines; both are logged.
+pub async fn load_rules(dir: &Path) -> Result<Vec<Rule>> {
+    let mut files = Vec::new();
+    let mut rd = match fs::read_dir(dir).await {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(item) = rd.next_entry().await? {
+        let path = item.path();
+        if matches!(path.extension().and_then(|e| e.to_str()), Some("yml" | "yaml")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    let mut rules: Vec<Rule> = Vec::new();
+    for path in files {
+        let source = format!("policies/{}", path.file_name().and_then(|n| n.to_str()).unwrap_or_default());
+        for rule in file_rules(&path, &source).await? {
+            if let Some(other) = rules.iter().find(|r| r.id == rule.id) {
+                tracing::warn!("policy_invalid: {source}: rule {} is already defined in {}; skipped", rule.id, other.source);
+                continue;
+            }
+            rules.push(rule);
+        }
+    }
+    Ok(rules)
+}
+
+/// The workspace's rules; edits apply without a restart.
+pub async fn rules() -> Result<Vec<Rule>> {
+    load_rules(&context_pack::workspace_root()?.join("policies")).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiredRule {
+    pub rule: String,
+    pub source: String,
+    pub outcome: PolicyOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Verdict KO (`ko:verdict/<id>`): what was decided about a task and which rules fired.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verdict {
+    pub ko_id: String,
+    /// The task, as `ko://task/<id>`.
+    pub subject: String,
+    pub event: PolicyEvent,
+    pub outcome: PolicyOutcome,
+    pub fired: Vec<FiredRule>,
+    /// Rules that applied to the event, fired or not.
+    pub evaluated: usize,
+    /// What the rules were evaluated against.
+    pub facts: Value,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl Verdict {
+    pub fn decision(&self) -> PolicyDecision {
+        let mut decisions = vec![format!("policy:{}", self.outcome.name())];
+        decisions.extend(self.fired.iter().map(|f| format!("rule:{}", f.rule)));
+        PolicyDecision { task_id: self.subject.clone(), decisions, sse_verdict_ko: Some(self.ko_id.clone()) }
+    }
+
+    /// One-line summary for errors and warnings.
+    pub fn summary(&self) -> String {
+        let fired: Vec<String> = self
+            .fired
+            .iter()
+            .filter(|f| f.outcome == self.outcome)
+            .map(|f| match &f.reason {
+                Some(r) => format!("{} ({r})", f.rule),
+                None => f.rule.clone(),
+            })
+            .collect();
+        format!("{} by {}", self.outcome.name(), fired.join(", "))
+    }
+}
+
+/// Evaluate `rules` for one event; `None` when no rule fires (the default is to allow).
+pub fn evaluate(rules: &[Rule], event: PolicyEvent, subject: String, facts: Value) -> Option<Verdict> {
+    let applicable: Vec<&Rule> = rules.iter().filter(|r| r.events.contains(&event)).collect();
+    let fired: Vec<FiredRule> = applicable
+        .iter()
+        .filter(|r| r.fires(event, &facts))
+        .map(|r| FiredRule {
+            rule: r.id.clone(),
+            source: r.source.clone(),
+            outcome: r.outcome,
+            reason: r.reason.clone().or(r.description.clone()),
+        })
+        .collect();
+    let outcome = fired.iter().map(|f| f.outcome).max()?;
+    Some(Verdict {
+        ko_id: verdict_ko(Uuid::new_v4()),
+        subject,
+        event,
+        outcome,
+        fired,
+        evaluated: applicable.len(),
+        facts,
+        created_at: OffsetDateTime::now_utc(),
+    })
+}
+
+pub fn task_facts(project_id: Uuid, task: &Task) -> Value {
+    serde_json::json!({ "event": PolicyEvent::TaskCreated, "project_id": project_id, "task": task })
+}
+
+/// Store the verdict KO and record the decision.
+pub async fn publish(actor: &str, verdict: &Verdict) -> Result<()> {
+    let id = verdict.ko_id.rsplit('/').next().unwrap_or_default();
+    let dir = verdicts_dir()?;
+    fs::create_dir_all(&dir).await?;
+    fs::write(dir.join(format!("{id}.json")), serde_json::to_vec_pretty(verdict)?).await?;
+    let detail = serde_json::json!(verdict.decision());
+    audit::record_committed(actor, "POLICY.DECISION", Some(&verdict.subject), detail).await;
+    Ok(())
+}
+
+/// Undo a task's work when policy turns it down: work not started is skipped, work under
+/// way or finished fails.
+fn reject(l: &mut Ledger, task_id: Uuid) -> Result<Vec<StatusChange>> {
+    let status = l.task(task_id).map(|t| t.status).ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+    match status {
+        TaskStatus::Pending | TaskStatus::Ready => scheduler::transition(l, task_id, TaskStatus::Skipped),
+        TaskStatus::Running => scheduler::transition(l, task_id, TaskStatus::Failed),
+        TaskStatus::Done => {
+            // Not a transition agents may make, so it bypasses `transition`
+            if let Some(t) = l.task_mut(task_id) {
+                t.status = TaskStatus::Failed;
+            }
+            let mut changes = vec![StatusChange { task_id, from: TaskStatus::Done, to: TaskStatus::Failed, reason: None }];
+            changes.extend(scheduler::refresh(l));
+            Ok(changes)
+        }
+        TaskStatus::Failed | TaskStatus::Skipped => Ok(Vec::new()),
+    }
+}
+
+/// Evaluate `attempt.finished` rules for a sealed runlog. A denied attempt fails its task; one
+/// needing review holds the task, so its result counts for nothing until a reviewer approves.
+/// `lease.complete` seals the runlog before marking the task done, so `task.status` is
+/// usually still `running` here. Attempts of tasks outside any ledger are not evaluated.
+pub async fn after_attempt(attempt: &Attempt, entries: &[RunlogEntry], actor: &str) -> Result<Option<Verdict>> {
+    let store = ledger::store().await?;
+    let Some(project_id) = store.project_of_task(attempt.task_id).await else { return Ok(None) };
+    let Some(task) = store.get(project_id).await.and_then(|l| l.task(attempt.task_id).cloned()) else { return Ok(None) };
+    let count = |level: &str| entries.iter().filter(|e| e.level.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(level))).count();
+    let facts = serde_json::json!({
+        "event": PolicyEvent::AttemptFinished,
+        "project_id": project_id,
+        "task": task,
+        "attempt": attempt,
+        "runlog": { "entries": entries.len(), "errors": count("error"), "warnings": count("warn") },
+    });
+    let Some(verdict) = evaluate(&rules().await?, PolicyEvent::AttemptFinished, task_ko(task.id), facts) else { return Ok(None) };
+    publish(actor, &verdict).await?;
+    let task_id = task.id;
+    let changes = match verdict.outcome {
+        PolicyOutcome::Allow => Vec::new(),
+        PolicyOutcome::RequireReview => {
+            let decision = verdict.decision();
+            store
+                .update(project_id, |l| {
+                    l.pending_decisions.push(decision);
+                    Ok(scheduler::refresh(l))
+                })
+                .await?
+        }
+        PolicyOutcome::Deny => store.update(project_id, |l| reject(l, task_id)).await?,
+    };
+    let reason = format!("policy {}", verdict.outcome.name());
+    scheduler::record_changes(actor, &changes, Some(&reason)).await;
+    gate::after_task_changes(project_id, actor).await?;
+    Ok(Some(verdict))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyListReq {}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyPendingReq {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyReviewReq {
+    pub task_id: Uuid,
+    pub approve: bool,
+    pub reason: Option<String>,
+    /// The reviewer; required.
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PolicyReviewResp {
+    pub task: Task,
+    pub decision: PolicyDecision,
+    /// Task status changes the review caused.
+    pub changes: Vec<StatusChange>,
+}
+
+pub struct PolicyService;
+
+impl PolicyService {
+    /// Handler for op="policy.list": the rules loaded from `policies/`.
+    pub async fn list(_req: PolicyListReq) -> Result<Vec<Rule>> {
+        rules().await
+    }
+
+    /// Handler for op="policy.pending": decisions waiting on a reviewer.
+    pub async fn pending(req: PolicyPendingReq) -> Result<Vec<PolicyDecision>> {
+        let ledger = ledger::store().await?.get(req.project_id).await.ok_or_else(|| anyhow!("project_not_found: {}", req.project_id))?;
+        Ok(ledger.pending_decisions)
+    }
+
+    /// Handler for op="policy.review": settle every pending decision on a task. Approval
+    /// releases the task; rejection skips or fails it.
+    pub async fn review(req: PolicyReviewReq) -> Result<PolicyReviewResp> {
+        let actor = req.actor.as_deref().map(str::trim).filter(|a| !a.is_empty() && *a != ANONYMOUS_ACTOR);
+        let actor = actor.ok_or_else(|| anyhow!("bad_request: a policy review needs the reviewer as actor"))?.to_string();
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(req.task_id).await.ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        let ko_id = task_ko(req.task_id);
+        let (settled, task, changes) = store
+            .update(project_id, |l| {
+                let (settled, rest): (Vec<_>, Vec<_>) = l.pending_decisions.drain(..).partition(|d| d.task_id == ko_id);
+                l.pending_decisions = rest;
+                if settled.is_empty() {
+                    return Err(anyhow!("invalid_transition: task {} has no pending policy decision", req.task_id));
+                }
+                let changes = if req.approve { scheduler::refresh(l) } else { reject(l, req.task_id)? };
+                let task = l.task(req.task_id).cloned().ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+                Ok((settled, task, changes))
+            })
+            .await?;
+
+        let outcome = if req.approve { "approve" } else { "reject" };
+        let mut decisions = vec![format!("review:{outcome}"), format!("reviewer:{actor}")];
+        decisions.extend(req.reason.iter().map(|r| format!("reason:{r}")));
+        let verdict = settled.iter().rev().find_map(|d| d.sse_verdict_ko.clone());
+        let decision = PolicyDecision { task_id: ko_id.clone(), decisions, sse_verdict_ko: verdict };
+        audit::record_committed(&actor, "POLICY.DECISION", Some(&ko_id), serde_json::json!(decision)).await;
+        scheduler::record_changes(&actor, &changes, Some(&format!("policy review {outcome}d"))).await;
+        gate::after_task_changes(project_id, &actor).await?;
+        Ok(PolicyReviewResp { task, decision, changes })
+    }
+}
+
+/// Resolves `ko:verdict/<id>` to a stored verdict.
+pub struct VerdictKoHandler;
+
+#[async_trait]
+impl KoHandler for VerdictKoHandler {
+    fn namespace(&self) -> &str {
+        "verdict"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> std::result::Result<ResolvedKo, KoError> {
+        let ko_id = format!("ko:verdict/{}", uri.path);
+        let id = Uuid::parse_str(&uri.path).map_err(|_| KoError::InvalidUri(format!("'{ko_id}': expected ko:verdict/<id>")))?;
+        let path = verdicts_dir().map_err(|e| ko_resolve::ko_error(&ko_id, e))?.join(format!("{id}.json"));
+        let data = fs::read(&path).await.map_err(|_| KoError::NotFound(ko_id.clone()))?;
+        let verdict: Verdict = serde_json::from_slice(&data).map_err(|e| ko_resolve::ko_error(&ko_id, e.into()))?;
+        Ok(ResolvedKo {
+            id: ko_id.clone(),
+            namespace: "verdict".into(),
+            title: Some(format!("Policy verdict: {}", verdict.summary())),
+            media_type: "application/json".into(),
+            content: serde_json::to_string_pretty(&verdict).map_err(|e| ko_resolve::ko_error(&ko_id, e.into()))?,
+            metadata: serde_json::json!({ "subject": verdict.subject, "outcome": verdict.outcome, "event": verdict.event }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+rules:
+  - id: analysis-review
+    events: [task.created]
+    when:
+      task.kind: analysis
+    outcome: require_review
+  - id: low-confidence
+    events: [attempt.finished]
+    when:
+      attempt.confidence: { lt: 0.5 }
+      task.ko_refs: { prefix: "ko:repo/" }
+    outcome: deny
+    reason: too unsure to accept
+"#;
+
+    #[test]
+    fn test_rules_fire_and_strictest_outcome_wins() {
+        let rules = parse_rules("policies/t.yml", RULES).unwrap();
+        let subject = task_ko(Uuid::nil());
+        let created = serde_json::json!({ "task": { "kind": "Analysis", "ko_refs": [] } });
+        let v = evaluate(&rules, PolicyEvent::TaskCreated, subject.clone(), created).unwrap();
+        assert_eq!((v.outcome, v.evaluated), (PolicyOutcome::RequireReview, 1));
+        assert_eq!(v.decision().decisions, ["policy:require_review", "rule:analysis-review"]);
+
+        let finished = |confidence: Value| {
+            let facts = serde_json::json!({ "task": { "ko_refs": ["ko:repo/x"] }, "attempt": { "confidence": confidence } });
+            evaluate(&rules, PolicyEvent::AttemptFinished, subject.clone(), facts)
+        };
+        assert_eq!(finished(serde_json::json!(0.2)).unwrap().summary(), "deny by low-confidence (too unsure to accept)");
+        // A missing fact never satisfies a comparison
+        assert!(finished(serde_json::json!(0.9)).is_none() && finished(Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_bad_rules_are_reported() {
+        assert!(parse_rules("policies/o.yml", "originality:\n  max_similarity: 0.15\n").unwrap().is_empty());
+        let typo = "rules:\n  - id: x\n    events: [task.created]\n    when:\n      task.kind: { equals: a }\n    outcome: deny\n";
+        assert_eq!(parse_rules("policies/t.yml", typo).unwrap_err().to_string(), "policy_invalid: policies/t.yml: rule x: task.kind: unknown operator");
+        let event = "rules:\n  - id: x\n    events: [task.done]\n    outcome: deny\n";
+        assert!(parse_rules("policies/t.yml", event).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bad_file_does_not_hide_the_others() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.yml"), "rules:\n  - id: x\n    events: [task.done]\n    outcome: deny\n").unwrap();
+        std::fs::write(dir.path().join("b.yml"), RULES).unwrap();
+        std::fs::write(dir.path().join("c.yml"), "rules:\n  - id: low-confidence\n    events: [task.created]\n    outcome: deny\n").unwrap();
+        let ids: Vec<String> = load_rules(dir.path()).await.unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["analysis-review", "low-confidence"]);
+
+        std::fs::write(dir.path().join("a.yml"), "rules: []\n# fixed\n").unwrap();
+        assert_eq!(load_rules(dir.path()).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,310 @@
+use pulldown_cmark::{html, Event, Options, Parser};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use crate::services::specbundle::SpecBundle;
+use crate::services::specpart::{DiagramFormat, SpecPart};
+
+/// Attachment part as listed in a rendered document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderedAttachment {
+    /// 1-based part position.
+    pub part: usize,
+    pub name: String,
+    pub source_path: String,
+    /// None when the attachment content is not available to hash.
+    pub sha256: Option<String>,
+    pub bytes: Option<usize>,
+}
+
+pub struct RenderInput<'a> {
+    pub ko_id: &'a str,
+    pub revision: u32,
+    pub bundle: &'a SpecBundle,
+    pub attachments: &'a [RenderedAttachment],
+    /// Mermaid's browser bundle, inlined so diagrams draw without fetching anything; without
+    /// it the HTML shows the diagram source (see `vendored_mermaid`).
+    pub mermaid_js: Option<&'a str>,
+}
+
+/// Vendored Mermaid bundle: `MERMAID_JS`, else `vendor/mermaid/mermaid.min.js` in the
+/// workspace. `None` when neither exists.
+pub async fn vendored_mermaid() -> Option<String> {
+    let path = match std::env::var_os("MERMAID_JS") {
+        Some(p) => PathBuf::from(p),
+        None => crate::services::context_pack::workspace_root().ok()?.join("vendor/mermaid/mermaid.min.js"),
+    };
+    tokio::fs::read_to_string(path).await.ok()
+}
+
+fn fence_lang(part: &SpecPart) -> &str {
+    match part {
+        SpecPart::Mermaid { .. } => "mermaid",
+        SpecPart::Json { .. } => "json",
+        SpecPart::Yaml { .. } => "yaml",
+        SpecPart::Openapi { content } if content.trim_start().starts_with('{') => "json",
+        SpecPart::Openapi { .. } => "yaml",
+        SpecPart::Code { language, .. } => language,
+        SpecPart::Diagram { format: DiagramFormat::Plantuml, .. } => "plantuml",
+        SpecPart::Diagram { format: DiagramFormat::Graphviz, .. } => "dot",
+        _ => "",
+    }
+}
+
+/// Fence long enough not to be closed by backtick runs inside `content`.
+fn fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+static SANITIZER: LazyLock<ammonia::Builder<'static>> = LazyLock::new(|| {
+    let mut b = ammonia::Builder::default();
+    b.url_schemes(HashSet::from(["http", "https", "mailto", "ko"]));
+    b
+});
+
+/// Markdown to HTML with raw HTML passed through as text, then sanitized so links and
+/// images cannot carry `javascript:` or other script-capable URLs.
+fn markdown_html(md: &str) -> String {
+    let events = Parser::new_ext(md, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH).map(|e| match e {
+        Event::Html(h) | Event::InlineHtml(h) => Event::Text(h),
+        e => e,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    SANITIZER.clean(&out).to_string()
+}
+
+fn sessions(bundle: &SpecBundle) -> Vec<String> {
+    bundle.source_sessions.iter().map(|s| format!("{} ({})", s.agent, s.session_id)).collect()
+}
+
+pub fn to_markdown(input: &RenderInput) -> String {
+    let b = input.bundle;
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", b.title);
+    let _ = writeln!(out, "- **KO:** `{}` (revision {})", input.ko_id, input.revision);
+    let _ = writeln!(out, "- **Created by:** {}", b.created_by);
+    if !b.tags.is_empty() {
+        let _ = writeln!(out, "- **Tags:** {}", b.tags.join(", "));
+    }
+    if !b.source_sessions.is_empty() {
+        let _ = writeln!(out, "- **Source sessions:** {}", sessions(b).join("; "));
+    }
+
+    for (i, part) in b.parts.iter().enumerate() {
+        out.push('\n');
+        match part {
+            SpecPart::Markdown { content } => out.push_str(content.trim_end()),
+            SpecPart::Attachment { path } => {
+                let _ = write!(out, "*Attachment {}: `{path}` (see Attachments)*", i + 1);
+            }
+            SpecPart::Link { url, title } => {
+                let _ = write!(out, "[{}]({url})", title.as_deref().unwrap_or(url));
+            }
+            _ => {
+                let text = part.content().unwrap_or_default().trim_end();
+                let f = fence(text);
+                let _ = write!(out, "{f}{}\n{text}\n{f}", fence_lang(part));
+            }
+        }
+        out.push('\n');
+    }
+
+    if !input.attachments.is_empty() {
+        out.push_str("\n## Attachments\n\n| Part | Name | SHA-256 | Bytes |\n|---|---|---|---|\n");
+        for a in input.attachments {
+            let _ = writeln!(
+                out,
+                "| {} | `{}` | {} | {} |",
+                a.part,
+                a.name,
+                a.sha256.as_deref().map(|h| format!("`{h}`")).unwrap_or_else(|| "unavailable".into()),
+                a.bytes.map(|n| n.to_string()).unwrap_or_default()
+            );
+        }
+    }
+    out
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:60rem;margin:2rem auto;padding:0 1rem;line-height:1.5}\
+header{border-bottom:1px solid #ddd;margin-bottom:1.5rem}dt{font-weight:600}dd{margin:0 0 .5rem}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto}section.part{margin-bottom:1.5rem}\
+table{border-collapse:collapse}td,th{border:1px solid #ddd;padding:.25rem .5rem}code{font-size:.9em}";
+
+/// Inline `<script>` for `js`; a `</script` inside it would end the element early.
+fn inline_script(js: &str) -> String {
+    format!("<script>{}</script>", js.replace("</script", "<\\/script"))
+}
+
+pub fn to_html(input: &RenderInput) -> String {
+    let b = input.bundle;
+    let mut out = String::new();
+    let title = escape_html(&b.title);
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<dl>\n"
+    );
+    let mut meta = vec![
+        ("KO", format!("<code>{}</code> (revision {})", escape_html(input.ko_id), input.revision)),
+        ("Created by", escape_html(&b.created_by)),
+    ];
+    if !b.tags.is_empty() {
+        meta.push(("Tags", escape_html(&b.tags.join(", "))));
+    }
+    if !b.source_sessions.is_empty() {
+        meta.push(("Source sessions", escape_html(&sessions(b).join("; "))));
+    }
+    for (k, v) in meta {
+        let _ = writeln!(out, "<dt>{k}</dt><dd>{v}</dd>");
+    }
+    out.push_str("</dl>\n</header>\n<main>\n");
+
+    for (i, part) in b.parts.iter().enumerate() {
+        let _ = writeln!(out, "<section class=\"part\" id=\"part-{}\" data-kind=\"{}\">", i + 1, part.kind());
+        match part {
+            SpecPart::Markdown { content } => out.push_str(&markdown_html(content)),
+            SpecPart::Mermaid { content } => {
+                let _ = writeln!(out, "<pre class=\"mermaid\">{}</pre>", escape_html(content));
+            }
+            SpecPart::Attachment { path } => {
+                let _ = writeln!(
+                    out,
+                    "<p><em>Attachment {}: <code>{}</code> (see <a href=\"#attachments\">Attachments</a>)</em></p>",
+                    i + 1,
+                    escape_html(path)
+                );
+            }
+            SpecPart::Link { url, title } => {
+                let link = format!("<p><a href=\"{}\">{}</a></p>", escape_html(url), escape_html(title.as_deref().unwrap_or(url)));
+                let _ = writeln!(out, "{}", SANITIZER.clean(&link));
+            }
+            _ => {
+                let _ = writeln!(
+                    out,
+                    "<pre><code class=\"language-{}\">{}</code></pre>",
+                    escape_html(fence_lang(part)),
+                    escape_html(part.content().unwrap_or_default())
+                );
+            }
+        }
+        out.push_str("</section>\n");
+    }
+
+    if !input.attachments.is_empty() {
+        out.push_str("<section id=\"attachments\">\n<h2>Attachments</h2>\n<table>\n<tr><th>Part</th><th>Name</th><th>SHA-256</th><th>Bytes</th></tr>\n");
+        for a in input.attachments {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                a.part,
+                escape_html(&a.name),
+                a.sha256.as_deref().map(|h| format!("<code>{h}</code>")).unwrap_or_else(|| "unavailable".into()),
+                a.bytes.map(|n| n.to_string()).unwrap_or_default()
+            );
+        }
+        out.push_str("</table>\n</section>\n");
+    }
+    if let Some(js) = input.mermaid_js
+        && b.parts.iter().any(|p| matches!(p, SpecPart::Mermaid { .. }))
+    {
+        out.push_str(&inline_script(js));
+        out.push_str("\n<script>mermaid.initialize({startOnLoad:true});</script>\n");
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::specbundle::SourceSession;
+
+    fn bundle() -> SpecBundle {
+        SpecBundle {
+            title: "Queue <Engine>".into(),
+            created_by: "alice".into(),
+            source_sessions: vec![SourceSession { agent: "claude".into(), session_id: "s1".into() }],
+            parts: vec![
+                SpecPart::Markdown { content: "## Goals\n<script>alert(1)</script>".into() },
+                SpecPart::Mermaid { content: "graph TD; A-->B".into() },
+                SpecPart::Code { language: "rust".into(), content: "let s = \"```\";".into() },
+                SpecPart::Attachment { path: "docs/design.pdf".into() },
+            ],
+            tags: vec!["queue".into()],
+            redactions: vec![],
+        }
+    }
+
+    fn input<'a>(bundle: &'a SpecBundle, attachments: &'a [RenderedAttachment]) -> RenderInput<'a> {
+        RenderInput { ko_id: "ko:specbundle/x", revision: 2, bundle, attachments, mermaid_js: None }
+    }
+
+    #[test]
+    fn test_markdown_render() {
+        let b = bundle();
+        let att = [RenderedAttachment {
+            part: 4,
+            name: "design.pdf".into(),
+            source_path: "docs/design.pdf".into(),
+            sha256: Some("abc".into()),
+            bytes: Some(3),
+        }];
+        let md = to_markdown(&input(&b, &att));
+        assert!(md.starts_with("# Queue <Engine>\n"));
+        assert!(md.contains("- **Tags:** queue\n- **Source sessions:** claude (s1)"));
+        assert!(md.contains("```mermaid\ngraph TD; A-->B\n```"));
+        assert!(md.contains("````rust\nlet s = \"```\";\n````"));
+        assert!(md.contains("| 4 | `design.pdf` | `abc` | 3 |"));
+    }
+
+    #[test]
+    fn test_html_render_escapes_and_embeds_mermaid() {
+        let b = bundle();
+        let html = to_html(&input(&b, &[]));
+        assert!(html.contains("<title>Queue &lt;Engine&gt;</title>"));
+        assert!(html.contains("<h2>Goals</h2>"));
+        assert!(!html.contains("<script>alert"));
+        assert!(html.contains("<pre class=\"mermaid\">graph TD; A--&gt;B</pre>"));
+        assert!(!html.contains("<script") && !html.contains("https://cdn"));
+        assert!(!html.contains("id=\"attachments\""));
+
+        let inlined = to_html(&RenderInput { mermaid_js: Some("var mermaid={};\"</script>\""), ..input(&b, &[]) });
+        assert!(inlined.contains("<script>var mermaid={};\"<\\/script>\"</script>"));
+        assert!(inlined.contains("mermaid.initialize"));
+    }
+
+    #[test]
+    fn test_html_render_drops_script_urls() {
+        let mut b = bundle();
+        b.parts = vec![
+            SpecPart::Markdown { content: "[click](javascript:alert(1)) ![x](data:text/html,hi) [ok](https://example.com)".into() },
+            SpecPart::Link { url: "javascript:alert(2)".into(), title: None },
+        ];
+        let html = to_html(&input(&b, &[]));
+        assert!(!html.contains("href=\"javascript") && !html.contains("src=\"data:"));
+        assert!(html.contains("href=\"https://example.com\""));
+    }
+}
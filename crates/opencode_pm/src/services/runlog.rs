@@ -0,0 +1,561 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::context_index::{ContextSource, SourceDoc};
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::{KoError, KoHandler, KoUri, ResolvedKo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+use crate::models::{Attempt, AttemptOutcome};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::graph;
+use crate::services::ledger::task_ko;
+use crate::services::ko_resolve;
+use crate::services::policy;
+use crate::services::storage::genesis_home;
+use crate::services::util::replace_file;
+
+const SCHEMA: &str = include_str!("../../../../contracts/schemas/task.runlog.schema.json");
+
+/// Longest entry line the NDJSON endpoint buffers before giving up on the request.
+pub const MAX_ENTRY_BYTES: usize = 64 * 1024;
+
+// One lock per task: serializes starting attempts, and appends against finalization so no
+// entry lands after the log is sealed. Idle locks are dropped as others are taken.
+static LOCKS: LazyLock<parking_lot::Mutex<HashMap<Uuid, Arc<Mutex<()>>>>> = LazyLock::new(Default::default);
+
+async fn lock_task(task_id: Uuid) -> OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = LOCKS.lock();
+        locks.retain(|_, l| Arc::strong_count(l) > 1);
+        locks.entry(task_id).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+/// One runlog line as stored and as the schema defines it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunlogEntry {
+    /// RFC 3339 timestamp.
+    pub ts: String,
+    pub msg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+/// Entry as sent by an agent; `ts` defaults to the time it is received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunlogEntryIn {
+    pub ts: Option<String>,
+    pub msg: String,
+    pub level: Option<String>,
+}
+
+impl RunlogEntryIn {
+    fn into_entry(self) -> Result<RunlogEntry> {
+        let ts = match self.ts {
+            Some(ts) => {
+                OffsetDateTime::parse(&ts, &Rfc3339).map_err(|e| anyhow!("bad_request: entry ts '{ts}': {e}"))?;
+                ts
+            }
+            None => OffsetDateTime::now_utc().format(&Rfc3339)?,
+        };
+        Ok(RunlogEntry { ts, msg: self.msg, level: self.level })
+    }
+}
+
+/// Entry with its 0-based position in the attempt's log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeqEntry {
+    pub seq: usize,
+    #[serde(flatten)]
+    pub entry: RunlogEntry,
+}
+
+/// Finalized runlog KO (`contracts/schemas/task.runlog.schema.json`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRunlog {
+    pub ko_id: String,
+    pub task_id: String,
+    pub attempt: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    pub entries: Vec<RunlogEntry>,
+}
+
+pub fn runlog_ko(task_id: Uuid, n: i32) -> String {
+    format!("ko:runlog/{task_id}/{n}")
+}
+
+fn runlogs_dir() -> Result<PathBuf> {
+    Ok(genesis_home()?.join("runlogs"))
+}
+
+/// `runlogs/<task_id>/<n>/` holds `attempt.json`, `entries.jsonl` with its `head.json` and,
+/// once sealed, `runlog.json`.
+fn attempt_dir(task_id: Uuid, n: i32) -> Result<PathBuf> {
+    Ok(runlogs_dir()?.join(task_id.to_string()).join(n.to_string()))
+}
+
+/// Sealed runlogs as context documents: `<task_id>/<n>` keys, stamped by file mtime and size.
+pub struct RunlogDocs {
+    dir: PathBuf,
+}
+
+impl RunlogDocs {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self { dir: runlogs_dir()? })
+    }
+}
+
+impl ContextSource for RunlogDocs {
+    fn name(&self) -> &str {
+        "runlog"
+    }
+
+    fn list(&self) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        let Ok(tasks) = std::fs::read_dir(&self.dir) else { return out };
+        for task in tasks.flatten() {
+            let Ok(attempts) = std::fs::read_dir(task.path()) else { continue };
+            for attempt in attempts.flatten() {
+                let Ok(meta) = attempt.path().join("runlog.json").metadata() else { continue };
+                let mtime = meta.modified().ok().and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok());
+                let key = format!("{}/{}", task.file_name().to_string_lossy(), attempt.file_name().to_string_lossy());
+                out.push((key, format!("{}:{}", mtime.map_or(0, |d| d.as_nanos()), meta.len())));
+            }
+        }
+        out
+    }
+
+    fn load(&self, key: &str) -> Option<SourceDoc> {
+        let data = std::fs::read(self.dir.join(key).join("runlog.json")).ok()?;
+        let runlog: TaskRunlog = serde_json::from_slice(&data).ok()?;
+        let text = runlog.entries.iter().map(|e| e.msg.as_str()).collect::<Vec<_>>().join("\n");
+        let title = format!("Runlog of task {} attempt {}", runlog.task_id, runlog.attempt);
+        Some(SourceDoc { ko_id: runlog.ko_id, title, text })
+    }
+}
+
+async fn read_attempt(dir: &Path) -> Result<Attempt> {
+    match fs::read(dir.join("attempt.json")).await {
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(anyhow!("attempt_not_found: {}", dir.display())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn write_attempt(dir: &Path, attempt: &Attempt) -> Result<()> {
+    replace_file(&dir.join("attempt.json"), &serde_json::to_vec_pretty(attempt)?).await
+}
+
+/// `head.json`: how many entries `entries.jsonl` holds and its length, so appends need
+/// not re-read the log.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Head {
+    seq: usize,
+    offset: u64,
+}
+
+/// The attempt's head, caught up with entries written after it was last saved (a crash
+/// between the two writes) and with a torn final line cut. Call under the task lock.
+async fn head(dir: &Path) -> Result<Head> {
+    let mut head: Head = match fs::read(dir.join("head.json")).await {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_default(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Head::default(),
+        Err(e) => return Err(e.into()),
+    };
+    let path = dir.join("entries.jsonl");
+    let len = match fs::metadata(&path).await {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e.into()),
+    };
+    if len == head.offset {
+        return Ok(head);
+    }
+    if len < head.offset {
+        head = Head::default();
+    }
+    let (entries, end) = read_entries_from(dir, head.offset).await?;
+    head = Head { seq: head.seq + entries.len(), offset: end };
+    if end < len {
+        OpenOptions::new().write(true).open(&path).await?.set_len(end).await?;
+    }
+    replace_file(&dir.join("head.json"), &serde_json::to_vec(&head)?).await?;
+    Ok(head)
+}
+
+fn finalized(dir: &Path) -> bool {
+    dir.join("runlog.json").is_file()
+}
+
+/// Complete entry lines starting at byte `offset`, and the offset after the last one.
+/// A trailing partial line (an append in progress) is left for the next read.
+pub async fn read_entries_from(dir: &Path, offset: u64) -> Result<(Vec<RunlogEntry>, u64)> {
+    let mut f = match fs::File::open(dir.join("entries.jsonl")).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let start = offset.min(f.metadata().await?.len());
+    f.seek(SeekFrom::Start(start)).await?;
+    let mut data = Vec::new();
+    f.read_to_end(&mut data).await?;
+    let end = data.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+    let mut entries = Vec::new();
+    for line in data[..end].split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+        entries.push(serde_json::from_slice(line)?);
+    }
+    Ok((entries, start + end as u64))
+}
+
+fn validate(runlog: &TaskRunlog) -> Result<()> {
+    let schema: serde_json::Value = serde_json::from_str(SCHEMA)?;
+    opencode_pm_core::schema::validate(&schema, &serde_json::to_value(runlog)?)
+        .map_err(|errors| anyhow!("runlog_invalid: {}", errors.join("; ")))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogStartReq {
+    pub task_id: Uuid,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogAppendReq {
+    pub task_id: Uuid,
+    pub attempt: i32,
+    pub entries: Vec<RunlogEntryIn>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogAppendResp {
+    pub appended: usize,
+    /// `seq` the next entry will get.
+    pub next_seq: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogFinalizeReq {
+    pub task_id: Uuid,
+    pub attempt: i32,
+    pub confidence: Option<f32>,
+    /// Set by the lease protocol when it closes an attempt.
+    #[serde(default)]
+    pub outcome: Option<AttemptOutcome>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogFinalizeResp {
+    pub runlog_ko: String,
+    pub entries: usize,
+    pub attempt: Attempt,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogTailReq {
+    pub task_id: Uuid,
+    pub attempt: i32,
+    /// Entries with `seq >= after`; the last `limit` entries when absent.
+    pub after: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunlogTailResp {
+    pub entries: Vec<SeqEntry>,
+    pub next_seq: usize,
+    pub finalized: bool,
+}
+
+pub struct RunlogService;
+
+impl RunlogService {
+    /// Handler for op="runlog.start": opens the next attempt of a task.
+    pub async fn start(req: RunlogStartReq) -> Result<Attempt> {
+        let _guard = lock_task(req.task_id).await;
+        let task_dir = runlogs_dir()?.join(req.task_id.to_string());
+        let mut n = 1;
+        if let Ok(mut rd) = fs::read_dir(&task_dir).await {
+            while let Some(item) = rd.next_entry().await? {
+                if let Some(k) = item.file_name().to_str().and_then(|s| s.parse::<i32>().ok()) {
+                    n = n.max(k + 1);
+                }
+            }
+        }
+        let dir = attempt_dir(req.task_id, n)?;
+        fs::create_dir_all(&dir).await?;
+        let attempt = Attempt {
+            id: Uuid::new_v4(),
+            task_id: req.task_id,
+            n,
+            started_at: OffsetDateTime::now_utc(),
+            finished_at: None,
+            runlog_ko: None,
+            confidence: None,
+            outcome: None,
+        };
+        write_attempt(&dir, &attempt).await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let ko = runlog_ko(req.task_id, n);
+        audit::record_committed(actor, "RUNLOG.STARTED", Some(&ko), serde_json::json!({ "attempt_id": attempt.id })).await;
+        Ok(attempt)
+    }
+
+    /// Handler for op="runlog.append"; also fed line by line by the streaming endpoint.
+    pub async fn append(req: RunlogAppendReq) -> Result<RunlogAppendResp> {
+        let entries = req.entries.into_iter().map(RunlogEntryIn::into_entry).collect::<Result<Vec<_>>>()?;
+        let dir = attempt_dir(req.task_id, req.attempt)?;
+        let _guard = lock_task(req.task_id).await;
+        read_attempt(&dir).await?;
+        if finalized(&dir) {
+            return Err(anyhow!("runlog_finalized: {}", runlog_ko(req.task_id, req.attempt)));
+        }
+        let head = head(&dir).await?;
+        let mut buf = Vec::new();
+        for e in &entries {
+            buf.extend(serde_json::to_vec(e)?);
+            buf.push(b'\n');
+        }
+        let mut f = OpenOptions::new().create(true).append(true).open(dir.join("entries.jsonl")).await?;
+        f.write_all(&buf).await?;
+        f.flush().await?;
+        let head = Head { seq: head.seq + entries.len(), offset: head.offset + buf.len() as u64 };
+        replace_file(&dir.join("head.json"), &serde_json::to_vec(&head)?).await?;
+        Ok(RunlogAppendResp { appended: entries.len(), next_seq: head.seq })
+    }
+
+    /// Handler for op="runlog.finalize": seals the attempt's log, validates it against the
+    /// runlog schema, stores it as a KO and points the attempt's `runlog_ko` at it. The
+    /// finished attempt then goes through the `attempt.finished` policy rules. A log sealed
+    /// by a finalize that stopped before updating the attempt is picked up where it left off.
+    pub async fn finalize(req: RunlogFinalizeReq) -> Result<RunlogFinalizeResp> {
+        let dir = attempt_dir(req.task_id, req.attempt)?;
+        let ko_id = runlog_ko(req.task_id, req.attempt);
+        let _guard = lock_task(req.task_id).await;
+        let mut attempt = read_attempt(&dir).await?;
+        let runlog = if !finalized(&dir) {
+            let (entries, _) = read_entries_from(&dir, 0).await?;
+            let runlog = TaskRunlog {
+                ko_id: ko_id.clone(),
+                task_id: req.task_id.to_string(),
+                attempt: req.attempt,
+                confidence: req.confidence,
+                entries,
+            };
+            validate(&runlog)?;
+            replace_file(&dir.join("runlog.json"), &serde_json::to_vec_pretty(&runlog)?).await?;
+            runlog
+        } else if attempt.runlog_ko.is_none() {
+            tracing::warn!(%ko_id, "completing an interrupted runlog finalize");
+            serde_json::from_slice(&fs::read(dir.join("runlog.json")).await?)?
+        } else {
+            return Err(anyhow!("runlog_finalized: {ko_id}"));
+        };
+
+        attempt.finished_at = Some(OffsetDateTime::now_utc());
+        attempt.confidence = runlog.confidence;
+        attempt.outcome = req.outcome;
+        attempt.runlog_ko = Some(ko_id.clone());
+        write_attempt(&dir, &attempt).await?;
+        // Policy may look at the task's runlogs again (gate validations)
+        drop(_guard);
+
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "entries": runlog.entries.len(), "confidence": runlog.confidence });
+        audit::record_committed(actor, "RUNLOG.FINALIZED", Some(&ko_id), detail).await;
+        graph::record_committed(actor, "runlog.finalize", [(ko_id.clone(), EdgeKind::ProducedBy, task_ko(req.task_id))]).await;
+        policy::after_attempt(&attempt, &runlog.entries, actor).await?;
+        Ok(RunlogFinalizeResp { runlog_ko: ko_id, entries: runlog.entries.len(), attempt })
+    }
+
+    /// Record how an attempt ended, finalizing its runlog unless the agent already did.
+    pub async fn close(req: RunlogFinalizeReq) -> Result<Attempt> {
+        let (task_id, n, outcome) = (req.task_id, req.attempt, req.outcome);
+        match Self::finalize(req).await {
+            Ok(resp) => Ok(resp.attempt),
+            Err(e) if e.to_string().starts_with("runlog_finalized:") => {
+                let dir = attempt_dir(task_id, n)?;
+                let _guard = lock_task(task_id).await;
+                let mut attempt = read_attempt(&dir).await?;
+                attempt.outcome = outcome;
+                write_attempt(&dir, &attempt).await?;
+                Ok(attempt)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Handler for op="runlog.tail"
+    pub async fn tail(req: RunlogTailReq) -> Result<RunlogTailResp> {
+        let dir = attempt_dir(req.task_id, req.attempt)?;
+        read_attempt(&dir).await?;
+        let (entries, _) = read_entries_from(&dir, 0).await?;
+        let next_seq = entries.len();
+        let from = match req.after {
+            Some(after) => after.min(next_seq),
+            None => next_seq.saturating_sub(req.limit.unwrap_or(100)),
+        };
+        let entries = entries
+            .into_iter()
+            .enumerate()
+            .skip(from)
+            .take(req.limit.unwrap_or(usize::MAX))
+            .map(|(seq, entry)| SeqEntry { seq, entry })
+            .collect();
+        Ok(RunlogTailResp { entries, next_seq, finalized: finalized(&dir) })
+    }
+
+    /// Directory of an existing attempt, for the follow stream.
+    pub async fn attempt_dir(task_id: Uuid, n: i32) -> Result<PathBuf> {
+        let dir = attempt_dir(task_id, n)?;
+        read_attempt(&dir).await?;
+        Ok(dir)
+    }
+
+    pub fn is_finalized(dir: &Path) -> bool {
+        finalized(dir)
+    }
+
+    /// Every attempt of a task, oldest first.
+    pub async fn attempts(task_id: Uuid) -> Result<Vec<Attempt>> {
+        let mut attempts = Vec::new();
+        let mut rd = match fs::read_dir(runlogs_dir()?.join(task_id.to_string())).await {
+            Ok(rd) => rd,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(attempts),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(item) = rd.next_entry().await? {
+            if item.file_type().await?.is_dir() && item.path().join("attempt.json").is_file() {
+                attempts.push(read_attempt(&item.path()).await?);
+            }
+        }
+        attempts.sort_by_key(|a| a.n);
+        Ok(attempts)
+    }
+
+    /// Recreate an attempt record that is missing here (a snapshot restored into a fresh
+    /// home). Existing attempts are left alone: their runlog files are authoritative.
+    pub async fn restore_attempt(attempt: &Attempt) -> Result<bool> {
+        let dir = attempt_dir(attempt.task_id, attempt.n)?;
+        let _guard = lock_task(attempt.task_id).await;
+        if dir.join("attempt.json").is_file() {
+            return Ok(false);
+        }
+        fs::create_dir_all(&dir).await?;
+        write_attempt(&dir, attempt).await?;
+        Ok(true)
+    }
+}
+
+/// Resolves `ko:runlog/<task_id>/<n>`: the sealed runlog, or the entries so far while the
+/// attempt is still running.
+pub struct RunlogKoHandler;
+
+#[async_trait]
+impl KoHandler for RunlogKoHandler {
+    fn namespace(&self) -> &str {
+        "runlog"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> std::result::Result<ResolvedKo, KoError> {
+        let bad = || KoError::InvalidUri(format!("'{uri}': expected ko:runlog/<task_id>/<attempt>"));
+        let (task, n) = uri.path.split_once('/').ok_or_else(bad)?;
+        let task_id = Uuid::parse_str(task).map_err(|_| bad())?;
+        let n: i32 = n.parse().map_err(|_| bad())?;
+        let ko_id = runlog_ko(task_id, n);
+        Self::load(task_id, n, &ko_id).await.map_err(|e| ko_resolve::ko_error(&ko_id, e))
+    }
+}
+
+impl RunlogKoHandler {
+    async fn load(task_id: Uuid, n: i32, ko_id: &str) -> Result<ResolvedKo> {
+        let dir = attempt_dir(task_id, n)?;
+        let attempt = read_attempt(&dir).await.map_err(|_| anyhow!("not_found: {ko_id}"))?;
+        let sealed = finalized(&dir);
+        let runlog: TaskRunlog = if sealed {
+            serde_json::from_slice(&fs::read(dir.join("runlog.json")).await?)?
+        } else {
+            TaskRunlog {
+                ko_id: ko_id.to_string(),
+                task_id: task_id.to_string(),
+                attempt: n,
+                confidence: None,
+                entries: read_entries_from(&dir, 0).await?.0,
+            }
+        };
+        Ok(ResolvedKo {
+            id: ko_id.to_string(),
+            namespace: "runlog".into(),
+            title: Some(format!("Task {task_id} attempt {n}")),
+            media_type: "application/json".into(),
+            content: serde_json::to_string_pretty(&runlog)?,
+            metadata: serde_json::json!({
+                "finalized": sealed,
+                "entries": runlog.entries.len(),
+                "attempt": attempt,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_entries_from_skips_partial_line() {
+        let tmp = tempfile::tempdir().unwrap();
+        let line = |msg: &str| format!("{{\"ts\":\"2025-09-18T12:00:00Z\",\"msg\":\"{msg}\"}}\n");
+        std::fs::write(tmp.path().join("entries.jsonl"), format!("{}{}{{\"ts\":", line("a"), line("b"))).unwrap();
+        let (entries, offset) = read_entries_from(tmp.path(), 0).await.unwrap();
+        assert_eq!(entries.iter().map(|e| e.msg.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        let (more, same) = read_entries_from(tmp.path(), offset).await.unwrap();
+        assert!(more.is_empty());
+        assert_eq!(same, offset);
+    }
+
+    #[tokio::test]
+    async fn test_head_catches_up_and_cuts_torn_line() {
+        let tmp = tempfile::tempdir().unwrap();
+        let line = |msg: &str| format!("{{\"ts\":\"2025-09-18T12:00:00Z\",\"msg\":\"{msg}\"}}\n");
+        let saved = Head { seq: 1, offset: line("a").len() as u64 };
+        std::fs::write(tmp.path().join("head.json"), serde_json::to_vec(&saved).unwrap()).unwrap();
+        // "b" landed after the head was saved, then an append was cut short
+        std::fs::write(tmp.path().join("entries.jsonl"), format!("{}{}{{\"ts\":", line("a"), line("b"))).unwrap();
+
+        let caught_up = head(tmp.path()).await.unwrap();
+        let full = (line("a").len() + line("b").len()) as u64;
+        assert_eq!(caught_up, Head { seq: 2, offset: full });
+        assert_eq!(std::fs::metadata(tmp.path().join("entries.jsonl")).unwrap().len(), full);
+        assert_eq!(head(tmp.path()).await.unwrap(), caught_up);
+        let (rest, _) = read_entries_from(tmp.path(), saved.offset).await.unwrap();
+        assert_eq!(rest.iter().map(|e| e.msg.as_str()).collect::<Vec<_>>(), vec!["b"]);
+    }
+
+    #[test]
+    fn test_schema_rejects_bad_confidence() {
+        let runlog = |confidence| TaskRunlog {
+            ko_id: "ko:runlog/x/1".into(),
+            task_id: "x".into(),
+            attempt: 1,
+            confidence,
+            entries: vec![RunlogEntry { ts: "2025-09-18T12:00:00Z".into(), msg: "done".into(), level: None }],
+        };
+        validate(&runlog(Some(0.9))).unwrap();
+        let err = validate(&runlog(Some(1.5))).unwrap_err().to_string();
+        assert!(err.starts_with("runlog_invalid: /confidence"), "{err}");
+        assert!(RunlogEntryIn { ts: Some("yesterday".into()), msg: "x".into(), level: None }.into_entry().is_err());
+    }
+}
@@ -0,0 +1,347 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{GateStatus, Predecessor, Task, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR};
+use crate::services::gate;
+use crate::services::ledger::{self, task_ko, Ledger};
+
+/// A status change made by a request or cascaded from one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub task_id: Uuid,
+    pub from: TaskStatus,
+    pub to: TaskStatus,
+    /// Why the scheduler made the change, when the request did not.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A done task no policy review is holding, or an approved gate.
+fn is_met(ledger: &Ledger, id: Uuid) -> bool {
+    ledger.task(id).is_some_and(|t| t.status == TaskStatus::Done && !ledger.held(id))
+        || ledger.gate(id).is_some_and(|g| g.status == GateStatus::Approved)
+}
+
+/// Predecessor entries of `task` that are not yet met. An `Either` entry is met as soon as
+/// any of its members is.
+pub fn unmet<'a>(ledger: &Ledger, task: &'a Task) -> Vec<&'a Predecessor> {
+    task.predecessors.iter().filter(|p| !p.ids().iter().any(|id| is_met(ledger, *id))).collect()
+}
+
+/// Why `task` can never become ready: a predecessor entry whose members were all skipped.
+fn dead_end(ledger: &Ledger, task: &Task) -> Option<String> {
+    let skipped = |id: &Uuid| ledger.task(*id).is_some_and(|t| t.status == TaskStatus::Skipped);
+    let p = task.predecessors.iter().find(|p| p.ids().iter().all(skipped))?;
+    let labels: Vec<String> = p.ids().iter().map(|id| label(ledger, *id)).collect();
+    Some(match p {
+        Predecessor::Task(_) => format!("predecessor {} was skipped", labels[0]),
+        Predecessor::Either { .. } => format!("every branch of either {} was skipped", labels.join(" or ")),
+    })
+}
+
+/// What a task or gate waits on: a task's predecessors, a gate's trigger tasks.
+fn dependencies(ledger: &Ledger, id: Uuid) -> Vec<Uuid> {
+    match (ledger.task(id), ledger.gate(id)) {
+        (Some(t), _) => t.predecessors.iter().flat_map(|p| p.ids()).copied().collect(),
+        (None, Some(g)) => g.trigger.clone(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// A task's or gate's label for messages: its plan key when it has one.
+pub fn label(ledger: &Ledger, id: Uuid) -> String {
+    let key = ledger.task(id).and_then(|t| t.key.clone()).or_else(|| ledger.gate(id).and_then(|g| g.key.clone()));
+    key.unwrap_or_else(|| id.to_string())
+}
+
+/// Path `root → … → root` if making `root` (a task or gate) wait on `deps` would close a loop.
+pub fn find_cycle(ledger: &Ledger, root: Uuid, deps: &[Uuid]) -> Option<Vec<Uuid>> {
+    // node → the node it was reached from, walking dependency edges
+    let mut parent: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut queue: VecDeque<Uuid> = VecDeque::new();
+    for id in deps {
+        if parent.insert(*id, root).is_none() {
+            queue.push_back(*id);
+        }
+    }
+    while let Some(id) = queue.pop_front() {
+        if id == root {
+            // Walk back from the closing edge to where we started
+            let mut middle = Vec::new();
+            let mut cur = parent[&root];
+            while cur != root {
+                middle.push(cur);
+                cur = parent[&cur];
+            }
+            middle.reverse();
+            return Some([vec![root], middle, vec![root]].concat());
+        }
+        for next in dependencies(ledger, id) {
+            if let Entry::Vacant(e) = parent.entry(next) {
+                e.insert(id);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Reject predecessors outside the project, empty either/or entries and cycles.
+pub fn check_predecessors(ledger: &Ledger, task_id: Uuid, predecessors: &[Predecessor]) -> Result<()> {
+    for p in predecessors {
+        if p.ids().is_empty() {
+            return Err(anyhow!("bad_request: empty either/or predecessor"));
+        }
+        if let Some(missing) = p.ids().iter().find(|id| ledger.task(**id).is_none() && ledger.gate(**id).is_none()) {
+            return Err(anyhow!("task_not_found: predecessor {missing} is not in project {}", ledger.project.id));
+        }
+    }
+    let deps: Vec<Uuid> = predecessors.iter().flat_map(|p| p.ids()).copied().collect();
+    check_acyclic(ledger, task_id, &deps)
+}
+
+pub fn check_acyclic(ledger: &Ledger, id: Uuid, deps: &[Uuid]) -> Result<()> {
+    if let Some(cycle) = find_cycle(ledger, id, deps) {
+        let path: Vec<String> = cycle.iter().map(|id| label(ledger, *id)).collect();
+        return Err(anyhow!("dependency_cycle: {}", path.join(" -> ")));
+    }
+    Ok(())
+}
+
+/// Move pending tasks whose condition now holds to ready, and ready tasks whose condition no
+/// longer holds (a predecessor was reopened) back to pending. Tasks held for policy review
+/// stay pending. A task whose condition can no longer hold, because every member of one of
+/// its entries was skipped, is skipped too, with the reason; reopening it is left to the
+/// caller.
+pub fn refresh(ledger: &mut Ledger) -> Vec<StatusChange> {
+    let mut changes = Vec::new();
+    let now = OffsetDateTime::now_utc();
+    // Skips cascade down the graph, one level per round
+    loop {
+        let targets: Vec<StatusChange> = ledger
+            .tasks
+            .iter()
+            .filter(|t| matches!(t.status, TaskStatus::Pending | TaskStatus::Ready))
+            .map(|t| {
+                let reason = dead_end(ledger, t);
+                let to = match reason {
+                    Some(_) => TaskStatus::Skipped,
+                    None if unmet(ledger, t).is_empty() && !ledger.held(t.id) => TaskStatus::Ready,
+                    None => TaskStatus::Pending,
+                };
+                StatusChange { task_id: t.id, from: t.status, to, reason }
+            })
+            .filter(|c| c.from != c.to)
+            .collect();
+        if targets.is_empty() {
+            return changes;
+        }
+        for c in targets {
+            if let Some(t) = ledger.task_mut(c.task_id) {
+                t.status = c.to;
+                t.ready_since = (c.to == TaskStatus::Ready).then_some(now);
+            }
+            changes.push(c);
+        }
+    }
+}
+
+fn allowed(from: TaskStatus, to: TaskStatus) -> bool {
+    use TaskStatus::*;
+    matches!(
+        (from, to),
+        (Ready, Running) | (Ready | Running, Done) | (Running, Failed) | (Pending | Ready, Skipped) | (Failed | Skipped | Done, Pending)
+    )
+}
+
+/// Set a task's status and cascade readiness. `Pending` reopens a finished task; the
+/// scheduler then decides whether it is ready again.
+pub fn transition(ledger: &mut Ledger, task_id: Uuid, to: TaskStatus) -> Result<Vec<StatusChange>> {
+    let task = ledger.task_mut(task_id).ok_or_else(|| anyhow!("task_not_found: {task_id}"))?;
+    let from = task.status;
+    if !allowed(from, to) {
+        return Err(anyhow!("invalid_transition: task {task_id} is {from:?}, cannot become {to:?}"));
+    }
+    task.status = to;
+    if to != TaskStatus::Running {
+        // Whoever held the task no longer does; their next heartbeat fails
+        task.lease = None;
+    }
+    let mut changes = vec![StatusChange { task_id, from, to, reason: None }];
+    changes.extend(refresh(ledger));
+    Ok(changes)
+}
+
+/// One `TASK.STATUS_CHANGED` audit record per change, once the changes are saved.
+pub async fn record_changes(actor: &str, changes: &[StatusChange], reason: Option<&str>) {
+    for c in changes {
+        let detail = serde_json::json!({ "from": c.from, "to": c.to, "reason": c.reason.as_deref().or(reason) });
+        audit::record_committed(actor, "TASK.STATUS_CHANGED", Some(&task_ko(c.task_id)), detail).await;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskDependReq {
+    pub task_id: Uuid,
+    /// Replaces the task's current predecessors.
+    pub predecessors: Vec<Predecessor>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskStatusReq {
+    pub task_id: Uuid,
+    pub status: TaskStatus,
+    pub reason: Option<String>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskReadyReq {
+    pub project_id: Uuid,
+    pub queue_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskChangeResp {
+    pub task: Task,
+    /// Every status change this request caused, including successors becoming ready.
+    pub changes: Vec<StatusChange>,
+}
+
+pub struct SchedulerService;
+
+impl SchedulerService {
+    /// Handler for op="task.depend"
+    pub async fn depend(req: TaskDependReq) -> Result<TaskChangeResp> {
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(req.task_id).await.ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        let (task, changes) = store
+            .update(project_id, |l| {
+                check_predecessors(l, req.task_id, &req.predecessors)?;
+                let task = l.task_mut(req.task_id).ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+                task.predecessors = req.predecessors.clone();
+                let task = task.clone();
+                Ok((task, refresh(l)))
+            })
+            .await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "predecessors": task.predecessors });
+        audit::record_committed(actor, "TASK.DEPENDENCIES_SET", Some(&task_ko(task.id)), detail).await;
+        record_changes(actor, &changes, Some("dependencies changed")).await;
+        Ok(TaskChangeResp { task, changes })
+    }
+
+    /// Handler for op="task.status"
+    pub async fn status(req: TaskStatusReq) -> Result<TaskChangeResp> {
+        let store = ledger::store().await?;
+        let project_id = store.project_of_task(req.task_id).await.ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+        let (task, changes) = store
+            .update(project_id, |l| {
+                let changes = transition(l, req.task_id, req.status)?;
+                let task = l.task(req.task_id).cloned().ok_or_else(|| anyhow!("task_not_found: {}", req.task_id))?;
+                Ok((task, changes))
+            })
+            .await?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        record_changes(actor, &changes, req.reason.as_deref()).await;
+        gate::after_task_changes(project_id, actor).await?;
+        Ok(TaskChangeResp { task, changes })
+    }
+
+    /// Handler for op="task.ready": tasks whose dependency condition is met, oldest first.
+    pub async fn ready(req: TaskReadyReq) -> Result<Vec<Task>> {
+        let ledger = ledger::store().await?.get(req.project_id).await.ok_or_else(|| anyhow!("project_not_found: {}", req.project_id))?;
+        let mut ready: Vec<Task> = ledger
+            .tasks
+            .into_iter()
+            .filter(|t| t.status == TaskStatus::Ready && req.queue_id.is_none_or(|q| t.queue_id == q))
+            .collect();
+        ready.sort_by_key(|t| t.created_at);
+        Ok(ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing;
+
+    fn task(l: &mut Ledger, key: &str, predecessors: Vec<Predecessor>) -> Uuid {
+        let t = testing::task(Some(key), TaskStatus::Pending, predecessors);
+        let id = t.id;
+        l.tasks.push(t);
+        id
+    }
+
+    fn status(l: &Ledger, id: Uuid) -> TaskStatus {
+        l.task(id).unwrap().status
+    }
+
+    #[test]
+    fn test_either_or_readiness() {
+        let mut l = testing::ledger();
+        let a1 = task(&mut l, "TASK-A1", vec![]);
+        let b1 = task(&mut l, "TASK-B1", vec![Predecessor::Task(a1)]);
+        let b2 = task(&mut l, "TASK-B2", vec![Predecessor::Task(a1)]);
+        let c1 = task(&mut l, "TASK-C1", vec![Predecessor::Either { either: vec![b1, b2] }]);
+        refresh(&mut l);
+        assert_eq!((status(&l, a1), status(&l, b1)), (TaskStatus::Ready, TaskStatus::Pending));
+
+        assert!(transition(&mut l, b1, TaskStatus::Running).is_err());
+        transition(&mut l, a1, TaskStatus::Done).unwrap();
+        assert_eq!((status(&l, b1), status(&l, b2), status(&l, c1)), (TaskStatus::Ready, TaskStatus::Ready, TaskStatus::Pending));
+
+        transition(&mut l, b2, TaskStatus::Skipped).unwrap();
+        transition(&mut l, b1, TaskStatus::Running).unwrap();
+        let changes = transition(&mut l, b1, TaskStatus::Done).unwrap();
+        assert_eq!(changes.last(), Some(&StatusChange { task_id: c1, from: TaskStatus::Pending, to: TaskStatus::Ready, reason: None }));
+
+        // Reopening the only done branch puts the successor back to pending
+        transition(&mut l, b1, TaskStatus::Pending).unwrap();
+        assert_eq!((status(&l, b1), status(&l, c1)), (TaskStatus::Ready, TaskStatus::Pending));
+    }
+
+    #[test]
+    fn test_all_skipped_either_skips_successors() {
+        let mut l = testing::ledger();
+        let b1 = task(&mut l, "TASK-B1", vec![]);
+        let b2 = task(&mut l, "TASK-B2", vec![]);
+        let c1 = task(&mut l, "TASK-C1", vec![Predecessor::Either { either: vec![b1, b2] }]);
+        let d1 = task(&mut l, "TASK-D1", vec![Predecessor::Task(c1)]);
+        refresh(&mut l);
+        transition(&mut l, b1, TaskStatus::Skipped).unwrap();
+        assert_eq!(status(&l, c1), TaskStatus::Pending);
+
+        let changes = transition(&mut l, b2, TaskStatus::Skipped).unwrap();
+        let reasons: Vec<(Uuid, Option<&str>)> = changes[1..].iter().map(|c| (c.task_id, c.reason.as_deref())).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (c1, Some("every branch of either TASK-B1 or TASK-B2 was skipped")),
+                (d1, Some("predecessor TASK-C1 was skipped")),
+            ]
+        );
+        assert_eq!((status(&l, c1), status(&l, d1)), (TaskStatus::Skipped, TaskStatus::Skipped));
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut l = testing::ledger();
+        let a = task(&mut l, "A", vec![]);
+        let b = task(&mut l, "B", vec![Predecessor::Task(a)]);
+        let c = task(&mut l, "C", vec![Predecessor::Either { either: vec![a, b] }]);
+        assert_eq!(find_cycle(&l, b, &[c]), Some(vec![b, c, b]));
+        assert_eq!(find_cycle(&l, a, &[b]), Some(vec![a, b, a]));
+        let err = check_predecessors(&l, a, &[Predecessor::Task(c)]).unwrap_err().to_string();
+        assert_eq!(err, "dependency_cycle: A -> C -> A");
+        assert_eq!(check_predecessors(&l, a, &[Predecessor::Task(a)]).unwrap_err().to_string(), "dependency_cycle: A -> A");
+        assert!(check_predecessors(&l, c, &[Predecessor::Task(b)]).is_ok());
+        assert!(check_predecessors(&l, c, &[Predecessor::Task(Uuid::new_v4())]).unwrap_err().to_string().starts_with("task_not_found"));
+    }
+}
@@ -2,12 +2,14 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use keyring::Entry;
 
+use crate::services::audit;
+
 const SERVICE_NS: &str = "tempext.genesis.secrets";
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SecretSetReq { pub provider: String, pub account: String, pub key: String }
+pub struct SecretSetReq { pub provider: String, pub account: String, pub key: String, pub actor: Option<String> }
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SecretGetReq { pub provider: String, pub account: String }
+pub struct SecretGetReq { pub provider: String, pub account: String, pub actor: Option<String> }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SecretGetResp { pub exists: bool, pub key: Option<String> }
 
@@ -18,16 +20,24 @@ impl SecretsService {
         let entry = Entry::new(&format!("{SERVICE_NS}.{}", req.provider), &req.account)
             .map_err(|e| anyhow!(e.to_string()))?;
         entry.set_password(&req.key).map_err(|e| anyhow!(e.to_string()))?;
+        let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "provider": req.provider, "account": req.account });
+        audit::record_committed(actor, "SECRET.WRITTEN", None, detail).await;
         Ok(())
     }
 
     pub async fn get(req: SecretGetReq) -> Result<SecretGetResp> {
         let entry = Entry::new(&format!("{SERVICE_NS}.{}", req.provider), &req.account)
             .map_err(|e| anyhow!(e.to_string()))?;
-        match entry.get_password() {
-            Ok(pw) => Ok(SecretGetResp { exists: true, key: Some(pw) }),
-            Err(keyring::Error::NoEntry) => Ok(SecretGetResp { exists: false, key: None }),
-            Err(e) => Err(anyhow!(e.to_string()))
-        }
+        let resp = match entry.get_password() {
+            Ok(pw) => SecretGetResp { exists: true, key: Some(pw) },
+            Err(keyring::Error::NoEntry) => SecretGetResp { exists: false, key: None },
+            Err(e) => return Err(anyhow!(e.to_string()))
+        };
+        // Never the key itself
+        let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "provider": req.provider, "account": req.account, "exists": resp.exists });
+        audit::record(actor, "SECRET.READ", None, detail).await?;
+        Ok(resp)
     }
 }
\ No newline at end of file
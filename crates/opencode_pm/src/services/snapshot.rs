@@ -0,0 +1,471 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::{KoError, KoHandler, KoUri, ResolvedKo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::models::{Attempt, Predecessor, TaskStatus};
+use crate::services::audit::{self, ANONYMOUS_ACTOR, SYSTEM_ACTOR};
+use crate::services::graph;
+use crate::services::ko_resolve;
+use crate::services::ledger::{self, project_ko, Ledger};
+use crate::services::runlog::RunlogService;
+use crate::services::storage::genesis_home;
+use crate::services::util::replace_file;
+
+const SCHEMA: &str = include_str!("../../../../contracts/schemas/orchestration.snapshot.schema.json");
+/// Periodic snapshots kept per project; manual and pre-restore ones are never pruned.
+const DEFAULT_KEEP: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotTrigger {
+    Manual,
+    Periodic,
+    /// Taken automatically before a restore overwrites the ledger.
+    PreRestore,
+}
+
+/// Everything captured for a project: its ledger plus the attempts of its tasks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectState {
+    #[serde(flatten)]
+    pub ledger: Ledger,
+    #[serde(default)]
+    pub attempts: Vec<Attempt>,
+}
+
+/// Snapshot KO (`contracts/schemas/orchestration.snapshot.schema.json`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub ko_id: String,
+    pub project_id: String,
+    /// RFC 3339 timestamp.
+    pub created_at: String,
+    pub trigger: SnapshotTrigger,
+    pub created_by: String,
+    pub state: ProjectState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub ko_id: String,
+    pub created_at: String,
+    pub trigger: SnapshotTrigger,
+    /// Ledger revision captured.
+    pub revision: u64,
+    pub tasks: usize,
+    pub attempts: usize,
+}
+
+impl From<&Snapshot> for SnapshotSummary {
+    fn from(s: &Snapshot) -> Self {
+        Self {
+            ko_id: s.ko_id.clone(),
+            created_at: s.created_at.clone(),
+            trigger: s.trigger,
+            revision: s.state.ledger.revision,
+            tasks: s.state.ledger.tasks.len(),
+            attempts: s.state.attempts.len(),
+        }
+    }
+}
+
+pub fn snapshot_ko(project_id: Uuid, snapshot_id: Uuid) -> String {
+    format!("ko:snapshot/{project_id}/{snapshot_id}")
+}
+
+/// `(project_id, snapshot_id)` of `ko:snapshot/<project_id>/<snapshot_id>`.
+fn parse_ko(ko_id: &str) -> Result<(Uuid, Uuid)> {
+    let bad = || anyhow!("invalid_ko_id: '{ko_id}': expected ko:snapshot/<project_id>/<snapshot_id>");
+    let uri = KoUri::parse(ko_id).map_err(|_| bad())?;
+    if uri.namespace != "snapshot" {
+        return Err(bad());
+    }
+    let (project, id) = uri.path.split_once('/').ok_or_else(bad)?;
+    Ok((Uuid::parse_str(project).map_err(|_| bad())?, Uuid::parse_str(id).map_err(|_| bad())?))
+}
+
+fn project_dir(project_id: Uuid) -> Result<PathBuf> {
+    Ok(genesis_home()?.join("snapshots").join(project_id.to_string()))
+}
+
+fn validate(snapshot: &Snapshot) -> Result<()> {
+    let schema: serde_json::Value = serde_json::from_str(SCHEMA)?;
+    opencode_pm_core::schema::validate(&schema, &serde_json::to_value(snapshot)?)
+        .map_err(|errors| anyhow!("snapshot_invalid: {}", errors.join("; ")))
+}
+
+async fn load(ko_id: &str) -> Result<Snapshot> {
+    let (project_id, id) = parse_ko(ko_id)?;
+    let path = project_dir(project_id)?.join(format!("{id}.json"));
+    match fs::read(&path).await {
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(anyhow!("snapshot_not_found: {ko_id}")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// All readable snapshots of a project, oldest first, and `<file>: <error>` for the
+/// ones that could not be read.
+async fn list(project_id: Uuid) -> Result<(Vec<Snapshot>, Vec<String>)> {
+    let (mut out, mut skipped) = (Vec::new(), Vec::new());
+    let mut rd = match fs::read_dir(project_dir(project_id)?).await {
+        Ok(rd) => rd,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((out, skipped)),
+        Err(e) => return Err(e.into()),
+    };
+    while let Some(item) = rd.next_entry().await? {
+        let path = item.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let read = match fs::read(&path).await {
+            Ok(data) => serde_json::from_slice::<Snapshot>(&data).map_err(anyhow::Error::from),
+            Err(e) => Err(e.into()),
+        };
+        match read {
+            Ok(s) => out.push(s),
+            Err(e) => skipped.push(format!("{}: {e}", item.file_name().to_string_lossy())),
+        }
+    }
+    out.sort_by_key(|s| OffsetDateTime::parse(&s.created_at, &Rfc3339).ok());
+    skipped.sort();
+    Ok((out, skipped))
+}
+
+/// Give a copied ledger's queues, tasks and gates fresh ids under project `into`, and
+/// rewrite every reference to them, so the copy shares no ids with the original. Leases
+/// stay with the original: running tasks of the copy go back to ready.
+fn remap_ids(ledger: &mut Ledger, into: Uuid) {
+    let ids: HashMap<Uuid, Uuid> = (ledger.queues.iter().map(|q| q.id))
+        .chain(ledger.tasks.iter().map(|t| t.id))
+        .chain(ledger.gates.iter().map(|g| g.id))
+        .map(|id| (id, Uuid::new_v4()))
+        .collect();
+    let id = |old: &mut Uuid| *old = ids.get(old).copied().unwrap_or(*old);
+    // KO ids such as `ko:task/<id>` or `ko:gate/<id>/poe`
+    let ko = |s: &mut String| {
+        for (old, new) in &ids {
+            if s.contains(&old.to_string()) {
+                *s = s.replace(&old.to_string(), &new.to_string());
+            }
+        }
+    };
+
+    ledger.project.id = into;
+    for q in &mut ledger.queues {
+        id(&mut q.id);
+        q.project_id = into;
+    }
+    for t in &mut ledger.tasks {
+        id(&mut t.id);
+        id(&mut t.queue_id);
+        t.ko_refs.iter_mut().for_each(ko);
+        for p in &mut t.predecessors {
+            match p {
+                Predecessor::Task(p) => id(p),
+                Predecessor::Either { either } => either.iter_mut().for_each(id),
+            }
+        }
+        if t.lease.take().is_some() && t.status == TaskStatus::Running {
+            t.status = TaskStatus::Ready;
+        }
+    }
+    for g in &mut ledger.gates {
+        id(&mut g.id);
+        g.project_id = into;
+        g.trigger.iter_mut().for_each(id);
+        g.report_ko.iter_mut().for_each(ko);
+    }
+    for d in &mut ledger.pending_decisions {
+        ko(&mut d.task_id);
+        d.sse_verdict_ko.iter_mut().for_each(ko);
+    }
+    ledger.revision = 0;
+}
+
+async fn capture(project_id: Uuid, trigger: SnapshotTrigger, actor: &str) -> Result<Snapshot> {
+    let ledger = ledger::store().await?.get(project_id).await.ok_or_else(|| anyhow!("project_not_found: {project_id}"))?;
+    let mut attempts = Vec::new();
+    for task in &ledger.tasks {
+        attempts.extend(RunlogService::attempts(task.id).await?);
+    }
+    let id = Uuid::new_v4();
+    let snapshot = Snapshot {
+        ko_id: snapshot_ko(project_id, id),
+        project_id: project_id.to_string(),
+        created_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+        trigger,
+        created_by: actor.to_string(),
+        state: ProjectState { ledger, attempts },
+    };
+    validate(&snapshot)?;
+    replace_file(&project_dir(project_id)?.join(format!("{id}.json")), &serde_json::to_vec_pretty(&snapshot)?).await?;
+
+    let detail = serde_json::json!({ "trigger": trigger, "revision": snapshot.state.ledger.revision });
+    audit::record_committed(actor, "SNAPSHOT.CREATED", Some(&snapshot.ko_id), detail).await;
+    graph::record_committed(actor, "snapshot.create", [(snapshot.ko_id.clone(), EdgeKind::DerivedFrom, project_ko(project_id))]).await;
+    Ok(snapshot)
+}
+
+/// Drop the oldest periodic snapshots beyond `keep`.
+async fn prune(project_id: Uuid, keep: usize) -> Result<usize> {
+    let periodic: Vec<Snapshot> = list(project_id).await?.0.into_iter().filter(|s| s.trigger == SnapshotTrigger::Periodic).collect();
+    let excess = periodic.len().saturating_sub(keep);
+    for s in &periodic[..excess] {
+        let (_, id) = parse_ko(&s.ko_id)?;
+        fs::remove_file(project_dir(project_id)?.join(format!("{id}.json"))).await?;
+    }
+    Ok(excess)
+}
+
+/// Snapshot every project whose ledger changed since its last snapshot, every `interval`.
+pub fn spawn_periodic(interval: Duration, keep: usize) {
+    tokio::spawn(async move {
+        // project → ledger revision last captured
+        let mut captured: HashMap<Uuid, u64> = HashMap::new();
+        loop {
+            tokio::time::sleep(interval).await;
+            let store = match ledger::store().await {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("periodic snapshot: no ledger store: {e}");
+                    continue;
+                }
+            };
+            for project_id in store.project_ids().await {
+                let Some(revision) = store.get(project_id).await.map(|l| l.revision) else { continue };
+                if !captured.contains_key(&project_id) {
+                    let last = list(project_id).await.ok().and_then(|(l, _)| l.last().map(|s| s.state.ledger.revision));
+                    captured.extend(last.map(|r| (project_id, r)));
+                }
+                if captured.get(&project_id) == Some(&revision) {
+                    continue;
+                }
+                match capture(project_id, SnapshotTrigger::Periodic, SYSTEM_ACTOR).await {
+                    Ok(s) => {
+                        captured.insert(project_id, s.state.ledger.revision);
+                        if let Err(e) = prune(project_id, keep).await {
+                            tracing::warn!(%project_id, "periodic snapshot: prune failed: {e}");
+                        }
+                    }
+                    Err(e) => tracing::warn!(%project_id, "periodic snapshot failed: {e}"),
+                }
+            }
+        }
+    });
+}
+
+/// `SNAPSHOT_INTERVAL_SECS` (default 3600; 0 disables) and `SNAPSHOT_KEEP` (default 24).
+pub fn periodic_from_env() -> Option<(Duration, usize)> {
+    let secs = std::env::var("SNAPSHOT_INTERVAL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(3600u64);
+    let keep = std::env::var("SNAPSHOT_KEEP").ok().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_KEEP);
+    (secs > 0).then(|| (Duration::from_secs(secs), keep))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotCreateReq {
+    pub project_id: Uuid,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotListReq {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotListResp {
+    pub snapshots: Vec<SnapshotSummary>,
+    /// `<file>: <error>` for snapshot files that could not be read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotGetReq {
+    pub ko_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotRestoreReq {
+    pub ko_id: String,
+    /// Restore into a new project with this id instead of overwriting the original, e.g. to
+    /// inspect an old state next to the live one. The copy's queues, tasks and gates get
+    /// new ids; attempts and leases are not copied.
+    pub into_project_id: Option<Uuid>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotRestoreResp {
+    pub project_id: Uuid,
+    pub revision: u64,
+    pub queues: usize,
+    pub tasks: usize,
+    /// Attempt records recreated because they were missing.
+    pub attempts_restored: usize,
+    /// Snapshot of the state the restore replaced, when there was one.
+    pub pre_restore_ko: Option<String>,
+}
+
+pub struct SnapshotService;
+
+impl SnapshotService {
+    /// Handler for op="snapshot.create"
+    pub async fn create(req: SnapshotCreateReq) -> Result<SnapshotSummary> {
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        Ok((&capture(req.project_id, SnapshotTrigger::Manual, actor).await?).into())
+    }
+
+    /// Handler for op="snapshot.list"
+    pub async fn list(req: SnapshotListReq) -> Result<SnapshotListResp> {
+        let (snapshots, skipped) = list(req.project_id).await?;
+        Ok(SnapshotListResp { snapshots: snapshots.iter().map(SnapshotSummary::from).collect(), skipped })
+    }
+
+    /// Handler for op="snapshot.get"
+    pub async fn get(req: SnapshotGetReq) -> Result<Snapshot> {
+        load(&req.ko_id).await
+    }
+
+    /// Handler for op="snapshot.restore": rehydrate a ledger from a snapshot, either in place
+    /// (after snapshotting the current state) or into a new project.
+    pub async fn restore(req: SnapshotRestoreReq) -> Result<SnapshotRestoreResp> {
+        let snapshot = load(&req.ko_id).await?;
+        validate(&snapshot)?;
+        let actor = req.actor.as_deref().unwrap_or(ANONYMOUS_ACTOR);
+        let store = ledger::store().await?;
+        let mut ledger = snapshot.state.ledger;
+        let mut attempts_restored = 0;
+        let mut pre_restore_ko = None;
+
+        match req.into_project_id {
+            Some(into) => {
+                if store.get(into).await.is_some() {
+                    return Err(anyhow!("project_exists: {into}"));
+                }
+                remap_ids(&mut ledger, into);
+            }
+            None => {
+                if store.get(ledger.project.id).await.is_some() {
+                    pre_restore_ko = Some(capture(ledger.project.id, SnapshotTrigger::PreRestore, actor).await?.ko_id);
+                }
+                for attempt in &snapshot.state.attempts {
+                    attempts_restored += RunlogService::restore_attempt(attempt).await? as usize;
+                }
+            }
+        }
+
+        let project_id = ledger.project.id;
+        let (queues, tasks) = (ledger.queues.len(), ledger.tasks.len());
+        let revision = store.replace(ledger).await?;
+        let detail = serde_json::json!({
+            "project_id": project_id,
+            "revision": revision,
+            "attempts_restored": attempts_restored,
+            "pre_restore_ko": pre_restore_ko,
+        });
+        audit::record_committed(actor, "SNAPSHOT.RESTORED", Some(&req.ko_id), detail).await;
+        if req.into_project_id.is_some() {
+            graph::record_committed(actor, "snapshot.restore", [(project_ko(project_id), EdgeKind::DerivedFrom, req.ko_id.clone())]).await;
+        }
+        Ok(SnapshotRestoreResp { project_id, revision, queues, tasks, attempts_restored, pre_restore_ko })
+    }
+}
+
+/// Resolves `ko:snapshot/<project_id>/<snapshot_id>` to the stored snapshot.
+pub struct SnapshotKoHandler;
+
+#[async_trait]
+impl KoHandler for SnapshotKoHandler {
+    fn namespace(&self) -> &str {
+        "snapshot"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> std::result::Result<ResolvedKo, KoError> {
+        let ko_id = format!("ko:snapshot/{}", uri.path);
+        let snapshot = load(&ko_id).await.map_err(|e| ko_resolve::ko_error(&ko_id, e))?;
+        let content = serde_json::to_string_pretty(&snapshot).map_err(|e| ko_resolve::ko_error(&ko_id, e.into()))?;
+        Ok(ResolvedKo {
+            id: snapshot.ko_id.clone(),
+            namespace: "snapshot".into(),
+            title: Some(format!("{} @ {}", snapshot.state.ledger.project.name, snapshot.created_at)),
+            media_type: "application/json".into(),
+            content,
+            metadata: serde_json::to_value(SnapshotSummary::from(&snapshot)).unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::PolicyDecision;
+    use crate::models::{Project, Queue, Task, TaskLease};
+    use crate::services::testing;
+
+    #[test]
+    fn test_snapshot_matches_schema() {
+        let project_id = Uuid::new_v4();
+        let project = Project { id: project_id, name: "p".into(), created_at: OffsetDateTime::now_utc() };
+        let snapshot = Snapshot {
+            ko_id: snapshot_ko(project_id, Uuid::new_v4()),
+            project_id: project_id.to_string(),
+            created_at: "2025-09-18T12:00:00Z".into(),
+            trigger: SnapshotTrigger::Manual,
+            created_by: "alice".into(),
+            state: ProjectState { ledger: Ledger::new(project), attempts: vec![] },
+        };
+        validate(&snapshot).unwrap();
+        let value = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(value["state"]["project"]["name"], "p");
+        assert!(value["state"]["queues"].is_array());
+        assert_eq!(parse_ko(&snapshot.ko_id).unwrap().0, project_id);
+        assert!(parse_ko("ko:snapshot/not-a-uuid/x").unwrap_err().to_string().starts_with("invalid_ko_id:"));
+    }
+
+    #[test]
+    fn test_remap_ids_leaves_no_original_id() {
+        let now = OffsetDateTime::now_utc();
+        let project = Project { id: Uuid::new_v4(), name: "p".into(), created_at: now };
+        let queue = Queue { id: Uuid::new_v4(), project_id: project.id, name: "q".into() };
+        let task = |ko_refs, predecessors| Task { queue_id: queue.id, ko_refs, ..testing::task(None, TaskStatus::Pending, predecessors) };
+        let a = task(vec![], vec![]);
+        let mut gate = testing::gate(project.id, None, vec![a.id]);
+        gate.report_ko = Some(format!("ko:gate/{}/poe", gate.id));
+        let mut b = task(vec![ledger::task_ko(a.id)], vec![Predecessor::Either { either: vec![a.id, gate.id] }]);
+        b.status = TaskStatus::Running;
+        b.lease = Some(TaskLease { lease_id: Uuid::new_v4(), agent: "x".into(), attempt: 1, claimed_at: now, expires_at: now });
+        let decision = PolicyDecision { task_id: ledger::task_ko(b.id), decisions: vec![], sse_verdict_ko: None };
+        let mut ledger = Ledger::new(project.clone());
+        ledger.queues.push(queue.clone());
+        ledger.tasks.extend([a.clone(), b.clone()]);
+        ledger.gates.push(gate.clone());
+        ledger.pending_decisions.push(decision);
+        ledger.revision = 7;
+
+        let into = Uuid::new_v4();
+        let mut copy = ledger.clone();
+        remap_ids(&mut copy, into);
+        let json = serde_json::to_string(&copy).unwrap();
+        for old in [project.id, queue.id, a.id, b.id, gate.id] {
+            assert!(!json.contains(&old.to_string()), "{old} left in {json}");
+        }
+        let (a2, b2, g2) = (&copy.tasks[0], &copy.tasks[1], &copy.gates[0]);
+        assert_eq!((copy.revision, copy.queues[0].project_id, a2.queue_id), (0, into, copy.queues[0].id));
+        assert_eq!(b2.predecessors, vec![Predecessor::Either { either: vec![a2.id, g2.id] }]);
+        assert_eq!(b2.ko_refs, vec![ledger::task_ko(a2.id)]);
+        assert_eq!((b2.status, b2.lease.is_none()), (TaskStatus::Ready, true));
+        assert_eq!((g2.trigger.clone(), g2.report_ko.clone()), (vec![a2.id], Some(format!("ko:gate/{}/poe", g2.id))));
+        assert_eq!(copy.pending_decisions[0].task_id, ledger::task_ko(b2.id));
+    }
+}
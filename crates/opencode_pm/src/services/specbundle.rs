@@ -1,28 +1,144 @@
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use opencode_pm_core::ko_graph::EdgeKind;
+use opencode_pm_core::ko_resolver::{KoError, KoHandler, KoUri, ResolvedKo};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard};
+use similar::DiffOp;
+use regex::Regex;
+use opencode_pm_core::context_index::{ContextSource, SourceDoc};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, OnceLock};
 use time::OffsetDateTime;
 
-fn base_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().ok_or_else(|| anyhow!("no HOME dir"))?;
-    Ok(home.join(".tempext-genesis").join("specbundles"))
+use crate::services::archive::{self, SignatureStatus};
+use crate::services::audit;
+use crate::services::context_pack;
+use crate::services::graph;
+use crate::services::guardian::GuardianService;
+use crate::services::ingest;
+use crate::services::ko_resolve;
+use crate::services::license::{self, LicenseFinding};
+use crate::services::originality::{self, OriginalityFinding};
+use crate::services::render::{self, RenderInput, RenderedAttachment};
+use crate::services::specpart::{validate_parts, StoredPart};
+use crate::services::storage::{bundle_store_from_env, confine, genesis_home, BundleStore};
+use crate::services::util::sha256_hex;
+
+pub use crate::services::specpart::SpecPart;
+
+static STORE: OnceLock<Arc<dyn BundleStore>> = OnceLock::new();
+static CONTEXT_DOCS: LazyLock<Arc<BundleDocs>> = LazyLock::new(Default::default);
+
+/// Install the backend chosen at startup (see `storage::bundle_store_from_env`).
+pub fn init_store(store: Arc<dyn BundleStore>) {
+    if STORE.set(store).is_err() {
+        tracing::warn!("specbundle store already initialised; keeping the first one");
+    }
 }
 
-async fn write_text(path: PathBuf, data: &str) -> Result<()> {
-    if let Some(parent) = path.parent() { fs::create_dir_all(parent).await?; }
-    let mut f = fs::File::create(path).await?;
-    f.write_all(data.as_bytes()).await?;
-    Ok(())
+fn store() -> Result<Arc<dyn BundleStore>> {
+    if let Some(s) = STORE.get() {
+        return Ok(s.clone());
+    }
+    let s = bundle_store_from_env()?;
+    Ok(STORE.get_or_init(|| s).clone())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SpecPart {
-    pub kind: String,          // "markdown" | "attachment" | "mermaid" | ...
-    pub path: Option<String>,  // for attachments
-    pub content: Option<String>, // for markdown/mermaid
+/// Head revisions of live bundles as context documents, so `context.fetch` sees every bundle
+/// and not only ingested ones. Kept current by create, revise, import and delete; seeded at
+/// startup by `load_context_docs`.
+#[derive(Default)]
+pub struct BundleDocs {
+    docs: parking_lot::RwLock<BTreeMap<String, (u32, SourceDoc)>>,
+}
+
+impl BundleDocs {
+    fn put(&self, ko_id: &str, revision: u32, bundle: &SpecBundle) {
+        let text = bundle.parts.iter().filter_map(SpecPart::content).collect::<Vec<_>>().join("\n\n");
+        let doc = SourceDoc { ko_id: ko_id.to_string(), title: bundle.title.clone(), text };
+        self.docs.write().insert(ko_id.to_string(), (revision, doc));
+    }
+
+    fn remove(&self, ko_id: &str) {
+        self.docs.write().remove(ko_id);
+    }
+}
+
+impl ContextSource for BundleDocs {
+    fn name(&self) -> &str {
+        "specbundle"
+    }
+
+    fn list(&self) -> Vec<(String, String)> {
+        self.docs.read().iter().map(|(k, (rev, _))| (k.clone(), rev.to_string())).collect()
+    }
+
+    fn load(&self, key: &str) -> Option<SourceDoc> {
+        self.docs.read().get(key).map(|(_, doc)| doc.clone())
+    }
+}
+
+pub fn context_docs() -> Arc<BundleDocs> {
+    CONTEXT_DOCS.clone()
+}
+
+/// Fill `context_docs` from the store; unreadable bundles are skipped with a warning.
+pub async fn load_context_docs() -> Result<usize> {
+    let store = store()?;
+    let mut n = 0;
+    for entry in read_index(store.as_ref()).await?.into_iter().filter(|e| e.deleted_at.is_none()) {
+        match read_revision(store.as_ref(), &folder_for(&entry.ko_id), entry.revision).await {
+            Ok(bundle) => {
+                CONTEXT_DOCS.put(&entry.ko_id, entry.revision, &bundle);
+                n += 1;
+            }
+            Err(e) => tracing::warn!(ko_id = %entry.ko_id, "specbundle not indexed for context: {e}"),
+        }
+    }
+    Ok(n)
+}
+
+/// Where exports are written; callers cannot pick a path outside it.
+fn export_dir() -> Result<PathBuf> {
+    Ok(genesis_home()?.join("exports"))
+}
+
+/// Directories archives may be imported from: `<genesis home>/imports`, the export
+/// directory, and any listed in `SPECBUNDLE_IMPORT_DIRS` (`:`-separated).
+fn import_dirs() -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![genesis_home()?.join("imports"), export_dir()?];
+    if let Some(extra) = std::env::var_os("SPECBUNDLE_IMPORT_DIRS") {
+        dirs.extend(std::env::split_paths(&extra).filter(|p| !p.as_os_str().is_empty()));
+    }
+    Ok(dirs)
+}
+
+/// Attachment sources are workspace files; nothing outside these roots is read.
+fn attachment_roots() -> Result<Vec<PathBuf>> {
+    Ok(vec![context_pack::workspace_root()?])
+}
+
+/// Accepts `ko:specbundle/<uuid>` and `ko://specbundle/<uuid>`; returns the canonical form.
+fn canonical_ko_id(ko_id: &str) -> Result<String> {
+    let raw = ko_id
+        .strip_prefix("ko://specbundle/")
+        .or_else(|| ko_id.strip_prefix("ko:specbundle/"))
+        .ok_or_else(|| anyhow!("not a specbundle KO id: '{ko_id}'"))?;
+    let id = Uuid::parse_str(raw).map_err(|_| anyhow!("not a specbundle KO id: '{ko_id}'"))?;
+    Ok(format!("ko:specbundle/{id}"))
+}
+
+/// Store key prefix for a bundle: `ko_specbundle_<uuid>`.
+fn folder_for(ko_id: &str) -> String {
+    ko_id.replace([':', '/'], "_")
+}
+
+// Immutable per-revision snapshot: revisions/<nnnn>/{bundle.json,revision.json,parts/,manifest.json}
+fn revision_dir(folder: &str, n: u32) -> String {
+    format!("{folder}/revisions/{n:04}")
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,76 +161,1603 @@ pub struct SourceSession {
 pub struct SpecbundleCreateReq {
     pub bundle: SpecBundle,
     pub ingest: Option<bool>,
+    /// Selects the Guardian allowlist to apply.
+    #[serde(default)]
+    pub project_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SpecbundleCreateResp {
     pub ko_id: String,
     pub stored_parts: usize,
+    /// True when an ingestion job was enqueued (see `ingest.status`).
     pub ingested: bool,
+    pub ingest_job_id: Option<String>,
+    /// Parts the originality policy flagged (`action: flag`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub originality: Vec<OriginalityFinding>,
+    /// Code parts and attachments under licenses that are not allowlisted but not rejected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<LicenseFinding>,
 }
 
-pub struct SpecbundleService;
+/// One row of `index.json`; enough to list and filter without opening bundle folders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub ko_id: String,
+    pub title: String,
+    pub created_by: String,
+    pub agents: Vec<String>,
+    pub tags: Vec<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub stored_parts: usize,
+    /// Head revision number; bundles start at 1.
+    #[serde(default = "first_revision")]
+    pub revision: u32,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub updated_at: Option<OffsetDateTime>,
+    /// Tombstone: set when the bundle is deleted.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
+}
 
-impl SpecbundleService {
-    /// Handler for target="opencode_pm", op="specbundle.create"
-    pub async fn create(req: SpecbundleCreateReq) -> Result<SpecbundleCreateResp> {
-        // 0) Guardian redaction pre-check (very simple)
-        if let Some(first_md) = req.bundle.parts.iter().find(|p| p.kind == "markdown").and_then(|p| p.content.as_ref()) {
-            for pat in &req.bundle.redactions {
-                if first_md.contains(pat) {
-                    return Err(anyhow!("guardian_redaction_violation: content contains redacted pattern '{}'", pat));
+fn first_revision() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleGetReq {
+    pub ko_id: String,
+    /// Defaults to the head revision.
+    pub revision: Option<u32>,
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleGetResp {
+    pub entry: IndexEntry,
+    pub revision: u32,
+    pub bundle: SpecBundle,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevisionMeta {
+    pub revision: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    pub author: String,
+    pub message: Option<String>,
+    pub stored_parts: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleReviseReq {
+    pub ko_id: String,
+    pub bundle: SpecBundle,
+    pub message: Option<String>,
+    /// Re-ingest the new revision. Defaults to true when an earlier revision was ingested, so
+    /// knowledge search does not keep serving the old text.
+    pub ingest: Option<bool>,
+    /// Selects the Guardian allowlist to apply.
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleReviseResp {
+    pub ko_id: String,
+    pub revision: u32,
+    pub stored_parts: usize,
+    /// True when an ingestion job was enqueued (see `ingest.status`).
+    #[serde(default)]
+    pub ingested: bool,
+    pub ingest_job_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub originality: Vec<OriginalityFinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<LicenseFinding>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleHistoryReq {
+    pub ko_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleHistoryResp {
+    pub ko_id: String,
+    pub revisions: Vec<RevisionMeta>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleDiffReq {
+    pub ko_id: String,
+    /// Defaults to the revision before `to`.
+    pub from: Option<u32>,
+    /// Defaults to the head revision.
+    pub to: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleDiffResp {
+    pub ko_id: String,
+    pub from: u32,
+    pub to: u32,
+    #[serde(flatten)]
+    pub diff: BundleDiff,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartRef {
+    /// 1-based part position.
+    pub index: usize,
+    pub kind: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartChange {
+    /// 1-based position in the newer bundle.
+    pub index: usize,
+    /// 1-based position in the older bundle.
+    pub index_from: usize,
+    pub kind_from: String,
+    pub kind_to: String,
+    /// Unified line diff, when both sides are text parts of the same kind.
+    pub line_diff: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BundleDiff {
+    /// Top-level fields that differ (title, tags, ...).
+    pub fields_changed: Vec<String>,
+    pub added: Vec<PartRef>,
+    pub removed: Vec<PartRef>,
+    pub changed: Vec<PartChange>,
+}
+
+fn line_diff(a: &str, b: &str) -> String {
+    let diff = similar::TextDiff::from_lines(a, b);
+    diff.unified_diff().context_radius(3).to_string()
+}
+
+fn part_hash(part: &SpecPart) -> String {
+    sha256_hex(&serde_json::to_vec(part).unwrap_or_default())
+}
+
+/// Sequence diff over part hashes, so inserting or dropping a part only reports that part.
+/// Within a replaced run, parts are paired in order as changes; the surplus is added/removed.
+pub fn diff_bundles(a: &SpecBundle, b: &SpecBundle) -> BundleDiff {
+    let mut out = BundleDiff::default();
+    let fields = [
+        ("title", a.title != b.title),
+        ("created_by", a.created_by != b.created_by),
+        ("tags", a.tags != b.tags),
+        ("redactions", a.redactions != b.redactions),
+        (
+            "source_sessions",
+            serde_json::to_value(&a.source_sessions).ok() != serde_json::to_value(&b.source_sessions).ok(),
+        ),
+    ];
+    out.fields_changed = fields.iter().filter(|(_, c)| *c).map(|(f, _)| f.to_string()).collect();
+
+    let ha: Vec<String> = a.parts.iter().map(part_hash).collect();
+    let hb: Vec<String> = b.parts.iter().map(part_hash).collect();
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, &ha, &hb) {
+        let (old_index, old_len, new_index, new_len) = match op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete { old_index, old_len, new_index } => (old_index, old_len, new_index, 0),
+            DiffOp::Insert { old_index, new_index, new_len } => (old_index, 0, new_index, new_len),
+            DiffOp::Replace { old_index, old_len, new_index, new_len } => (old_index, old_len, new_index, new_len),
+        };
+        for k in 0..old_len.max(new_len) {
+            let (i, j) = (old_index + k, new_index + k);
+            match (a.parts.get(i).filter(|_| k < old_len), b.parts.get(j).filter(|_| k < new_len)) {
+                (Some(pa), Some(pb)) => {
+                    let line_diff = match (pa.content(), pb.content()) {
+                        (Some(ta), Some(tb)) if pa.kind() == pb.kind() => Some(line_diff(ta, tb)),
+                        _ => None,
+                    };
+                    out.changed.push(PartChange {
+                        index: j + 1,
+                        index_from: i + 1,
+                        kind_from: pa.kind().to_string(),
+                        kind_to: pb.kind().to_string(),
+                        line_diff,
+                    });
                 }
+                (Some(pa), None) => out.removed.push(PartRef { index: i + 1, kind: pa.kind().to_string() }),
+                (None, Some(pb)) => out.added.push(PartRef { index: j + 1, kind: pb.kind().to_string() }),
+                (None, None) => {}
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SpecbundleListReq {
+    pub tag: Option<String>,
+    pub created_by: Option<String>,
+    pub agent: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub created_after: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub created_before: Option<OffsetDateTime>,
+    /// Case-insensitive match against title and tags.
+    pub q: Option<String>,
+    #[serde(default)]
+    pub include_deleted: bool,
+    pub limit: Option<usize>,
+}
+
+impl SpecbundleListReq {
+    fn matches(&self, e: &IndexEntry) -> bool {
+        if e.deleted_at.is_some() && !self.include_deleted {
+            return false;
+        }
+        if let Some(tag) = &self.tag && !e.tags.iter().any(|t| t == tag) {
+            return false;
+        }
+        if let Some(by) = &self.created_by && &e.created_by != by {
+            return false;
+        }
+        if let Some(agent) = &self.agent && !e.agents.iter().any(|a| a == agent) {
+            return false;
+        }
+        if self.created_after.is_some_and(|t| e.created_at < t) {
+            return false;
+        }
+        if self.created_before.is_some_and(|t| e.created_at >= t) {
+            return false;
+        }
+        if let Some(q) = &self.q {
+            let q = q.to_lowercase();
+            if !e.title.to_lowercase().contains(&q) && !e.tags.iter().any(|t| t.to_lowercase().contains(&q)) {
+                return false;
             }
         }
+        true
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleListResp {
+    pub items: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleSearchReq {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub ko_id: String,
+    pub title: String,
+    /// 1-based index of the matching part.
+    pub part: usize,
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleSearchResp {
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleDeleteReq {
+    pub ko_id: String,
+    /// Also remove the bundle folder; the tombstone stays in the index.
+    #[serde(default)]
+    pub purge: bool,
+    /// Recorded in the audit log.
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleDeleteResp {
+    pub ko_id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub deleted_at: OffsetDateTime,
+    pub purged: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SpecbundleVerifyReq {
+    /// Verify a single bundle; all bundles when omitted.
+    pub ko_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    /// An interrupted write: staging dir, temp file or missing head snapshot.
+    Incomplete,
+    /// A manifest entry is missing or its hash does not match.
+    Corrupted,
+    /// Indexed (and not deleted) but the folder is gone.
+    Missing,
+    /// Written but absent from index.json (interrupted between the two writes); verify
+    /// adds the entry back.
+    Unindexed,
+    /// Written before manifests existed; nothing to check against.
+    Unverified,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyProblem {
+    pub ko_id: Option<String>,
+    pub path: String,
+    pub status: VerifyStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleVerifyResp {
+    pub checked: usize,
+    pub problems: Vec<VerifyProblem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleExportReq {
+    pub ko_id: String,
+    /// Relative to `<genesis home>/exports` (absolute paths must lie inside it); defaults to
+    /// `<folder>.tar.zst` there.
+    pub out_path: Option<String>,
+    /// Recorded in the audit log.
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleExportResp {
+    pub ko_id: String,
+    pub path: String,
+    pub bytes: usize,
+    pub sha256: String,
+    pub files: usize,
+    /// Attachment source paths that could not be read and were exported as descriptors only.
+    pub missing_attachments: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflict {
+    /// Import under a fresh KO id when the original is already taken.
+    #[default]
+    Remap,
+    Fail,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleImportReq {
+    /// Archive inside an import directory (see `import_dirs`); relative paths are taken
+    /// against `<genesis home>/imports`.
+    pub path: String,
+    #[serde(default)]
+    pub on_conflict: ImportConflict,
+    /// Accept archives that are unsigned or signed with another key (hashes are still checked).
+    /// Signatures are HMACs with this host's key, so archives from another machine verify only
+    /// when both share `SPECBUNDLE_SIGNING_KEY`.
+    #[serde(default)]
+    pub allow_unsigned: bool,
+    /// Selects the Guardian allowlist applied to the imported head revision.
+    pub project_id: Option<String>,
+    /// Recorded in the audit log.
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleImportResp {
+    pub ko_id: String,
+    pub original_ko_id: String,
+    pub remapped: bool,
+    pub revision: u32,
+    pub attachments: usize,
+    pub signature: SignatureStatus,
+    /// Parts the originality policy flagged (`action: flag`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub originality: Vec<OriginalityFinding>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub licenses: Vec<LicenseFinding>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderFormat {
+    Markdown,
+    Html,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleRenderReq {
+    pub ko_id: String,
+    /// Defaults to the head revision.
+    pub revision: Option<u32>,
+    #[serde(default)]
+    pub format: RenderFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpecbundleRenderResp {
+    pub ko_id: String,
+    pub revision: u32,
+    pub markdown: Option<String>,
+    pub html: Option<String>,
+    pub attachments: Vec<RenderedAttachment>,
+}
+
+// Serializes read-modify-write cycles on index.json.
+static INDEX_LOCK: Mutex<()> = Mutex::const_new(());
+async fn read_index(store: &dyn BundleStore) -> Result<Vec<IndexEntry>> {
+    match store.get_string("index.json").await? {
+        Some(s) => Ok(serde_json::from_str(&s)?),
+        None => rebuild_index(store).await,
+    }
+}
+
+async fn write_index(store: &dyn BundleStore, entries: &[IndexEntry]) -> Result<()> {
+    store.put("index.json", serde_json::to_string_pretty(entries)?.as_bytes()).await
+}
+
+/// Bundle folders present in the store (`ko_specbundle_<uuid>`), from a full key listing.
+fn bundle_folders(keys: &[String]) -> Vec<String> {
+    let mut folders: Vec<String> = keys
+        .iter()
+        .filter_map(|k| k.split('/').next())
+        .filter(|f| f.starts_with("ko_specbundle_"))
+        .map(str::to_string)
+        .collect();
+    folders.dedup();
+    folders
+}
+
+/// Index entry recreated from a bundle folder; `None` without a readable head snapshot.
+async fn entry_from_folder(store: &dyn BundleStore, folder: &str) -> Result<Option<IndexEntry>> {
+    let raw = folder.trim_start_matches("ko_specbundle_");
+    let Some(bundle) = store.get_string(&format!("{folder}/bundle.json")).await? else { return Ok(None) };
+    let Ok(bundle) = parse_stored_bundle(bundle.as_bytes()) else { return Ok(None) };
+    let revision = latest_revision(store, folder).await?;
+    let first = read_revision_meta(store, folder, 1).await?;
+    let head = if revision > 1 { read_revision_meta(store, folder, revision).await? } else { None };
+    let created_at = first.as_ref().map(|m| m.created_at).unwrap_or_else(OffsetDateTime::now_utc);
+    let deleted_at = store
+        .get_string(&format!("{folder}/tombstone.json"))
+        .await?
+        .and_then(|s| serde_json::from_str::<SpecbundleDeleteResp>(&s).ok())
+        .map(|t| t.deleted_at);
+    Ok(Some(IndexEntry {
+        created_by: first.map(|m| m.author).unwrap_or_else(|| bundle.created_by.clone()),
+        stored_parts: bundle.parts.len(),
+        revision,
+        updated_at: head.map(|m| m.created_at),
+        ..entry_for(&bundle, format!("ko:specbundle/{raw}"), created_at, deleted_at)
+    }))
+}
+
+/// Recreate index entries from the bundle folders (used when index.json is missing).
+async fn rebuild_index(store: &dyn BundleStore) -> Result<Vec<IndexEntry>> {
+    let mut entries = Vec::new();
+    for folder in bundle_folders(&store.list("ko_specbundle_").await?) {
+        entries.extend(entry_from_folder(store, &folder).await?);
+    }
+    entries.sort_by_key(|e| e.created_at);
+    Ok(entries)
+}
+
+/// Add index entries for `folders` that have a head snapshot but no entry, e.g. after a
+/// crash between writing the bundle and updating index.json. Returns what was re-added.
+async fn reconcile_index(store: &dyn BundleStore, folders: &[String]) -> Result<Vec<VerifyProblem>> {
+    let _guard = INDEX_LOCK.lock().await;
+    let mut index = read_index(store).await?;
+    let mut added = Vec::new();
+    for folder in folders {
+        if index.iter().any(|e| folder_for(&e.ko_id) == *folder) {
+            continue;
+        }
+        let Some(entry) = entry_from_folder(store, folder).await? else { continue };
+        added.push(VerifyProblem {
+            ko_id: Some(entry.ko_id.clone()),
+            path: folder.clone(),
+            status: VerifyStatus::Unindexed,
+            detail: "missing from index.json; entry re-added".into(),
+        });
+        index.push(entry);
+    }
+    if !added.is_empty() {
+        index.sort_by_key(|e| e.created_at);
+        write_index(store, &index).await?;
+    }
+    Ok(added)
+}
+
+fn entry_for(bundle: &SpecBundle, ko_id: String, created_at: OffsetDateTime, deleted_at: Option<OffsetDateTime>) -> IndexEntry {
+    let mut agents: Vec<String> = bundle.source_sessions.iter().map(|s| s.agent.clone()).collect();
+    agents.sort();
+    agents.dedup();
+    IndexEntry {
+        ko_id,
+        title: bundle.title.clone(),
+        created_by: bundle.created_by.clone(),
+        agents,
+        tags: bundle.tags.clone(),
+        created_at,
+        stored_parts: 0,
+        revision: 1,
+        updated_at: None,
+        deleted_at,
+    }
+}
+
+/// Revision numbers present under `<folder>/revisions/`, ascending.
+async fn revision_numbers(store: &dyn BundleStore, folder: &str) -> Result<Vec<u32>> {
+    let prefix = format!("{folder}/revisions/");
+    let mut revs: Vec<u32> = store
+        .list(&prefix)
+        .await?
+        .iter()
+        .filter_map(|k| k[prefix.len()..].split('/').next()?.parse().ok())
+        .collect();
+    revs.dedup();
+    Ok(revs)
+}
+
+async fn read_revision_meta(store: &dyn BundleStore, folder: &str, n: u32) -> Result<Option<RevisionMeta>> {
+    Ok(store
+        .get_string(&format!("{}/revision.json", revision_dir(folder, n)))
+        .await?
+        .and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+async fn latest_revision(store: &dyn BundleStore, folder: &str) -> Result<u32> {
+    Ok(revision_numbers(store, folder).await?.last().copied().unwrap_or(1))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the revision directory.
+    pub path: String,
+    pub sha256: String,
+    pub bytes: usize,
+}
+
+/// `manifest.json`, written last into each revision; its presence marks the revision complete.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub revision: u32,
+    pub files: Vec<ManifestFile>,
+}
+
+/// Files of a revision being assembled before they are committed to the store together.
+#[derive(Default)]
+struct Staging {
+    files: Vec<(String, Vec<u8>)>,
+    manifest: Vec<ManifestFile>,
+}
+
+impl Staging {
+    fn put(&mut self, rel: &str, data: &str) {
+        self.manifest.push(ManifestFile { path: rel.to_string(), sha256: sha256_hex(data.as_bytes()), bytes: data.len() });
+        self.files.push((rel.to_string(), data.as_bytes().to_vec()));
+    }
+}
+
+fn write_parts(staging: &mut Staging, bundle: &SpecBundle) -> usize {
+    for (i, part) in bundle.parts.iter().enumerate() {
+        staging.put(&format!("parts/{:04}.{}", i + 1, part.file_suffix()), &part.stored_body());
+    }
+    bundle.parts.len()
+}
+
+/// Write revision `n` and point the top-level bundle.json (head snapshot) at it.
+/// All files go to the store in one `put_all`, manifest last: the local backend stages and
+/// renames atomically, object stores treat a revision without manifest as incomplete.
+async fn write_revision(store: &dyn BundleStore, folder: &str, n: u32, bundle: &SpecBundle, message: Option<String>) -> Result<RevisionMeta> {
+    let dir = revision_dir(folder, n);
+    if !store.list(&format!("{dir}/")).await?.is_empty() {
+        return Err(anyhow!("revision {n} already exists"));
+    }
+    let bundle_json = serde_json::to_string_pretty(bundle)?;
+    let mut staging = Staging::default();
+    staging.put("bundle.json", &bundle_json);
+    let stored_parts = write_parts(&mut staging, bundle);
+    let meta = RevisionMeta {
+        revision: n,
+        created_at: OffsetDateTime::now_utc(),
+        author: bundle.created_by.clone(),
+        message,
+        stored_parts,
+    };
+    staging.put("revision.json", &serde_json::to_string_pretty(&meta)?);
+    let manifest = Manifest { revision: n, files: std::mem::take(&mut staging.manifest) };
+    staging.files.push(("manifest.json".into(), serde_json::to_string_pretty(&manifest)?.into_bytes()));
+
+    store.put_all(&dir, &staging.files).await?;
+    store.put(&format!("{folder}/bundle.json"), bundle_json.as_bytes()).await?;
+    Ok(meta)
+}
+
+/// A stored bundle.json. Parts in the pre-typed shape are mapped onto `SpecPart` rather than
+/// failing the whole bundle (see `specpart::StoredPart`).
+fn parse_stored_bundle(raw: &[u8]) -> Result<SpecBundle> {
+    if let Ok(bundle) = serde_json::from_slice(raw) {
+        return Ok(bundle);
+    }
+    let mut doc: serde_json::Value = serde_json::from_slice(raw)?;
+    if let Some(parts) = doc.get_mut("parts") {
+        let stored: Vec<StoredPart> = serde_json::from_value(parts.take())?;
+        let typed: Vec<SpecPart> = stored.into_iter().map(SpecPart::from).collect();
+        *parts = serde_json::to_value(typed)?;
+    }
+    Ok(serde_json::from_value(doc)?)
+}
+
+async fn read_revision(store: &dyn BundleStore, folder: &str, n: u32) -> Result<SpecBundle> {
+    let raw = match store.get_string(&format!("{}/bundle.json", revision_dir(folder, n))).await? {
+        Some(s) => s,
+        // Bundles written before revisions existed only have the top-level snapshot
+        None if n == 1 && revision_numbers(store, folder).await?.is_empty() => store
+            .get_string(&format!("{folder}/bundle.json"))
+            .await?
+            .ok_or_else(|| anyhow!("no bundle.json"))?,
+        None => return Err(anyhow!("revision {n} not found")),
+    };
+    parse_stored_bundle(raw.as_bytes())
+}
+
+/// Originality: every inline part against the indexed corpus, leaving out the bundle's own
+/// earlier revisions when it is revised.
+async fn check_originality(bundle: &SpecBundle, actor: &str, ko_id: Option<&str>) -> Result<Vec<OriginalityFinding>> {
+    let items: Vec<(String, &str)> =
+        bundle.parts.iter().enumerate().filter_map(|(i, p)| Some((format!("part {}", i + 1), p.content()?))).collect();
+    originality::enforce(actor, ko_id, &items).await
+}
+
+/// Licenses: code parts, and attachments from `archived` (`attachments/<part>/<name>` files
+/// of an imported archive) or else their source files inside the attachment roots.
+/// Attachments that cannot be read there are reported as unscanned.
+async fn check_licenses(bundle: &SpecBundle, actor: &str, ko_id: Option<&str>, archived: Option<&[(String, Vec<u8>)]>) -> Result<Vec<LicenseFinding>> {
+    let mut items: Vec<(String, String)> = Vec::new();
+    let mut unscanned = Vec::new();
+    for (i, part) in bundle.parts.iter().enumerate() {
+        let item = format!("part {}", i + 1);
+        let data = match part {
+            SpecPart::Code { content, .. } => content.as_bytes().to_vec(),
+            SpecPart::Attachment { path } => {
+                let prefix = format!("attachments/{:04}/", i + 1);
+                let data = match archived {
+                    Some(files) => files.iter().find(|(p, _)| p.starts_with(&prefix)).map(|(_, d)| d.clone()),
+                    None => match confine(&attachment_roots()?, path).await {
+                        Ok(file) => tokio::fs::read(file).await.ok(),
+                        Err(_) => None,
+                    },
+                };
+                match data {
+                    Some(data) => data,
+                    None => {
+                        unscanned.push(item);
+                        continue;
+                    }
+                }
+            }
+            _ => continue,
+        };
+        let scanned = &data[..data.len().min(license::MAX_SCAN_BYTES)];
+        items.push((item, String::from_utf8_lossy(scanned).into_owned()));
+    }
+    let items: Vec<(String, &str)> = items.iter().map(|(l, t)| (l.clone(), t.as_str())).collect();
+    license::enforce(actor, ko_id, &items, &unscanned).await
+}
+
+/// Guardian: caller-listed redactions, then built-in secret/PII detectors. Secrets fail the
+/// request; PII is redacted in place. Returns how many parts were redacted.
+async fn guard(bundle: &mut SpecBundle, project_id: Option<&str>) -> Result<usize> {
+    let guardian = GuardianService::for_project(project_id).await?;
+    let mut redacted = 0;
+    for (i, part) in bundle.parts.iter_mut().enumerate() {
+        let Some(text) = part.content_mut() else { continue };
+        for pat in &bundle.redactions {
+            if text.contains(pat.as_str()) {
+                return Err(anyhow!("guardian_redaction_violation: content contains redacted pattern '{}'", pat));
+            }
+        }
+        let clean = guardian.check(&format!("part {}", i + 1), text)?;
+        if clean != *text {
+            *text = clean;
+            redacted += 1;
+        }
+    }
+    Ok(redacted)
+}
+
+async fn verify_revision(store: &dyn BundleStore, dir: &str) -> Result<Option<(VerifyStatus, String)>> {
+    let manifest: Manifest = match store.get_string(&format!("{dir}/manifest.json")).await? {
+        Some(s) => serde_json::from_str(&s)?,
+        None => return Ok(Some((VerifyStatus::Unverified, "no manifest.json".into()))),
+    };
+    for f in &manifest.files {
+        match store.get(&format!("{dir}/{}", f.path)).await? {
+            Some(data) if sha256_hex(&data) == f.sha256 => {}
+            Some(_) => return Ok(Some((VerifyStatus::Corrupted, format!("hash mismatch: {}", f.path)))),
+            None => return Ok(Some((VerifyStatus::Corrupted, format!("missing: {}", f.path)))),
+        }
+    }
+    Ok(None)
+}
+
+/// Check one bundle folder: stray staging/temp files, head snapshot, every revision's manifest.
+async fn verify_folder(store: &dyn BundleStore, folder: &str, ko_id: Option<&str>) -> Result<Vec<VerifyProblem>> {
+    let mut problems = Vec::new();
+    let mut report = |path: String, status, detail: String| {
+        problems.push(VerifyProblem { ko_id: ko_id.map(str::to_string), path, status, detail })
+    };
+
+    let keys = store.list(&format!("{folder}/")).await?;
+    let mut interrupted: Vec<String> = store
+        .interrupted(&format!("{folder}/"))
+        .await?
+        .iter()
+        .filter_map(|k| {
+            let segs: Vec<&str> = k.split('/').collect();
+            let at = segs.iter().position(|s| s.starts_with(".staging-") || s.contains(".tmp-"))?;
+            Some(segs[..=at].join("/"))
+        })
+        .collect();
+    interrupted.dedup();
+    for path in interrupted {
+        report(path, VerifyStatus::Incomplete, "interrupted write".into());
+    }
+    if !keys.iter().any(|k| *k == format!("{folder}/bundle.json")) {
+        report(folder.to_string(), VerifyStatus::Incomplete, "no head bundle.json".into());
+    }
+
+    let revs = revision_numbers(store, folder).await?;
+    if revs.is_empty() {
+        report(folder.to_string(), VerifyStatus::Unverified, "no revisions (written before revisions existed)".into());
+    }
+    for n in revs {
+        let dir = revision_dir(folder, n);
+        if let Some((status, detail)) = verify_revision(store, &dir).await? {
+            report(dir, status, detail);
+        }
+    }
+    Ok(problems)
+}
+
+/// Hold the index lock while `ko_id` is live, so a concurrent `specbundle.delete` waits for
+/// the caller. `None` when the bundle has been deleted.
+pub(crate) async fn lock_live(ko_id: &str) -> Result<Option<MutexGuard<'static, ()>>> {
+    let store = store()?;
+    let guard = INDEX_LOCK.lock().await;
+    Ok(find_entry(store.as_ref(), ko_id).await?.deleted_at.is_none().then_some(guard))
+}
+
+async fn find_entry(store: &dyn BundleStore, ko_id: &str) -> Result<IndexEntry> {
+    read_index(store)
+        .await?
+        .into_iter()
+        .find(|e| e.ko_id == ko_id)
+        .ok_or_else(|| anyhow!("specbundle_not_found: {ko_id}"))
+}
+
+/// Archive layout: `bundle/<folder contents>` (head snapshot, revisions, audit) and
+/// `attachments/<part>/<file name>` for attachment parts of the head whose source is readable
+/// and inside `roots`.
+async fn export_archive(store: &dyn BundleStore, ko_id: &str, key: &[u8], roots: &[PathBuf]) -> Result<(Vec<u8>, usize, Vec<String>)> {
+    let entry = find_entry(store, ko_id).await?;
+    if entry.deleted_at.is_some() {
+        return Err(anyhow!("specbundle_deleted: {ko_id}"));
+    }
+    let folder = folder_for(ko_id);
+    let mut files = Vec::new();
+    for k in store.list(&format!("{folder}/")).await? {
+        let rel = &k[folder.len() + 1..];
+        if rel == "tombstone.json" {
+            continue;
+        }
+        let data = store.get(&k).await?.ok_or_else(|| anyhow!("'{k}' vanished during export"))?;
+        files.push((format!("bundle/{rel}"), data));
+    }
+
+    let head = read_revision(store, &folder, entry.revision).await?;
+    let mut missing = Vec::new();
+    for (i, part) in head.parts.iter().enumerate() {
+        let SpecPart::Attachment { path } = part else { continue };
+        let name = std::path::Path::new(path).file_name().map(|n| n.to_string_lossy().into_owned());
+        let data = match confine(roots, path).await {
+            Ok(file) => tokio::fs::read(file).await.ok(),
+            Err(_) => None,
+        };
+        match (name, data) {
+            (Some(name), Some(data)) => files.push((format!("attachments/{:04}/{name}", i + 1), data)),
+            _ => missing.push(path.clone()),
+        }
+    }
+
+    let count = files.len();
+    let meta = serde_json::json!({ "kind": "specbundle", "ko_id": ko_id, "entry": entry });
+    Ok((archive::pack(&files, meta, key)?, count, missing))
+}
+
+async fn import_archive(store: &dyn BundleStore, data: &[u8], key: &[u8], req: &SpecbundleImportReq) -> Result<SpecbundleImportResp> {
+    let unpacked = archive::unpack(data, key).map_err(|e| anyhow!("specbundle_archive_invalid: {e}"))?;
+    if unpacked.signature != SignatureStatus::Valid && !req.allow_unsigned {
+        return Err(anyhow!(
+            "specbundle_signature_invalid: archive signature is {:?}; set allow_unsigned to import anyway",
+            unpacked.signature
+        ));
+    }
+    let meta = &unpacked.manifest.meta;
+    if meta["kind"] != "specbundle" {
+        return Err(anyhow!("specbundle_archive_invalid: not a specbundle archive"));
+    }
+    let original = canonical_ko_id(meta["ko_id"].as_str().unwrap_or_default())?;
+    let entry: IndexEntry = serde_json::from_value(meta["entry"].clone())
+        .map_err(|e| anyhow!("specbundle_archive_invalid: bad index entry: {e}"))?;
+
+    let mut files = Vec::with_capacity(unpacked.files.len());
+    let mut attachments = 0;
+    for (path, data) in unpacked.files {
+        if let Some(rel) = path.strip_prefix("bundle/") {
+            files.push((rel.to_string(), data));
+        } else if path.starts_with("attachments/") {
+            attachments += 1;
+            files.push((path, data));
+        } else {
+            return Err(anyhow!("specbundle_archive_invalid: unexpected file '{path}'"));
+        }
+    }
+    let head = files
+        .iter()
+        .find(|(p, _)| p == "bundle.json")
+        .ok_or_else(|| anyhow!("specbundle_archive_invalid: no bundle.json"))?;
+    let mut bundle = parse_stored_bundle(&head.1)?;
+    validate_parts(&bundle.parts)?;
+    // Archived revisions are stored byte for byte, so PII cannot be redacted on the way in
+    if guard(&mut bundle, req.project_id.as_deref()).await? > 0 {
+        return Err(anyhow!("guardian_pii_detected: the archived head revision contains PII; redact it at the source and export again"));
+    }
+    let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+    // A re-import of a bundle already here is not a copy of itself
+    let originality = check_originality(&bundle, actor, Some(&original)).await?;
+    let licenses = check_licenses(&bundle, actor, None, Some(&files)).await?;
+
+    let _guard = INDEX_LOCK.lock().await;
+    let mut index = read_index(store).await?;
+    let taken = |id: &str| index.iter().any(|e| e.ko_id == id);
+    let ko_id = if !taken(&original) && store.list(&format!("{}/", folder_for(&original))).await?.is_empty() {
+        original.clone()
+    } else if req.on_conflict == ImportConflict::Fail {
+        return Err(anyhow!("specbundle_conflict: {original} already exists"));
+    } else {
+        format!("ko:specbundle/{}", Uuid::new_v4())
+    };
+
+    let folder = folder_for(&ko_id);
+    store.put_all(&folder, &files).await?;
+    let problems = verify_folder(store, &folder, Some(&ko_id)).await?;
+    if let Some(p) = problems.first() {
+        store.remove_prefix(&folder).await?;
+        return Err(anyhow!("specbundle_archive_invalid: {}: {}", p.path, p.detail));
+    }
+    // The stored revisions, not the archived index entry, decide what the head is
+    let revision = latest_revision(store, &folder).await?;
+    if entry.revision != revision {
+        store.remove_prefix(&folder).await?;
+        return Err(anyhow!(
+            "specbundle_archive_invalid: index entry names revision {} but the archive holds up to {revision}",
+            entry.revision
+        ));
+    }
+    index.push(IndexEntry { ko_id: ko_id.clone(), revision, deleted_at: None, ..entry });
+    write_index(store, &index).await?;
+    CONTEXT_DOCS.put(&ko_id, revision, &bundle);
+
+    Ok(SpecbundleImportResp {
+        remapped: ko_id != original,
+        ko_id,
+        original_ko_id: original,
+        revision,
+        attachments,
+        signature: unpacked.signature,
+        originality,
+        licenses,
+    })
+}
+
+/// Hash attachment parts: the copy stored with the bundle (imported archives), else the source
+/// file if it lies inside `roots`.
+async fn attachment_digests(store: &dyn BundleStore, folder: &str, bundle: &SpecBundle, roots: &[PathBuf]) -> Result<Vec<RenderedAttachment>> {
+    let mut out = Vec::new();
+    for (i, part) in bundle.parts.iter().enumerate() {
+        let SpecPart::Attachment { path } = part else { continue };
+        let stored = store.list(&format!("{folder}/attachments/{:04}/", i + 1)).await?;
+        let data = match (stored.first(), confine(roots, path).await) {
+            (Some(k), _) => store.get(k).await?,
+            (None, Ok(file)) => tokio::fs::read(file).await.ok(),
+            (None, Err(_)) => None,
+        };
+        out.push(RenderedAttachment {
+            part: i + 1,
+            name: std::path::Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone()),
+            source_path: path.clone(),
+            sha256: data.as_deref().map(sha256_hex),
+            bytes: data.as_ref().map(Vec::len),
+        });
+    }
+    Ok(out)
+}
+
+/// Case-insensitive literal match for `specbundle.search`.
+fn search_pattern(q: &str) -> Result<Regex> {
+    Ok(Regex::new(&format!("(?i){}", regex::escape(q)))?)
+}
+
+/// About 40 bytes either side of `text[at..at + len]`, widened to char boundaries.
+fn snippet(text: &str, at: usize, len: usize) -> String {
+    let mut start = at.saturating_sub(40);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (at + len + 40).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    text[start..end].replace('\n', " ")
+}
+
+pub struct SpecbundleService;
+
+impl SpecbundleService {
+    /// Handler for target="opencode_pm", op="specbundle.create"
+    pub async fn create(mut req: SpecbundleCreateReq) -> Result<SpecbundleCreateResp> {
+        let store = store()?;
+        let store = store.as_ref();
+
+        // 0) Part validation + Guardian, originality and license pre-checks
+        validate_parts(&req.bundle.parts)?;
+        guard(&mut req.bundle, req.project_id.as_deref()).await?;
+        let flagged = check_originality(&req.bundle, &req.bundle.created_by, None).await?;
+        let licenses = check_licenses(&req.bundle, &req.bundle.created_by, None, None).await?;
 
         // 1) Assign KO id + folder
         let ko_id = format!("ko:specbundle/{}", Uuid::new_v4());
-        let folder = base_dir()?.join(ko_id.replace(':', "_")); // ko_specbundle_<uuid>
-        let parts_dir = folder.join("parts");
-        fs::create_dir_all(&parts_dir).await?;
-
-        // 2) Persist bundle.json (metadata snapshot)
-        let bundle_json = serde_json::to_string_pretty(&req.bundle)?;
-        write_text(folder.join("bundle.json"), &bundle_json).await?;
-
-        // 3) Persist parts
-        let mut stored = 0usize;
-        for (i, part) in req.bundle.parts.iter().enumerate() {
-            let idx = format!("{:04}", i + 1);
-            match (part.kind.as_str(), &part.content, &part.path) {
-                ("markdown", Some(md), _) => {
-                    write_text(parts_dir.join(format!("{idx}.md")), md).await?;
-                    stored += 1;
-                }
-                ("mermaid", Some(mmd), _) => {
-                    write_text(parts_dir.join(format!("{idx}.mmd")), mmd).await?;
-                    stored += 1;
+        let folder = folder_for(&ko_id);
+
+        // 2+3) Persist revision 1 (bundle.json snapshot + parts)
+        let stored = write_revision(store, &folder, 1, &req.bundle, None).await?.stored_parts;
+
+        // 4) Index entry
+        {
+            let _guard = INDEX_LOCK.lock().await;
+            let mut index = read_index(store).await?;
+            index.retain(|e| e.ko_id != ko_id);
+            index.push(IndexEntry {
+                stored_parts: stored,
+                ..entry_for(&req.bundle, ko_id.clone(), OffsetDateTime::now_utc(), None)
+            });
+            write_index(store, &index).await?;
+        }
+        let detail = serde_json::json!({ "revision": 1, "stored_parts": stored, "tags": req.bundle.tags });
+        audit::record_committed(&req.bundle.created_by, "SPECBUNDLE.CREATED", Some(&ko_id), detail).await;
+        graph::record_committed(&req.bundle.created_by, "specbundle.create", graph::bundle_edges(&ko_id, &req.bundle)).await;
+        CONTEXT_DOCS.put(&ko_id, 1, &req.bundle);
+        originality::record_flagged(&req.bundle.created_by, Some(&ko_id), &flagged).await;
+        license::record(&req.bundle.created_by, Some(&ko_id), &licenses).await;
+
+        // 5) Optional ingestion, picked up by the background worker once indexed
+        let ingested = req.ingest.unwrap_or(false);
+        let mut ingest_job_id = None;
+        if ingested {
+            let job = ingest::queue()?.enqueue(&ko_id, 1).await?;
+            let detail = serde_json::json!({ "job_id": job.job_id, "revision": 1 });
+            audit::record_committed(&req.bundle.created_by, "INGEST.ENQUEUED", Some(&ko_id), detail).await;
+            ingest_job_id = Some(job.job_id);
+        }
+
+        Ok(SpecbundleCreateResp { ko_id, stored_parts: stored, ingested, ingest_job_id, originality: flagged, licenses })
+    }
+
+    /// Handler for op="specbundle.get"
+    pub async fn get(req: SpecbundleGetReq) -> Result<SpecbundleGetResp> {
+        let store = store()?;
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        let entry = find_entry(store.as_ref(), &ko_id).await?;
+        if entry.deleted_at.is_some() && !req.include_deleted {
+            return Err(anyhow!("specbundle_deleted: {ko_id}"));
+        }
+        let revision = req.revision.unwrap_or(entry.revision);
+        let bundle = read_revision(store.as_ref(), &folder_for(&ko_id), revision)
+            .await
+            .map_err(|e| anyhow!("specbundle_unreadable: {ko_id}: {e}"))?;
+        Ok(SpecbundleGetResp { entry, revision, bundle })
+    }
+
+    /// Handler for op="specbundle.revise": appends an immutable revision under the same KO id.
+    pub async fn revise(mut req: SpecbundleReviseReq) -> Result<SpecbundleReviseResp> {
+        let store = store()?;
+        let store = store.as_ref();
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        validate_parts(&req.bundle.parts)?;
+        guard(&mut req.bundle, req.project_id.as_deref()).await?;
+        let flagged = check_originality(&req.bundle, &req.bundle.created_by, Some(&ko_id)).await?;
+        let licenses = check_licenses(&req.bundle, &req.bundle.created_by, Some(&ko_id), None).await?;
+
+        let _guard = INDEX_LOCK.lock().await;
+        let mut index = read_index(store).await?;
+        let entry = index
+            .iter_mut()
+            .find(|e| e.ko_id == ko_id)
+            .ok_or_else(|| anyhow!("specbundle_not_found: {ko_id}"))?;
+        if entry.deleted_at.is_some() {
+            return Err(anyhow!("specbundle_deleted: {ko_id}"));
+        }
+        let revision = entry.revision + 1;
+        let meta = write_revision(store, &folder_for(&ko_id), revision, &req.bundle, req.message).await?;
+
+        // Keep the original creator and creation time; everything else follows the new head
+        *entry = IndexEntry {
+            created_by: entry.created_by.clone(),
+            stored_parts: meta.stored_parts,
+            revision,
+            updated_at: Some(meta.created_at),
+            ..entry_for(&req.bundle, ko_id.clone(), entry.created_at, None)
+        };
+        write_index(store, &index).await?;
+        let detail = serde_json::json!({ "revision": revision, "stored_parts": meta.stored_parts, "message": meta.message });
+        audit::record_committed(&req.bundle.created_by, "SPECBUNDLE.REVISED", Some(&ko_id), detail).await;
+        // The bundle id names the head; the revision it replaced stays addressable as `<ko_id>@<n>`
+        let mut edges = graph::bundle_edges(&ko_id, &req.bundle);
+        edges.push((ko_id.clone(), EdgeKind::Supersedes, format!("{ko_id}@{}", revision - 1)));
+        graph::record_committed(&req.bundle.created_by, "specbundle.revise", edges).await;
+        CONTEXT_DOCS.put(&ko_id, revision, &req.bundle);
+        originality::record_flagged(&req.bundle.created_by, Some(&ko_id), &flagged).await;
+        license::record(&req.bundle.created_by, Some(&ko_id), &licenses).await;
+
+        let ingested = match req.ingest {
+            Some(ingest) => ingest,
+            None => ingest::queue().is_ok_and(|q| q.knowledge().doc(&ko_id).is_some()),
+        };
+        let mut ingest_job_id = None;
+        if ingested {
+            let job = ingest::queue()?.enqueue(&ko_id, revision).await?;
+            let detail = serde_json::json!({ "job_id": job.job_id, "revision": revision });
+            audit::record_committed(&req.bundle.created_by, "INGEST.ENQUEUED", Some(&ko_id), detail).await;
+            ingest_job_id = Some(job.job_id);
+        }
+        Ok(SpecbundleReviseResp {
+            ko_id,
+            revision,
+            stored_parts: meta.stored_parts,
+            ingested,
+            ingest_job_id,
+            originality: flagged,
+            licenses,
+        })
+    }
+
+    /// Handler for op="specbundle.history"; oldest first.
+    pub async fn history(req: SpecbundleHistoryReq) -> Result<SpecbundleHistoryResp> {
+        let store = store()?;
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        let entry = find_entry(store.as_ref(), &ko_id).await?;
+        let folder = folder_for(&ko_id);
+        let mut revisions = Vec::new();
+        for n in 1..=entry.revision {
+            match read_revision_meta(store.as_ref(), &folder, n).await? {
+                Some(meta) => revisions.push(meta),
+                // Pre-revision bundle: synthesize from the index entry
+                None if n == 1 => revisions.push(RevisionMeta {
+                    revision: 1,
+                    created_at: entry.created_at,
+                    author: entry.created_by.clone(),
+                    message: None,
+                    stored_parts: entry.stored_parts,
+                }),
+                None => return Err(anyhow!("revision {n} not found")),
+            }
+        }
+        Ok(SpecbundleHistoryResp { ko_id, revisions })
+    }
+
+    /// Handler for op="specbundle.diff"
+    pub async fn diff(req: SpecbundleDiffReq) -> Result<SpecbundleDiffResp> {
+        let store = store()?;
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        let entry = find_entry(store.as_ref(), &ko_id).await?;
+        let to = req.to.unwrap_or(entry.revision);
+        let from = req.from.unwrap_or(to.saturating_sub(1).max(1));
+        for n in [from, to] {
+            if n == 0 || n > entry.revision {
+                return Err(anyhow!("revision {n} out of range 1..={}", entry.revision));
+            }
+        }
+        let folder = folder_for(&ko_id);
+        let a = read_revision(store.as_ref(), &folder, from).await?;
+        let b = read_revision(store.as_ref(), &folder, to).await?;
+        Ok(SpecbundleDiffResp { ko_id, from, to, diff: diff_bundles(&a, &b) })
+    }
+
+    /// Handler for op="specbundle.list"; newest first.
+    pub async fn list(req: SpecbundleListReq) -> Result<SpecbundleListResp> {
+        let store = store()?;
+        let mut items: Vec<IndexEntry> = read_index(store.as_ref()).await?.into_iter().filter(|e| req.matches(e)).collect();
+        items.reverse();
+        if let Some(limit) = req.limit { items.truncate(limit); }
+        Ok(SpecbundleListResp { items })
+    }
+
+    /// Handler for op="specbundle.search": case-insensitive text match over part contents.
+    pub async fn search(req: SpecbundleSearchReq) -> Result<SpecbundleSearchResp> {
+        let store = store()?;
+        if req.q.is_empty() {
+            return Err(anyhow!("empty query"));
+        }
+        let pattern = search_pattern(&req.q)?;
+        let limit = req.limit.unwrap_or(50);
+        let mut hits = Vec::new();
+        for entry in read_index(store.as_ref()).await?.into_iter().rev().filter(|e| e.deleted_at.is_none()) {
+            let Some(raw) = store.get(&format!("{}/bundle.json", folder_for(&entry.ko_id))).await? else { continue };
+            // One unreadable bundle must not hide every other hit
+            let bundle = match parse_stored_bundle(&raw) {
+                Ok(b) => b,
+                Err(e) => {
+                    tracing::warn!(ko_id = %entry.ko_id, "specbundle search: skipping unreadable bundle: {e}");
+                    continue;
                 }
-                ("attachment", _, Some(p)) => {
-                    // For now just record the path in a small descriptor
-                    let desc = serde_json::json!({ "source_path": p });
-                    write_text(parts_dir.join(format!("{idx}.attachment.json")), &desc.to_string()).await?;
-                    stored += 1;
+            };
+            for (i, part) in bundle.parts.iter().enumerate() {
+                let Some(text) = part.content() else { continue };
+                if let Some(m) = pattern.find(text) {
+                    hits.push(SearchHit {
+                        ko_id: entry.ko_id.clone(),
+                        title: entry.title.clone(),
+                        part: i + 1,
+                        snippet: snippet(text, m.start(), m.len()),
+                    });
+                    if hits.len() >= limit {
+                        return Ok(SpecbundleSearchResp { hits });
+                    }
                 }
-                _ => {}
             }
         }
+        Ok(SpecbundleSearchResp { hits })
+    }
 
-        // 4) Optional ingest enqueue (stub)
-        let ingested = req.ingest.unwrap_or(false);
-        if ingested {
-            let audit = serde_json::json!({
-              "event": "INGEST.ENQUEUED",
-              "ko_id": ko_id,
-              "at": OffsetDateTime::now_utc().to_string(),
-              "tags": req.bundle.tags,
-              "redactions": req.bundle.redactions,
-            });
-            write_text(folder.join("audit.json"), &serde_json::to_string_pretty(&audit)?).await?;
-            // TODO: emit real event on your bus
+    /// Handler for op="specbundle.verify"; also run once at startup.
+    pub async fn verify(req: SpecbundleVerifyReq) -> Result<SpecbundleVerifyResp> {
+        let store = store()?;
+        let store = store.as_ref();
+        if let Some(ko_id) = req.ko_id {
+            let ko_id = canonical_ko_id(&ko_id)?;
+            let folder = folder_for(&ko_id);
+            if store.list(&format!("{folder}/")).await?.is_empty() {
+                return Err(anyhow!("specbundle_not_found: {ko_id}"));
+            }
+            let problems = verify_folder(store, &folder, Some(&ko_id)).await?;
+            return Ok(SpecbundleVerifyResp { checked: 1, problems });
+        }
+
+        let folders = bundle_folders(&store.list("ko_specbundle_").await?);
+        let mut problems = Vec::new();
+        for folder in &folders {
+            let ko_id = format!("ko:specbundle/{}", folder.trim_start_matches("ko_specbundle_"));
+            problems.extend(verify_folder(store, folder, Some(&ko_id)).await?);
+        }
+        problems.extend(reconcile_index(store, &folders).await?);
+        for e in read_index(store).await? {
+            let folder = folder_for(&e.ko_id);
+            if e.deleted_at.is_none() && !folders.contains(&folder) {
+                problems.push(VerifyProblem {
+                    path: folder,
+                    ko_id: Some(e.ko_id),
+                    status: VerifyStatus::Missing,
+                    detail: "indexed but folder is missing".into(),
+                });
+            }
+        }
+        Ok(SpecbundleVerifyResp { checked: folders.len(), problems })
+    }
+
+    /// Handler for op="specbundle.delete": tombstones the entry, optionally purging the folder.
+    pub async fn delete(req: SpecbundleDeleteReq) -> Result<SpecbundleDeleteResp> {
+        let store = store()?;
+        let store = store.as_ref();
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        let _guard = INDEX_LOCK.lock().await;
+        let mut index = read_index(store).await?;
+        let entry = index
+            .iter_mut()
+            .find(|e| e.ko_id == ko_id)
+            .ok_or_else(|| anyhow!("specbundle_not_found: {ko_id}"))?;
+        let deleted_at = *entry.deleted_at.get_or_insert_with(OffsetDateTime::now_utc);
+        let resp = SpecbundleDeleteResp { ko_id: ko_id.clone(), deleted_at, purged: req.purge };
+
+        let folder = folder_for(&ko_id);
+        // Deleted bundles must not keep surfacing as context
+        CONTEXT_DOCS.remove(&ko_id);
+        if req.purge {
+            store.remove_prefix(&folder).await?;
+        } else if !store.list(&format!("{folder}/")).await?.is_empty() {
+            store.put(&format!("{folder}/tombstone.json"), serde_json::to_string_pretty(&resp)?.as_bytes()).await?;
+        }
+        write_index(store, &index).await?;
+        // Ingest checks for the tombstone under INDEX_LOCK, so nothing re-adds the entry after this
+        if let Ok(q) = ingest::queue()
+            && let Err(e) = q.knowledge().remove(&ko_id)
+        {
+            tracing::warn!(%ko_id, "specbundle delete: knowledge entry not removed: {e}");
+        }
+        let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+        audit::record_committed(actor, "SPECBUNDLE.DELETED", Some(&ko_id), serde_json::json!({ "purged": req.purge })).await;
+        Ok(resp)
+    }
+
+    /// Handler for op="specbundle.render": one Markdown document and/or one HTML page.
+    pub async fn render(req: SpecbundleRenderReq) -> Result<SpecbundleRenderResp> {
+        let got = Self::get(SpecbundleGetReq { ko_id: req.ko_id, revision: req.revision, include_deleted: false }).await?;
+        let store = store()?;
+        let attachments = attachment_digests(store.as_ref(), &folder_for(&got.entry.ko_id), &got.bundle, &attachment_roots()?).await?;
+        let mermaid_js = match req.format {
+            RenderFormat::Markdown => None,
+            _ => render::vendored_mermaid().await,
+        };
+        let input = RenderInput {
+            ko_id: &got.entry.ko_id,
+            revision: got.revision,
+            bundle: &got.bundle,
+            attachments: &attachments,
+            mermaid_js: mermaid_js.as_deref(),
+        };
+        let markdown = (req.format != RenderFormat::Html).then(|| render::to_markdown(&input));
+        let html = (req.format != RenderFormat::Markdown).then(|| render::to_html(&input));
+        Ok(SpecbundleRenderResp { ko_id: got.entry.ko_id, revision: got.revision, markdown, html, attachments })
+    }
+
+    /// Handler for op="specbundle.export": writes a signed `.tar.zst` of the whole bundle.
+    pub async fn export(req: SpecbundleExportReq) -> Result<SpecbundleExportResp> {
+        let store = store()?;
+        let ko_id = canonical_ko_id(&req.ko_id)?;
+        let key = archive::signing_key().await?;
+        let (data, files, missing_attachments) = export_archive(store.as_ref(), &ko_id, &key, &attachment_roots()?).await?;
+
+        let dir = export_dir()?;
+        tokio::fs::create_dir_all(&dir).await?;
+        let requested = req.out_path.unwrap_or_else(|| format!("{}.tar.zst", folder_for(&ko_id)));
+        let path = confine(&[dir], &requested).await?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, &data).await?;
+        let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({ "path": path.display().to_string(), "sha256": sha256_hex(&data) });
+        audit::record_committed(actor, "SPECBUNDLE.EXPORTED", Some(&ko_id), detail).await;
+        Ok(SpecbundleExportResp {
+            ko_id,
+            path: path.display().to_string(),
+            bytes: data.len(),
+            sha256: sha256_hex(&data),
+            files,
+            missing_attachments,
+        })
+    }
+
+    /// Handler for op="specbundle.import": verifies an exported archive and restores it.
+    pub async fn import(req: SpecbundleImportReq) -> Result<SpecbundleImportResp> {
+        let store = store()?;
+        let path = confine(&import_dirs()?, &req.path).await?;
+        let data = tokio::fs::read(&path).await.map_err(|e| anyhow!("cannot read '{}': {e}", req.path))?;
+        let key = archive::signing_key().await?;
+        let resp = import_archive(store.as_ref(), &data, &key, &req).await?;
+        let actor = req.actor.as_deref().unwrap_or(audit::ANONYMOUS_ACTOR);
+        let detail = serde_json::json!({
+            "path": req.path,
+            "original_ko_id": resp.original_ko_id,
+            "remapped": resp.remapped,
+            "signature": resp.signature,
+        });
+        audit::record_committed(actor, "SPECBUNDLE.IMPORTED", Some(&resp.ko_id), detail).await;
+        originality::record_flagged(actor, Some(&resp.ko_id), &resp.originality).await;
+        license::record(actor, Some(&resp.ko_id), &resp.licenses).await;
+        if resp.remapped {
+            graph::record_committed(actor, "specbundle.import", [(resp.ko_id.clone(), EdgeKind::DerivedFrom, resp.original_ko_id.clone())]).await;
+        }
+        Ok(resp)
+    }
+}
+
+/// Resolves `ko:specbundle/<uuid>` to the rendered markdown of its head revision;
+/// `ko:specbundle/<uuid>@<n>` selects revision n.
+pub struct SpecbundleKoHandler;
+
+#[async_trait]
+impl KoHandler for SpecbundleKoHandler {
+    fn namespace(&self) -> &str {
+        "specbundle"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> std::result::Result<ResolvedKo, KoError> {
+        let (raw, revision) = match uri.path.split_once('@') {
+            Some((raw, n)) => {
+                let n = n.parse().map_err(|_| KoError::InvalidUri(format!("'{uri}': bad revision '{n}'")))?;
+                (raw, Some(n))
+            }
+            None => (uri.path.as_str(), None),
+        };
+        let id = Uuid::parse_str(raw).map_err(|_| KoError::InvalidUri(format!("'{uri}': not a specbundle id")))?;
+        let ko_id = format!("ko:specbundle/{id}");
+        Self::get_rendered(&ko_id, revision).await.map_err(|e| ko_resolve::ko_error(&ko_id, e))
+    }
+}
+
+impl SpecbundleKoHandler {
+    async fn get_rendered(ko_id: &str, revision: Option<u32>) -> Result<ResolvedKo> {
+        let mut got = SpecbundleService::get(SpecbundleGetReq { ko_id: ko_id.to_string(), revision: None, include_deleted: false }).await?;
+        if let Some(n) = revision.filter(|n| *n != got.revision) {
+            if n == 0 || n > got.entry.revision {
+                return Err(anyhow!("revision_not_found: {ko_id}@{n}"));
+            }
+            got = SpecbundleService::get(SpecbundleGetReq { ko_id: ko_id.to_string(), revision, include_deleted: false }).await?;
+        }
+        let rendered = SpecbundleService::render(SpecbundleRenderReq {
+            ko_id: got.entry.ko_id.clone(),
+            revision: Some(got.revision),
+            format: RenderFormat::Markdown,
+        })
+        .await?;
+        Ok(ResolvedKo {
+            id: got.entry.ko_id.clone(),
+            namespace: "specbundle".into(),
+            title: Some(got.bundle.title.clone()),
+            media_type: "text/markdown".into(),
+            content: rendered.markdown.unwrap_or_default(),
+            metadata: serde_json::json!({
+                "revision": got.revision,
+                "head_revision": got.entry.revision,
+                "created_by": got.entry.created_by,
+                "created_at": got.entry.created_at.format(&time::format_description::well_known::Rfc3339)?,
+                "tags": got.entry.tags,
+                "parts": got.bundle.parts.len(),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::storage::LocalFsStore;
+    use time::macros::datetime;
+
+    fn entry() -> IndexEntry {
+        IndexEntry {
+            ko_id: "ko:specbundle/00000000-0000-0000-0000-000000000001".into(),
+            title: "Queue Engine Spec".into(),
+            created_by: "alice".into(),
+            agents: vec!["claude".into(), "codex".into()],
+            tags: vec!["queue".into(), "draft".into()],
+            created_at: datetime!(2025-09-18 12:00:00 UTC),
+            stored_parts: 2,
+            revision: 1,
+            updated_at: None,
+            deleted_at: None,
+        }
+    }
+
+    fn part(kind: &str, content: &str) -> SpecPart {
+        serde_json::from_value(serde_json::json!({ "kind": kind, "content": content })).unwrap()
+    }
+
+    fn bundle(title: &str, parts: Vec<SpecPart>) -> SpecBundle {
+        SpecBundle {
+            title: title.into(),
+            created_by: "alice".into(),
+            source_sessions: vec![],
+            parts,
+            tags: vec![],
+            redactions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_diff_bundles_parts() {
+        let a = bundle("Spec", vec![part("markdown", "# A\nold line\n"), part("mermaid", "graph TD; A-->B")]);
+        let b = bundle(
+            "Spec v2",
+            vec![part("markdown", "# A\nnew line\n"), part("mermaid", "graph TD; A-->B"), part("json", "{}")],
+        );
+        let d = diff_bundles(&a, &b);
+        assert_eq!(d.fields_changed, vec!["title".to_string()]);
+        assert_eq!(d.added, vec![PartRef { index: 3, kind: "json".into() }]);
+        assert!(d.removed.is_empty());
+        assert_eq!(d.changed.len(), 1);
+        let ld = d.changed[0].line_diff.as_deref().unwrap();
+        assert!(ld.contains("-old line") && ld.contains("+new line"));
+
+        let back = diff_bundles(&b, &a);
+        assert_eq!(back.removed, vec![PartRef { index: 3, kind: "json".into() }]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_detects_corruption_and_interrupted_writes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = LocalFsStore::new(tmp.path());
+        let folder = "ko_specbundle_x";
+        let b = bundle("Spec", vec![part("markdown", "# A\n"), part("mermaid", "graph TD")]);
+        write_revision(&store, folder, 1, &b, None).await.unwrap();
+        write_revision(&store, folder, 2, &b, Some("same".into())).await.unwrap();
+        assert!(write_revision(&store, folder, 2, &b, None).await.is_err());
+        assert_eq!(revision_numbers(&store, folder).await.unwrap(), vec![1, 2]);
+        assert_eq!(read_revision(&store, folder, 2).await.unwrap().title, "Spec");
+        assert!(verify_folder(&store, folder, None).await.unwrap().is_empty());
+
+        let rev2 = tmp.path().join(folder).join("revisions/0002");
+        std::fs::write(rev2.join("parts/0001.md"), "tampered").unwrap();
+        std::fs::create_dir_all(tmp.path().join(folder).join("revisions/.staging-deadbeef")).unwrap();
+        std::fs::write(tmp.path().join(folder).join("revisions/.staging-deadbeef/bundle.json"), "{").unwrap();
+        let problems = verify_folder(&store, folder, None).await.unwrap();
+        let statuses: Vec<_> = problems.iter().map(|p| p.status).collect();
+        assert_eq!(statuses, vec![VerifyStatus::Incomplete, VerifyStatus::Corrupted]);
+        assert!(problems[1].detail.contains("parts/0001.md"));
+    }
+
+    #[test]
+    fn test_stored_bundle_with_legacy_parts() {
+        let raw = serde_json::json!({
+            "title": "Old", "created_by": "alice", "source_sessions": [], "tags": [], "redactions": [],
+            "parts": [
+                {"kind": "markdown", "path": null, "content": "# A"},
+                {"kind": "notes", "path": null, "content": "free text"},
+                {"kind": "attachment", "path": "docs/a.pdf", "content": null},
+            ],
+        });
+        let b = parse_stored_bundle(raw.to_string().as_bytes()).unwrap();
+        assert_eq!(b.parts.iter().map(|p| p.kind()).collect::<Vec<_>>(), vec!["markdown", "markdown", "attachment"]);
+    }
+
+    #[tokio::test]
+    async fn test_verify_reindexes_orphaned_folders() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = LocalFsStore::new(tmp.path());
+        write_index(&store, &[]).await.unwrap();
+        let ko_id = entry().ko_id;
+        let folder = folder_for(&ko_id);
+        write_revision(&store, &folder, 1, &bundle("Orphan", vec![part("markdown", "# A\n")]), None).await.unwrap();
+
+        let added = reconcile_index(&store, std::slice::from_ref(&folder)).await.unwrap();
+        assert_eq!(added.iter().map(|p| p.status).collect::<Vec<_>>(), vec![VerifyStatus::Unindexed]);
+        assert_eq!(find_entry(&store, &ko_id).await.unwrap().title, "Orphan");
+        assert!(reconcile_index(&store, &[folder]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        let src = tempfile::tempdir().unwrap();
+        let store = LocalFsStore::new(src.path().join("store"));
+        let attachment = src.path().join("notes.txt");
+        std::fs::write(&attachment, "attached").unwrap();
+        let ko_id = entry().ko_id;
+        let folder = folder_for(&ko_id);
+        let mut b = bundle("Spec", vec![part("markdown", "# A\n")]);
+        b.parts.push(SpecPart::Attachment { path: attachment.display().to_string() });
+        write_revision(&store, &folder, 1, &b, None).await.unwrap();
+        write_revision(&store, &folder, 2, &b, Some("again".into())).await.unwrap();
+        write_index(&store, &[IndexEntry { revision: 2, ..entry() }]).await.unwrap();
+
+        let roots = [src.path().to_path_buf()];
+        let (data, files, missing) = export_archive(&store, &ko_id, b"key", &roots).await.unwrap();
+        assert!(missing.is_empty());
+        // Sources outside the attachment roots are never read
+        let elsewhere = tempfile::tempdir().unwrap();
+        let (_, _, missing) = export_archive(&store, &ko_id, b"key", &[elsewhere.path().to_path_buf()]).await.unwrap();
+        assert_eq!(missing, vec![attachment.display().to_string()]);
+        assert!(files > 2);
+
+        let req = |on_conflict, allow_unsigned| SpecbundleImportReq {
+            path: String::new(),
+            on_conflict,
+            allow_unsigned,
+            project_id: None,
+            actor: None,
+        };
+        // Into an empty store: KO id preserved, all revisions restored
+        let dst = LocalFsStore::new(src.path().join("other"));
+        let out = import_archive(&dst, &data, b"key", &req(ImportConflict::Remap, false)).await.unwrap();
+        assert_eq!((out.ko_id.as_str(), out.remapped, out.revision, out.attachments), (ko_id.as_str(), false, 2, 1));
+        assert_eq!(revision_numbers(&dst, &folder).await.unwrap(), vec![1, 2]);
+        assert_eq!(dst.get_string(&format!("{folder}/attachments/0002/notes.txt")).await.unwrap().unwrap(), "attached");
+
+        // Back into the source store: conflict
+        let err = import_archive(&store, &data, b"key", &req(ImportConflict::Fail, false)).await.unwrap_err();
+        assert!(err.to_string().starts_with("specbundle_conflict"));
+        let out = import_archive(&store, &data, b"key", &req(ImportConflict::Remap, false)).await.unwrap();
+        assert!(out.remapped && out.ko_id != ko_id);
+        assert_eq!(read_index(&store).await.unwrap().len(), 2);
+
+        // Wrong key
+        let err = import_archive(&dst, &data, b"other", &req(ImportConflict::Remap, false)).await.unwrap_err();
+        assert!(err.to_string().starts_with("specbundle_signature_invalid"));
+        let out = import_archive(&dst, &data, b"other", &req(ImportConflict::Remap, true)).await.unwrap();
+        assert_eq!(out.signature, SignatureStatus::Invalid);
+
+        // An index entry that disagrees with the archived revisions is rejected and leaves nothing behind
+        write_index(&store, &[IndexEntry { revision: 1, ..entry() }]).await.unwrap();
+        let (data, _, _) = export_archive(&store, &ko_id, b"key", &roots).await.unwrap();
+        let fresh = LocalFsStore::new(src.path().join("fresh"));
+        let err = import_archive(&fresh, &data, b"key", &req(ImportConflict::Remap, false)).await.unwrap_err();
+        assert!(err.to_string().starts_with("specbundle_archive_invalid: index entry names revision 1"));
+        assert!(fresh.list(&format!("{folder}/")).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_insert_at_top_reports_only_that_part() {
+        let a = bundle("S", vec![part("markdown", "# A\n"), part("mermaid", "graph TD"), part("json", "{}")]);
+        let b = bundle("S", vec![part("yaml", "k: v"), part("markdown", "# A\n"), part("mermaid", "graph TD"), part("json", "{}")]);
+        let d = diff_bundles(&a, &b);
+        assert_eq!(d.added, vec![PartRef { index: 1, kind: "yaml".into() }]);
+        assert!(d.removed.is_empty() && d.changed.is_empty());
+
+        let d = diff_bundles(&b, &a);
+        assert_eq!(d.removed, vec![PartRef { index: 1, kind: "yaml".into() }]);
+        assert!(d.added.is_empty() && d.changed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_kind_change_has_no_line_diff() {
+        let d = diff_bundles(&bundle("S", vec![part("markdown", "x")]), &bundle("S", vec![part("mermaid", "x")]));
+        assert_eq!(d.changed[0].kind_to, "mermaid");
+        assert!(d.changed[0].line_diff.is_none());
+    }
+
+    #[test]
+    fn test_search_snippet_on_non_ascii_text() {
+        // 'İ' lowercases to two chars, which shifted offsets taken from a lowercased copy
+        let text = format!("{}Queue ENGINE leasing{}", "İ".repeat(30), "ß".repeat(30));
+        let m = search_pattern("queue engine").unwrap().find(&text).unwrap();
+        assert_eq!(&text[m.start()..m.end()], "Queue ENGINE");
+        assert!(snippet(&text, m.start(), m.len()).contains("Queue ENGINE leasing"));
+        assert!(search_pattern("a.b").unwrap().find("axb").is_none());
+    }
+
+    #[test]
+    fn test_canonical_ko_id() {
+        let id = "00000000-0000-0000-0000-000000000001";
+        assert_eq!(canonical_ko_id(&format!("ko://specbundle/{id}")).unwrap(), format!("ko:specbundle/{id}"));
+        assert!(canonical_ko_id("ko:specbundle/../../etc").is_err());
+        assert!(canonical_ko_id("ko://runlog/1").is_err());
+    }
+
+    #[test]
+    fn test_list_filters() {
+        let e = entry();
+        assert!(SpecbundleListReq::default().matches(&e));
+        assert!(SpecbundleListReq { tag: Some("queue".into()), agent: Some("codex".into()), ..Default::default() }.matches(&e));
+        assert!(!SpecbundleListReq { created_by: Some("bob".into()), ..Default::default() }.matches(&e));
+        assert!(SpecbundleListReq { q: Some("ENGINE".into()), ..Default::default() }.matches(&e));
+        assert!(!SpecbundleListReq {
+            created_after: Some(datetime!(2025-09-19 00:00:00 UTC)),
+            ..Default::default()
         }
+        .matches(&e));
+        assert!(SpecbundleListReq {
+            created_after: Some(datetime!(2025-09-18 00:00:00 UTC)),
+            created_before: Some(datetime!(2025-09-19 00:00:00 UTC)),
+            ..Default::default()
+        }
+        .matches(&e));
+    }
 
-        Ok(SpecbundleCreateResp { ko_id, stored_parts: stored, ingested })
+    #[test]
+    fn test_tombstoned_entries_hidden_by_default() {
+        let e = IndexEntry { deleted_at: Some(datetime!(2025-09-20 00:00:00 UTC)), ..entry() };
+        assert!(!SpecbundleListReq::default().matches(&e));
+        assert!(SpecbundleListReq { include_deleted: true, ..Default::default() }.matches(&e));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,272 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagramFormat {
+    Plantuml,
+    Graphviz,
+}
+
+/// One SpecBundle part, tagged by `kind`. Unknown kinds fail deserialization instead of
+/// being dropped; `validate` then checks each part's content against its kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpecPart {
+    Markdown { content: String },
+    Mermaid { content: String },
+    Attachment { path: String },
+    Json { content: String },
+    Yaml { content: String },
+    /// OpenAPI/Swagger document as JSON or YAML.
+    Openapi { content: String },
+    Code { language: String, content: String },
+    Diagram { format: DiagramFormat, content: String },
+    Link { url: String, title: Option<String> },
+}
+
+/// Part shape written before kinds were typed: a free-form `kind` with optional `path` and
+/// `content`. Only read back from storage; requests must use the typed form.
+#[derive(Debug, Deserialize)]
+pub struct LegacyPart {
+    kind: String,
+    path: Option<String>,
+    content: Option<String>,
+}
+
+impl From<LegacyPart> for SpecPart {
+    /// Known kinds keep their meaning; anything else keeps its text as markdown so part
+    /// positions (and search hits) stay where they were.
+    fn from(p: LegacyPart) -> Self {
+        let content = p.content.unwrap_or_default();
+        match (p.kind.as_str(), p.path) {
+            ("attachment", Some(path)) => SpecPart::Attachment { path },
+            ("mermaid", _) => SpecPart::Mermaid { content },
+            ("json", _) => SpecPart::Json { content },
+            ("yaml", _) => SpecPart::Yaml { content },
+            ("openapi", _) => SpecPart::Openapi { content },
+            ("code", _) => SpecPart::Code { language: "text".into(), content },
+            _ => SpecPart::Markdown { content },
+        }
+    }
+}
+
+/// A stored part: the typed form, else the legacy shape.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum StoredPart {
+    Typed(SpecPart),
+    Legacy(LegacyPart),
+}
+
+impl From<StoredPart> for SpecPart {
+    fn from(p: StoredPart) -> Self {
+        match p {
+            StoredPart::Typed(p) => p,
+            StoredPart::Legacy(p) => p.into(),
+        }
+    }
+}
+
+fn code_ext(language: &str) -> &'static str {
+    match language.to_ascii_lowercase().as_str() {
+        "rust" | "rs" => "rs",
+        "python" | "py" => "py",
+        "javascript" | "js" => "js",
+        "typescript" | "ts" => "ts",
+        "go" | "golang" => "go",
+        "java" => "java",
+        "c" => "c",
+        "cpp" | "c++" => "cpp",
+        "shell" | "bash" | "sh" => "sh",
+        "sql" => "sql",
+        "toml" => "toml",
+        "html" => "html",
+        "css" => "css",
+        _ => "txt",
+    }
+}
+
+fn parse_structured(content: &str) -> Result<serde_json::Value> {
+    match serde_json::from_str(content) {
+        Ok(v) => Ok(v),
+        Err(_) => Ok(serde_yaml::from_str(content)?),
+    }
+}
+
+impl SpecPart {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpecPart::Markdown { .. } => "markdown",
+            SpecPart::Mermaid { .. } => "mermaid",
+            SpecPart::Attachment { .. } => "attachment",
+            SpecPart::Json { .. } => "json",
+            SpecPart::Yaml { .. } => "yaml",
+            SpecPart::Openapi { .. } => "openapi",
+            SpecPart::Code { .. } => "code",
+            SpecPart::Diagram { .. } => "diagram",
+            SpecPart::Link { .. } => "link",
+        }
+    }
+
+    /// Inline text of the part, if it carries any (attachments and links do not).
+    pub fn content(&self) -> Option<&str> {
+        match self {
+            SpecPart::Markdown { content }
+            | SpecPart::Mermaid { content }
+            | SpecPart::Json { content }
+            | SpecPart::Yaml { content }
+            | SpecPart::Openapi { content }
+            | SpecPart::Code { content, .. }
+            | SpecPart::Diagram { content, .. } => Some(content),
+            SpecPart::Attachment { .. } | SpecPart::Link { .. } => None,
+        }
+    }
+
+    pub fn content_mut(&mut self) -> Option<&mut String> {
+        match self {
+            SpecPart::Markdown { content }
+            | SpecPart::Mermaid { content }
+            | SpecPart::Json { content }
+            | SpecPart::Yaml { content }
+            | SpecPart::Openapi { content }
+            | SpecPart::Code { content, .. }
+            | SpecPart::Diagram { content, .. } => Some(content),
+            SpecPart::Attachment { .. } | SpecPart::Link { .. } => None,
+        }
+    }
+
+    /// File name (without the `NNNN.` index) used when the part is persisted.
+    pub fn file_suffix(&self) -> String {
+        match self {
+            SpecPart::Markdown { .. } => "md".into(),
+            SpecPart::Mermaid { .. } => "mmd".into(),
+            SpecPart::Attachment { .. } => "attachment.json".into(),
+            SpecPart::Json { .. } => "json".into(),
+            SpecPart::Yaml { .. } => "yaml".into(),
+            SpecPart::Openapi { content } if content.trim_start().starts_with('{') => "openapi.json".into(),
+            SpecPart::Openapi { .. } => "openapi.yaml".into(),
+            SpecPart::Code { language, .. } => code_ext(language).into(),
+            SpecPart::Diagram { format: DiagramFormat::Plantuml, .. } => "puml".into(),
+            SpecPart::Diagram { format: DiagramFormat::Graphviz, .. } => "dot".into(),
+            SpecPart::Link { .. } => "link.json".into(),
+        }
+    }
+
+    /// Bytes written for the part: inline content, or a small JSON descriptor.
+    pub fn stored_body(&self) -> String {
+        match self {
+            // For now just record the path in a small descriptor
+            SpecPart::Attachment { path } => serde_json::json!({ "source_path": path }).to_string(),
+            SpecPart::Link { url, title } => serde_json::json!({ "url": url, "title": title }).to_string(),
+            _ => self.content().unwrap_or_default().to_string(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(content) = self.content() && content.trim().is_empty() {
+            return Err(anyhow!("empty content"));
+        }
+        match self {
+            SpecPart::Attachment { path } if path.trim().is_empty() => Err(anyhow!("empty path")),
+            SpecPart::Json { content } => serde_json::from_str::<serde_json::Value>(content)
+                .map(|_| ())
+                .map_err(|e| anyhow!("invalid JSON: {e}")),
+            SpecPart::Yaml { content } => serde_yaml::from_str::<serde_yaml::Value>(content)
+                .map(|_| ())
+                .map_err(|e| anyhow!("invalid YAML: {e}")),
+            SpecPart::Openapi { content } => {
+                let doc = parse_structured(content).map_err(|e| anyhow!("invalid OpenAPI document: {e}"))?;
+                if doc.get("openapi").is_none() && doc.get("swagger").is_none() {
+                    return Err(anyhow!("OpenAPI document has no 'openapi' or 'swagger' version field"));
+                }
+                if doc.get("info").is_none() {
+                    return Err(anyhow!("OpenAPI document has no 'info' object"));
+                }
+                Ok(())
+            }
+            SpecPart::Code { language, .. } if language.trim().is_empty() => Err(anyhow!("code part needs a language")),
+            SpecPart::Diagram { format: DiagramFormat::Plantuml, content } if !content.contains("@start") => {
+                Err(anyhow!("PlantUML diagram must contain an @start... block"))
+            }
+            SpecPart::Diagram { format: DiagramFormat::Graphviz, content }
+                if !content.split_whitespace().take(3).any(|w| w == "graph" || w == "digraph") =>
+            {
+                Err(anyhow!("Graphviz diagram must start with 'graph' or 'digraph'"))
+            }
+            SpecPart::Link { url, .. } => match reqwest::Url::parse(url) {
+                Ok(u) if matches!(u.scheme(), "http" | "https" | "ko") => Ok(()),
+                Ok(u) => Err(anyhow!("unsupported link scheme '{}'", u.scheme())),
+                Err(e) => Err(anyhow!("invalid link url: {e}")),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Validate every part, naming the first offender as `part N (kind): reason`.
+pub fn validate_parts(parts: &[SpecPart]) -> Result<()> {
+    for (i, part) in parts.iter().enumerate() {
+        part.validate()
+            .map_err(|e| anyhow!("invalid_spec_part: part {} ({}): {e}", i + 1, part.kind()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(v: serde_json::Value) -> Result<SpecPart, serde_json::Error> {
+        serde_json::from_value(v)
+    }
+
+    #[test]
+    fn test_legacy_parts_still_parse() {
+        let p = parse(serde_json::json!({"kind": "markdown", "path": null, "content": "# A"})).unwrap();
+        assert_eq!(p, SpecPart::Markdown { content: "# A".into() });
+        let p = parse(serde_json::json!({"kind": "attachment", "path": "docs/a.pdf"})).unwrap();
+        assert_eq!(p.file_suffix(), "attachment.json");
+    }
+
+    #[test]
+    fn test_stored_legacy_parts_fall_back() {
+        let stored = |v| SpecPart::from(serde_json::from_value::<StoredPart>(v).unwrap());
+        let p = stored(serde_json::json!({"kind": "spreadsheet", "path": null, "content": "a,b"}));
+        assert_eq!(p, SpecPart::Markdown { content: "a,b".into() });
+        let p = stored(serde_json::json!({"kind": "code", "content": "fn main() {}"}));
+        assert_eq!(p, SpecPart::Code { language: "text".into(), content: "fn main() {}".into() });
+        let p = stored(serde_json::json!({"kind": "link", "url": "https://example.com", "title": null}));
+        assert_eq!(p.kind(), "link");
+    }
+
+    #[test]
+    fn test_unknown_kind_rejected() {
+        let err = parse(serde_json::json!({"kind": "spreadsheet", "content": "a,b"})).unwrap_err();
+        assert!(err.to_string().contains("unknown variant `spreadsheet`"));
+    }
+
+    #[test]
+    fn test_content_validation() {
+        let ok = [
+            serde_json::json!({"kind": "json", "content": "{\"a\": 1}"}),
+            serde_json::json!({"kind": "yaml", "content": "a: 1\nb: [x, y]"}),
+            serde_json::json!({"kind": "openapi", "content": "openapi: 3.0.3\ninfo: {title: x, version: '1'}\npaths: {}"}),
+            serde_json::json!({"kind": "code", "language": "rust", "content": "fn main() {}"}),
+            serde_json::json!({"kind": "diagram", "format": "graphviz", "content": "digraph { a -> b }"}),
+            serde_json::json!({"kind": "diagram", "format": "plantuml", "content": "@startuml\nA -> B\n@enduml"}),
+            serde_json::json!({"kind": "link", "url": "https://example.com/spec", "title": "Spec"}),
+        ];
+        let parts: Vec<SpecPart> = ok.into_iter().map(|v| parse(v).unwrap()).collect();
+        validate_parts(&parts).unwrap();
+        assert_eq!(parts[2].file_suffix(), "openapi.yaml");
+        assert_eq!(parts[3].file_suffix(), "rs");
+
+        let bad = SpecPart::Json { content: "{nope".into() };
+        let err = validate_parts(&[SpecPart::Markdown { content: "x".into() }, bad]).unwrap_err();
+        assert!(err.to_string().starts_with("invalid_spec_part: part 2 (json): invalid JSON"));
+        assert!(SpecPart::Openapi { content: "{\"info\": {}}".into() }.validate().is_err());
+        assert!(SpecPart::Link { url: "file:///etc/passwd".into(), title: None }.validate().is_err());
+        assert!(SpecPart::Markdown { content: "  ".into() }.validate().is_err());
+    }
+}
@@ -0,0 +1,640 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use time::OffsetDateTime;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::services::util::{replace_file, sha256_hex, sync_dir, write_synced};
+
+/// Root for all local PM state. `TEMPEXT_GENESIS_HOME` overrides `$HOME/.tempext-genesis`.
+pub fn genesis_home() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("TEMPEXT_GENESIS_HOME").filter(|v| !v.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("no HOME dir"))?;
+    Ok(home.join(".tempext-genesis"))
+}
+
+/// Resolve a caller-supplied path inside one of `roots`: relative paths are taken against
+/// the first root, absolute ones must already lie inside a root. `..` is refused and
+/// symlinks are followed before the check, so neither can step outside.
+pub async fn confine(roots: &[PathBuf], requested: &str) -> Result<PathBuf> {
+    let p = Path::new(requested);
+    let denied = || anyhow!("path_not_allowed: '{requested}' is outside the allowed directories");
+    if requested.is_empty() || p.components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+        return Err(denied());
+    }
+    let first = roots.first().ok_or_else(denied)?;
+    let joined = if p.is_absolute() { p.to_path_buf() } else { first.join(p) };
+    // The deepest existing ancestor decides where the path really points
+    let mut existing = joined.as_path();
+    let mut rest = Vec::new();
+    while !fs::try_exists(existing).await.unwrap_or(false) {
+        rest.push(existing.file_name().ok_or_else(denied)?);
+        existing = existing.parent().ok_or_else(denied)?;
+    }
+    let mut real = fs::canonicalize(existing).await?;
+    real.extend(rest.iter().rev());
+    for root in roots {
+        if let Ok(root) = fs::canonicalize(root).await
+            && real.starts_with(&root)
+        {
+            return Ok(real);
+        }
+    }
+    Err(denied())
+}
+
+/// Flat key/value object storage for SpecBundles. Keys are `/`-separated relative paths
+/// (`index.json`, `ko_specbundle_<uuid>/revisions/0001/bundle.json`, ...).
+#[async_trait]
+pub trait BundleStore: Send + Sync {
+    /// Human-readable location, for logs.
+    fn describe(&self) -> String;
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    /// Create or replace a single object; readers see either the old or the new value.
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+    /// Create every object under a new `prefix`. Fails if anything already exists there.
+    /// Files are committed in order, so callers put their completion marker last.
+    async fn put_all(&self, prefix: &str, files: &[(String, Vec<u8>)]) -> Result<()>;
+    /// All keys starting with `prefix`, sorted. Leftovers of interrupted writes are not keys.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+    async fn remove_prefix(&self, prefix: &str) -> Result<()>;
+
+    /// Leftovers of interrupted writes under `prefix` (staging dirs, temp files), sorted.
+    /// Backends whose writes are atomic per object have none.
+    async fn interrupted(&self, _prefix: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_string(&self, key: &str) -> Result<Option<String>> {
+        match self.get(key).await? {
+            Some(data) => Ok(Some(String::from_utf8(data).map_err(|_| anyhow!("{key}: not UTF-8"))?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn check_key(key: &str) -> Result<()> {
+    if key.starts_with('/') || key.split('/').any(|s| s == ".." || s == ".") || key.contains('\\') {
+        return Err(anyhow!("invalid storage key '{key}'"));
+    }
+    Ok(())
+}
+
+/// Local directory backend. Single objects are replaced via temp file + rename; `put_all`
+/// stages into a `.staging-<uuid>` sibling, fsyncs, then renames it into place.
+pub struct LocalFsStore {
+    root: PathBuf,
+}
+
+impl LocalFsStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key.trim_end_matches('/')))
+    }
+
+    /// Every file under `prefix`, staging and temp files included, sorted.
+    async fn walk(&self, prefix: &str) -> Result<Vec<String>> {
+        check_key(prefix)?;
+        // Walk from the deepest directory the prefix names, then filter
+        let start = match prefix.rfind('/') {
+            Some(i) => self.root.join(&prefix[..i]),
+            None => self.root.clone(),
+        };
+        let mut keys = Vec::new();
+        let mut stack = vec![start];
+        while let Some(dir) = stack.pop() {
+            let mut rd = match fs::read_dir(&dir).await {
+                Ok(rd) => rd,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(item) = rd.next_entry().await? {
+                let path = item.path();
+                if item.file_type().await?.is_dir() {
+                    stack.push(path);
+                    continue;
+                }
+                let rel = path.strip_prefix(&self.root)?;
+                let key = rel.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/");
+                if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Written by `put` (`*.tmp-<uuid>`) or `put_all` (`.staging-<uuid>/`) and not yet renamed.
+fn is_interrupted(key: &str) -> bool {
+    key.split('/').any(|s| s.starts_with(".staging-") || s.contains(".tmp-"))
+}
+
+#[async_trait]
+impl BundleStore for LocalFsStore {
+    fn describe(&self) -> String {
+        format!("fs:{}", self.root.display())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        replace_file(&self.path(key)?, data).await
+    }
+
+    async fn put_all(&self, prefix: &str, files: &[(String, Vec<u8>)]) -> Result<()> {
+        let dest = self.path(prefix)?;
+        if fs::try_exists(&dest).await? {
+            return Err(anyhow!("'{prefix}' already exists"));
+        }
+        let parent = dest.parent().ok_or_else(|| anyhow!("invalid prefix '{prefix}'"))?;
+        let staging = parent.join(format!(".staging-{}", Uuid::new_v4().simple()));
+        let staged = async {
+            for (rel, data) in files {
+                check_key(rel)?;
+                write_synced(&staging.join(rel), data).await?;
+            }
+            fs::create_dir_all(&staging).await?;
+            sync_dir(&staging).await
+        };
+        if let Err(e) = staged.await {
+            let _ = fs::remove_dir_all(&staging).await;
+            return Err(e);
+        }
+        fs::rename(&staging, &dest).await?;
+        sync_dir(parent).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = self.walk(prefix).await?;
+        keys.retain(|k| !is_interrupted(k));
+        Ok(keys)
+    }
+
+    async fn interrupted(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = self.walk(prefix).await?;
+        keys.retain(|k| is_interrupted(k));
+        Ok(keys)
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<()> {
+        let path = self.path(prefix)?;
+        let res = if fs::metadata(&path).await.map(|m| m.is_dir()).unwrap_or(false) {
+            fs::remove_dir_all(&path).await
+        } else {
+            fs::remove_file(&path).await
+        };
+        match res {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// e.g. `https://s3.us-east-1.amazonaws.com` or `http://127.0.0.1:9000` for MinIO.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Key prefix inside the bucket, e.g. `specbundles/`.
+    pub prefix: String,
+}
+
+/// S3-compatible backend (AWS, MinIO, R2, ...) using path-style requests and SigV4 signing.
+/// Object stores have no rename, so `put_all` relies on write order: the caller's last file
+/// (the manifest) is what marks a revision complete.
+pub struct S3Store {
+    cfg: S3Config,
+    http: reqwest::Client,
+}
+
+static LIST_KEY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<Key>(.*?)</Key>").unwrap());
+static LIST_NEXT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<NextContinuationToken>(.*?)</NextContinuationToken>").unwrap());
+
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl S3Store {
+    pub fn new(cfg: S3Config) -> Self {
+        Self { cfg, http: reqwest::Client::new() }
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: Option<&str>,
+        query: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let path = match key {
+            Some(k) => format!("/{}/{}", self.cfg.bucket, uri_encode(&format!("{}{k}", self.cfg.prefix), false)),
+            None => format!("/{}", self.cfg.bucket),
+        };
+        let mut pairs: Vec<(String, String)> =
+            query.iter().map(|(k, v)| (uri_encode(k, true), uri_encode(v, true))).collect();
+        pairs.sort();
+        let canonical_query = pairs.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join("&");
+
+        let endpoint = self.cfg.endpoint.trim_end_matches('/');
+        let url = if canonical_query.is_empty() {
+            format!("{endpoint}{path}")
+        } else {
+            format!("{endpoint}{path}?{canonical_query}")
+        };
+        let parsed = reqwest::Url::parse(&url)?;
+        let host = match parsed.port() {
+            Some(port) => format!("{}:{port}", parsed.host_str().unwrap_or_default()),
+            None => parsed.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = OffsetDateTime::now_utc();
+        let amz_date = now.format(time::macros::format_description!("[year][month][day]T[hour][minute][second]Z"))?;
+        let date = &amz_date[..8];
+        let payload_hash = sha256_hex(&body);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{method}\n{path}\n{canonical_query}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.cfg.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let mut signing_key = hmac_sha256(format!("AWS4{}", self.cfg.secret_key).as_bytes(), date);
+        for part in [self.cfg.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part);
+        }
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.cfg.access_key
+        );
+
+        Ok(self
+            .http
+            .request(method, parsed)
+            .header("x-amz-date", amz_date.as_str())
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await?)
+    }
+
+    async fn expect_ok(resp: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+        if resp.status().is_success() {
+            return Ok(resp);
+        }
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        Err(anyhow!("s3 {what} failed: {status}: {}", body.chars().take(200).collect::<String>()))
+    }
+}
+
+#[async_trait]
+impl BundleStore for S3Store {
+    fn describe(&self) -> String {
+        format!("s3:{}/{}/{}", self.cfg.endpoint.trim_end_matches('/'), self.cfg.bucket, self.cfg.prefix)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        check_key(key)?;
+        let resp = self.send(reqwest::Method::GET, Some(key), &[], Vec::new()).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let resp = Self::expect_ok(resp, "get").await?;
+        Ok(Some(resp.bytes().await?.to_vec()))
+    }
+
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        check_key(key)?;
+        let resp = self.send(reqwest::Method::PUT, Some(key), &[], data.to_vec()).await?;
+        Self::expect_ok(resp, "put").await?;
+        Ok(())
+    }
+
+    async fn put_all(&self, prefix: &str, files: &[(String, Vec<u8>)]) -> Result<()> {
+        let prefix = format!("{}/", prefix.trim_end_matches('/'));
+        if !self.list(&prefix).await?.is_empty() {
+            return Err(anyhow!("'{prefix}' already exists"));
+        }
+        for (rel, data) in files {
+            self.put(&format!("{prefix}{rel}"), data).await?;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        check_key(prefix)?;
+        let full_prefix = format!("{}{prefix}", self.cfg.prefix);
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2".to_string()), ("prefix", full_prefix.clone())];
+            if let Some(t) = token.take() {
+                query.push(("continuation-token", t));
+            }
+            let resp = self.send(reqwest::Method::GET, None, &query, Vec::new()).await?;
+            let body = Self::expect_ok(resp, "list").await?.text().await?;
+            for cap in LIST_KEY.captures_iter(&body) {
+                let key = xml_unescape(&cap[1]);
+                if let Some(k) = key.strip_prefix(&self.cfg.prefix) {
+                    keys.push(k.to_string());
+                }
+            }
+            match LIST_NEXT.captures(&body) {
+                Some(cap) if body.contains("<IsTruncated>true</IsTruncated>") => token = Some(xml_unescape(&cap[1])),
+                _ => break,
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<()> {
+        for key in self.list(prefix).await? {
+            let resp = self.send(reqwest::Method::DELETE, Some(&key), &[], Vec::new()).await?;
+            Self::expect_ok(resp, "delete").await?;
+        }
+        Ok(())
+    }
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Pick the SpecBundle backend from the environment:
+/// `SPECBUNDLE_STORE=fs` (default; root from `SPECBUNDLE_ROOT`, else `<genesis home>/specbundles`)
+/// or `SPECBUNDLE_STORE=s3` (`SPECBUNDLE_S3_ENDPOINT`, `SPECBUNDLE_S3_BUCKET`,
+/// `SPECBUNDLE_S3_REGION`, `SPECBUNDLE_S3_PREFIX`, `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`).
+/// Errors are `config_invalid:`; the server starts anyway and SpecBundle ops report them.
+pub fn bundle_store_from_env() -> Result<Arc<dyn BundleStore>> {
+    store_from_env().map_err(|e| anyhow!("config_invalid: {e}"))
+}
+
+/// S3 bucket naming rules: 3–63 lowercase letters, digits, dots and hyphens, starting and
+/// ending with a letter or digit.
+fn check_s3_config(cfg: &S3Config) -> Result<()> {
+    if !(cfg.endpoint.starts_with("http://") || cfg.endpoint.starts_with("https://")) {
+        return Err(anyhow!("SPECBUNDLE_S3_ENDPOINT must be an http(s) URL, got '{}'", cfg.endpoint));
+    }
+    let b = &cfg.bucket;
+    let edge_ok = |c: Option<char>| c.is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    let valid = (3..=63).contains(&b.len())
+        && b.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-')
+        && edge_ok(b.chars().next())
+        && edge_ok(b.chars().last())
+        && !b.contains("..");
+    if !valid {
+        return Err(anyhow!("SPECBUNDLE_S3_BUCKET '{b}' is not a valid bucket name"));
+    }
+    Ok(())
+}
+
+fn store_from_env() -> Result<Arc<dyn BundleStore>> {
+    match env("SPECBUNDLE_STORE").as_deref().unwrap_or("fs") {
+        "fs" => {
+            let root = match env("SPECBUNDLE_ROOT") {
+                Some(r) => PathBuf::from(r),
+                None => genesis_home()?.join("specbundles"),
+            };
+            Ok(Arc::new(LocalFsStore::new(root)))
+        }
+        "s3" => {
+            let need = |n: &str| env(n).ok_or_else(|| anyhow!("SPECBUNDLE_STORE=s3 requires {n}"));
+            let mut prefix = env("SPECBUNDLE_S3_PREFIX").unwrap_or_default();
+            if !prefix.is_empty() && !prefix.ends_with('/') {
+                prefix.push('/');
+            }
+            let cfg = S3Config {
+                endpoint: need("SPECBUNDLE_S3_ENDPOINT")?,
+                bucket: need("SPECBUNDLE_S3_BUCKET")?,
+                region: env("SPECBUNDLE_S3_REGION").unwrap_or_else(|| "us-east-1".into()),
+                access_key: need("AWS_ACCESS_KEY_ID")?,
+                secret_key: need("AWS_SECRET_ACCESS_KEY")?,
+                prefix,
+            };
+            check_s3_config(&cfg)?;
+            Ok(Arc::new(S3Store::new(cfg)))
+        }
+        other => Err(anyhow!("unknown SPECBUNDLE_STORE '{other}' (expected fs or s3)")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, extract::State, http::{HeaderMap, Method, StatusCode, Uri}, Router};
+    use parking_lot::Mutex;
+    use std::collections::BTreeMap;
+
+    type Objects = Arc<Mutex<BTreeMap<String, Vec<u8>>>>;
+
+    fn percent_decode(v: &str) -> String {
+        let b = v.as_bytes();
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < b.len() {
+            if b[i] == b'%' {
+                out.push(u8::from_str_radix(&v[i + 1..i + 3], 16).unwrap());
+                i += 3;
+            } else {
+                out.push(b[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    fn query_param(uri: &Uri, name: &str) -> Option<String> {
+        uri.query()?.split('&').find_map(|kv| {
+            let (k, v) = kv.split_once('=')?;
+            (k == name).then(|| percent_decode(v))
+        })
+    }
+
+    /// Minimal MinIO-style stand-in: path-style GET/PUT/DELETE plus ListObjectsV2 with 2-key pages.
+    async fn s3_standin(State(objects): State<Objects>, method: Method, uri: Uri, headers: HeaderMap, body: Bytes) -> (StatusCode, Vec<u8>) {
+        let auth = headers.get("authorization").and_then(|v| v.to_str().ok()).unwrap_or("");
+        if !auth.starts_with("AWS4-HMAC-SHA256 Credential=test-key/") || !headers.contains_key("x-amz-date") {
+            return (StatusCode::FORBIDDEN, b"unsigned".to_vec());
+        }
+        let path = uri.path().strip_prefix("/bucket").unwrap_or("");
+        let key = percent_decode(path.trim_start_matches('/'));
+        let mut objects = objects.lock();
+        match (method, key.is_empty()) {
+            (Method::GET, true) => {
+                let prefix = query_param(&uri, "prefix").unwrap_or_default();
+                let start: usize = query_param(&uri, "continuation-token").map(|t| t.parse().unwrap()).unwrap_or(0);
+                let matching: Vec<&String> = objects.keys().filter(|k| k.starts_with(&prefix)).collect();
+                let page: String = matching.iter().skip(start).take(2).map(|k| format!("<Contents><Key>{k}</Key></Contents>")).collect();
+                let truncated = start + 2 < matching.len();
+                let next = if truncated { format!("<NextContinuationToken>{}</NextContinuationToken>", start + 2) } else { String::new() };
+                let xml = format!("<ListBucketResult><IsTruncated>{truncated}</IsTruncated>{page}{next}</ListBucketResult>");
+                (StatusCode::OK, xml.into_bytes())
+            }
+            (Method::GET, false) => match objects.get(&key) {
+                Some(v) => (StatusCode::OK, v.clone()),
+                None => (StatusCode::NOT_FOUND, b"NoSuchKey".to_vec()),
+            },
+            (Method::PUT, false) => {
+                objects.insert(key, body.to_vec());
+                (StatusCode::OK, Vec::new())
+            }
+            (Method::DELETE, false) => {
+                objects.remove(&key);
+                (StatusCode::NO_CONTENT, Vec::new())
+            }
+            _ => (StatusCode::BAD_REQUEST, Vec::new()),
+        }
+    }
+
+    async fn exercise(store: &dyn BundleStore) {
+        assert!(store.get("index.json").await.unwrap().is_none());
+        store.put("index.json", b"[]").await.unwrap();
+        store.put("index.json", b"[1]").await.unwrap();
+        assert_eq!(store.get_string("index.json").await.unwrap().as_deref(), Some("[1]"));
+
+        let files = vec![
+            ("bundle.json".to_string(), b"{}".to_vec()),
+            ("parts/0001.md".to_string(), b"# A".to_vec()),
+            ("manifest.json".to_string(), b"{}".to_vec()),
+        ];
+        store.put_all("ko_x/revisions/0001", &files).await.unwrap();
+        assert!(store.put_all("ko_x/revisions/0001", &files).await.is_err());
+        assert_eq!(
+            store.list("ko_x/").await.unwrap(),
+            vec![
+                "ko_x/revisions/0001/bundle.json",
+                "ko_x/revisions/0001/manifest.json",
+                "ko_x/revisions/0001/parts/0001.md",
+            ]
+        );
+        assert_eq!(store.list("").await.unwrap().len(), 4);
+        assert!(store.get("../etc/passwd").await.is_err());
+
+        store.remove_prefix("ko_x").await.unwrap();
+        assert!(store.list("ko_x/").await.unwrap().is_empty());
+        assert_eq!(store.list("").await.unwrap(), vec!["index.json"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_fs_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = LocalFsStore::new(tmp.path());
+        exercise(&store).await;
+
+        // Leftovers of interrupted writes are reported separately, never listed
+        std::fs::create_dir_all(tmp.path().join("ko_y/revisions/.staging-ab")).unwrap();
+        std::fs::write(tmp.path().join("ko_y/revisions/.staging-ab/bundle.json"), "{").unwrap();
+        std::fs::write(tmp.path().join("ko_y/bundle.tmp-cd"), "{").unwrap();
+        assert!(store.list("ko_y/").await.unwrap().is_empty());
+        assert_eq!(store.interrupted("ko_y/").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_confine() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("exports");
+        std::fs::create_dir_all(&root).unwrap();
+        let roots = [root.clone()];
+        let inside = confine(&roots, "a/b.tar.zst").await.unwrap();
+        assert!(inside.starts_with(root.canonicalize().unwrap()));
+        assert!(confine(&roots, &inside.display().to_string()).await.is_ok());
+        assert!(confine(&roots, "../escape").await.is_err());
+        assert!(confine(&roots, "/etc/passwd").await.is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/etc", root.join("link")).unwrap();
+            assert!(confine(&roots, "link/passwd").await.is_err());
+        }
+    }
+
+    #[test]
+    fn test_s3_config_is_checked() {
+        let cfg = |endpoint: &str, bucket: &str| S3Config {
+            endpoint: endpoint.into(),
+            bucket: bucket.into(),
+            region: "us-east-1".into(),
+            access_key: "k".into(),
+            secret_key: "s".into(),
+            prefix: String::new(),
+        };
+        assert!(check_s3_config(&cfg("http://127.0.0.1:9000", "spec-bundles")).is_ok());
+        assert!(check_s3_config(&cfg("http://127.0.0.1:9000", "Spec_Bundles")).is_err());
+        assert!(check_s3_config(&cfg("127.0.0.1:9000", "bundles")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_s3_store_against_standin() {
+        let objects: Objects = Default::default();
+        let app = Router::new().fallback(s3_standin).with_state(objects.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let store = S3Store::new(S3Config {
+            endpoint: format!("http://{addr}"),
+            bucket: "bucket".into(),
+            region: "us-east-1".into(),
+            access_key: "test-key".into(),
+            secret_key: "test-secret".into(),
+            prefix: "specbundles/".into(),
+        });
+        exercise(&store).await;
+        assert!(objects.lock().keys().all(|k| k.starts_with("specbundles/")));
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("a b/c~d", false), "a%20b/c~d");
+        assert_eq!(uri_encode("a/b", true), "a%2Fb");
+    }
+}
@@ -0,0 +1,390 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use uuid::Uuid;
+
+use crate::events::PolicyDecision;
+use crate::models::{GateStatus, Predecessor, TaskStatus};
+use crate::services::ledger::{self, task_ko, Ledger};
+use crate::services::scheduler;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Task,
+    Gate,
+}
+
+/// A task or gate as it appears in a view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: Uuid,
+    pub kind: NodeKind,
+    pub key: Option<String>,
+    pub title: Option<String>,
+    /// Task or gate status, snake_case.
+    pub status: String,
+    /// Held for policy review: a held task does not count as done until the hold is released.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub held: bool,
+}
+
+fn status_name(status: impl Serialize) -> String {
+    serde_json::to_value(status).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+fn node(l: &Ledger, id: Uuid) -> Option<GraphNode> {
+    if let Some(t) = l.task(id) {
+        let held = l.held(id);
+        return Some(GraphNode { id, kind: NodeKind::Task, key: t.key.clone(), title: t.title.clone(), status: status_name(t.status), held });
+    }
+    let g = l.gate(id)?;
+    Some(GraphNode { id, kind: NodeKind::Gate, key: g.key.clone(), title: g.title.clone(), status: status_name(g.status), held: false })
+}
+
+/// What a task or gate waits on that has not happened yet: a task's unmet predecessor
+/// entries, a gate's trigger tasks that are not done. Each entry is one condition; an
+/// either/or entry has several members, any one of which would do.
+fn unmet_entries(l: &Ledger, id: Uuid) -> Vec<Vec<Uuid>> {
+    if let Some(t) = l.task(id) {
+        return scheduler::unmet(l, t).into_iter().map(|p| p.ids().to_vec()).collect();
+    }
+    let Some(g) = l.gate(id) else { return Vec::new() };
+    if g.status != GateStatus::Waiting {
+        return Vec::new();
+    }
+    g.trigger.iter().filter(|t| l.task(**t).is_none_or(|t| t.status != TaskStatus::Done)).map(|t| vec![*t]).collect()
+}
+
+/// Whether an unmet item is itself held up by something else (rather than needing work).
+fn is_waiting(l: &Ledger, id: Uuid) -> bool {
+    match (l.task(id), l.gate(id)) {
+        (Some(t), _) => t.status == TaskStatus::Pending,
+        (None, Some(g)) => g.status == GateStatus::Waiting,
+        (None, None) => false,
+    }
+}
+
+/// The items holding `id` up that someone can act on now: ready, running, failed or skipped
+/// tasks, tasks held for policy review and gates under validation or review, found by
+/// following waiting items back. A held task that also waits on others is both.
+fn actionable(l: &Ledger, id: Uuid) -> Vec<Uuid> {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<Uuid> = unmet_entries(l, id).into_iter().flatten().collect();
+    let mut out = Vec::new();
+    while let Some(next) = stack.pop() {
+        if !seen.insert(next) {
+            continue;
+        }
+        let waiting = is_waiting(l, next);
+        if waiting {
+            stack.extend(unmet_entries(l, next).into_iter().flatten());
+        }
+        if !waiting || l.held(next) {
+            out.push(next);
+        }
+    }
+    out.sort_by_key(|id| scheduler::label(l, *id));
+    out
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedTask {
+    pub task: GraphNode,
+    /// Unmet predecessor entries; an entry with several members is an either/or.
+    pub waiting_on: Vec<Vec<GraphNode>>,
+    /// What to work on or review to unblock it, following waiting items back.
+    pub actionable: Vec<GraphNode>,
+    /// The policy decision the task itself is held on, until a reviewer releases it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold: Option<PolicyDecision>,
+}
+
+fn blocked(l: &Ledger, queue_id: Option<Uuid>) -> Vec<BlockedTask> {
+    l.tasks
+        .iter()
+        .filter(|t| t.status == TaskStatus::Pending && queue_id.is_none_or(|q| t.queue_id == q))
+        .filter_map(|t| {
+            let waiting_on: Vec<Vec<GraphNode>> =
+                unmet_entries(l, t.id).into_iter().map(|e| e.into_iter().filter_map(|id| node(l, id)).collect()).collect();
+            let ko_id = task_ko(t.id);
+            Some(BlockedTask {
+                task: node(l, t.id)?,
+                waiting_on,
+                actionable: actionable(l, t.id).into_iter().filter_map(|id| node(l, id)).collect(),
+                hold: l.pending_decisions.iter().find(|d| d.task_id == ko_id).cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Longest dependency chain ending at `target`, counted in tasks (TES-2025 plans carry no
+/// time estimates). An either/or entry contributes its shortest member, since finishing
+/// any one of them is enough. Returns the chain from its start to `target`, or a
+/// `dependency_cycle` error naming the loop if the dependencies go round in one.
+fn critical_path(l: &Ledger, target: Uuid) -> Result<Vec<Uuid>> {
+    fn entries(l: &Ledger, id: Uuid) -> Vec<Vec<Uuid>> {
+        match (l.task(id), l.gate(id)) {
+            (Some(t), _) => t.predecessors.iter().map(|p| p.ids().to_vec()).collect(),
+            (None, Some(g)) => g.trigger.iter().map(|t| vec![*t]).collect(),
+            (None, None) => Vec::new(),
+        }
+    }
+    // id → (tasks on the longest chain ending here, the dependency it continues from);
+    // `trail` is the chain being explored, to name a cycle when one closes
+    fn longest(l: &Ledger, id: Uuid, memo: &mut HashMap<Uuid, (usize, Option<Uuid>)>, trail: &mut Vec<Uuid>) -> Result<usize> {
+        if let Some((n, _)) = memo.get(&id) {
+            return Ok(*n);
+        }
+        if let Some(pos) = trail.iter().position(|t| *t == id) {
+            let cycle: Vec<String> = trail[pos..].iter().chain([&id]).rev().map(|t| scheduler::label(l, *t)).collect();
+            return Err(anyhow!("dependency_cycle: {}", cycle.join(" -> ")));
+        }
+        trail.push(id);
+        let mut best: (usize, Option<Uuid>) = (0, None);
+        for entry in entries(l, id) {
+            let mut pick: Option<(usize, Uuid)> = None;
+            for m in entry {
+                let n = longest(l, m, memo, trail)?;
+                if pick.is_none_or(|(p, _)| n < p) {
+                    pick = Some((n, m));
+                }
+            }
+            if let Some((n, m)) = pick
+                && (best.1.is_none() || n > best.0)
+            {
+                best = (n, Some(m));
+            }
+        }
+        trail.pop();
+        let own = usize::from(l.task(id).is_some());
+        memo.insert(id, (best.0 + own, best.1));
+        Ok(best.0 + own)
+    }
+    let mut memo = HashMap::new();
+    longest(l, target, &mut memo, &mut Vec::new())?;
+    let mut path = vec![target];
+    while let Some(&(_, Some(prev))) = memo.get(path.last().unwrap_or(&target)) {
+        path.push(prev);
+    }
+    path.reverse();
+    Ok(path)
+}
+
+fn mermaid_label(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "#quot;"))
+}
+
+/// Fill/stroke per status, shared by tasks and gates where the meaning matches.
+const STATUS_STYLES: [(&str, &str); 12] = [
+    ("pending", "fill:#eeeeee,stroke:#9e9e9e,color:#424242"),
+    ("ready", "fill:#bbdefb,stroke:#1976d2,color:#0d47a1"),
+    ("running", "fill:#fff59d,stroke:#f9a825,color:#5d4037"),
+    ("done", "fill:#c8e6c9,stroke:#388e3c,color:#1b5e20"),
+    ("failed", "fill:#ffcdd2,stroke:#d32f2f,color:#b71c1c"),
+    ("skipped", "fill:#f5f5f5,stroke:#bdbdbd,color:#9e9e9e,stroke-dasharray:4 2"),
+    ("waiting", "fill:#eeeeee,stroke:#9e9e9e,color:#424242"),
+    ("validating", "fill:#fff59d,stroke:#f9a825,color:#5d4037"),
+    ("awaiting_approval", "fill:#ffe0b2,stroke:#ef6c00,color:#e65100"),
+    ("approved", "fill:#c8e6c9,stroke:#388e3c,color:#1b5e20"),
+    ("validation_failed", "fill:#ffcdd2,stroke:#d32f2f,color:#b71c1c"),
+    ("rejected", "fill:#ffcdd2,stroke:#d32f2f,color:#b71c1c"),
+];
+
+/// Mermaid flowchart of a project's tasks (boxes) and gates (hexagons), predecessors
+/// pointing at what waits on them; either/or members are dotted. Nodes are colored by
+/// status.
+fn mermaid(l: &Ledger) -> String {
+    let mut ids: HashMap<Uuid, String> = HashMap::new();
+    let mut classes: Vec<(String, String)> = Vec::new();
+    let mut out = String::from("graph LR\n");
+    let label = |key: &Option<String>, title: &Option<String>, id: Uuid| match (key, title) {
+        (Some(k), Some(t)) => format!("{k}: {t}"),
+        (Some(k), None) => k.clone(),
+        (None, Some(t)) => t.clone(),
+        (None, None) => id.to_string(),
+    };
+    for (i, t) in l.tasks.iter().enumerate() {
+        let n = format!("t{i}");
+        let _ = writeln!(out, "  {n}[{}]", mermaid_label(&label(&t.key, &t.title, t.id)));
+        classes.push((status_name(t.status), n.clone()));
+        ids.insert(t.id, n);
+    }
+    for (i, g) in l.gates.iter().enumerate() {
+        let n = format!("g{i}");
+        let _ = writeln!(out, "  {n}{{{{{}}}}}", mermaid_label(&label(&g.key, &g.title, g.id)));
+        classes.push((status_name(g.status), n.clone()));
+        ids.insert(g.id, n);
+    }
+    for t in &l.tasks {
+        for p in &t.predecessors {
+            let arrow = match p {
+                Predecessor::Task(_) => "-->",
+                Predecessor::Either { .. } => "-.->|either|",
+            };
+            for id in p.ids() {
+                if let Some(from) = ids.get(id) {
+                    let _ = writeln!(out, "  {from} {arrow} {}", ids[&t.id]);
+                }
+            }
+        }
+    }
+    for g in &l.gates {
+        for id in &g.trigger {
+            if let Some(from) = ids.get(id) {
+                let _ = writeln!(out, "  {from} --> {}", ids[&g.id]);
+            }
+        }
+    }
+    for (status, style) in STATUS_STYLES {
+        let _ = writeln!(out, "  classDef {status} {style}");
+    }
+    for (status, n) in &classes {
+        let _ = writeln!(out, "  class {n} {status}");
+    }
+    out
+}
+
+/// A task or gate by id or plan key.
+fn resolve(l: &Ledger, target: &str) -> Result<Uuid> {
+    let by_id = Uuid::parse_str(target).ok().filter(|id| l.task(*id).is_some() || l.gate(*id).is_some());
+    let by_key = || {
+        let task = l.tasks.iter().find(|t| t.key.as_deref() == Some(target)).map(|t| t.id);
+        task.or_else(|| l.gates.iter().find(|g| g.key.as_deref() == Some(target)).map(|g| g.id))
+    };
+    by_id.or_else(by_key).ok_or_else(|| anyhow!("task_not_found: no task or gate '{target}' in project {}", l.project.id))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskBlockedReq {
+    pub project_id: Uuid,
+    pub queue_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CriticalPathReq {
+    pub project_id: Uuid,
+    /// Gate (or task) id or plan key, e.g. `GATE-A-READY`.
+    pub target: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CriticalPathResp {
+    /// From the first task of the chain to the target.
+    pub path: Vec<GraphNode>,
+    /// Tasks on the path.
+    pub tasks: usize,
+    /// Tasks on the path that are not done yet.
+    pub remaining: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGraphReq {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaskGraphResp {
+    pub mermaid: String,
+}
+
+pub struct TaskGraphService;
+
+impl TaskGraphService {
+    async fn ledger(project_id: Uuid) -> Result<Ledger> {
+        ledger::store().await?.get(project_id).await.ok_or_else(|| anyhow!("project_not_found: {project_id}"))
+    }
+
+    /// Handler for op="task.blocked": pending tasks, what they wait on and what would
+    /// unblock them.
+    pub async fn blocked(req: TaskBlockedReq) -> Result<Vec<BlockedTask>> {
+        Ok(blocked(&Self::ledger(req.project_id).await?, req.queue_id))
+    }
+
+    /// Handler for op="task.critical_path"
+    pub async fn critical_path(req: CriticalPathReq) -> Result<CriticalPathResp> {
+        let l = Self::ledger(req.project_id).await?;
+        let target = resolve(&l, req.target.trim())?;
+        let path: Vec<GraphNode> = critical_path(&l, target)?.into_iter().filter_map(|id| node(&l, id)).collect();
+        let tasks: Vec<_> = path.iter().filter(|n| n.kind == NodeKind::Task).collect();
+        let remaining = tasks.iter().filter(|n| n.status != status_name(TaskStatus::Done)).count();
+        Ok(CriticalPathResp { tasks: tasks.len(), remaining, path })
+    }
+
+    /// Handler for op="task.graph": Mermaid source with status coloring.
+    pub async fn graph(req: TaskGraphReq) -> Result<TaskGraphResp> {
+        Ok(TaskGraphResp { mermaid: mermaid(&Self::ledger(req.project_id).await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing;
+
+    fn sample() -> Ledger {
+        let mut l = testing::ledger();
+        let mut task = |key: &str, status, predecessors| {
+            let t = testing::task(Some(key), status, predecessors);
+            let id = t.id;
+            l.tasks.push(t);
+            id
+        };
+        // A1 → A2 → A3, B1 → B3 and either(A3, B1) → B3: longest chain to the gate via A3
+        let a1 = task("TASK-A1", TaskStatus::Done, vec![]);
+        let a2 = task("TASK-A2", TaskStatus::Running, vec![Predecessor::Task(a1)]);
+        let a3 = task("TASK-A3", TaskStatus::Pending, vec![Predecessor::Task(a2)]);
+        let b1 = task("TASK-B1", TaskStatus::Ready, vec![]);
+        let b2 = task("TASK-B2", TaskStatus::Pending, vec![Predecessor::Either { either: vec![a3, b1] }]);
+        let b3 = task("TASK-B3", TaskStatus::Pending, vec![Predecessor::Task(a3), Predecessor::Task(b2)]);
+        l.gates.push(testing::gate(l.project.id, Some("GATE-X"), vec![b3]));
+        l
+    }
+
+    fn keys(l: &Ledger, ids: &[Uuid]) -> Vec<String> {
+        ids.iter().map(|id| scheduler::label(l, *id)).collect()
+    }
+
+    #[test]
+    fn test_blocked_and_critical_path() {
+        let l = sample();
+        let blocked = blocked(&l, None);
+        let b3 = blocked.iter().find(|b| b.task.key.as_deref() == Some("TASK-B3")).unwrap();
+        let waiting: Vec<Vec<_>> = b3.waiting_on.iter().map(|e| e.iter().map(|n| n.key.clone().unwrap()).collect()).collect();
+        assert_eq!(waiting, [vec!["TASK-A3"], vec!["TASK-B2"]]);
+        let actionable: Vec<_> = b3.actionable.iter().map(|n| n.key.clone().unwrap()).collect();
+        assert_eq!(actionable, ["TASK-A2", "TASK-B1"]);
+
+        let gate = resolve(&l, "GATE-X").unwrap();
+        // Through A3 (3 tasks deep) rather than B2, whose either/or counts only B1
+        assert_eq!(keys(&l, &critical_path(&l, gate).unwrap()), ["TASK-A1", "TASK-A2", "TASK-A3", "TASK-B3", "GATE-X"]);
+        assert!(resolve(&l, "GATE-Y").unwrap_err().to_string().starts_with("task_not_found:"));
+
+        let mmd = mermaid(&l);
+        assert!(mmd.contains("  t0 --> t1\n"));
+        assert!(mmd.contains("  t3 -.->|either| t4\n"));
+        assert!(mmd.contains("  g0{{\"GATE-X\"}}\n") && mmd.contains("  t5 --> g0\n"));
+        assert!(mmd.contains("  class t0 done\n") && mmd.contains("  class g0 waiting\n"));
+    }
+
+    #[test]
+    fn test_holds_and_cycles_are_reported() {
+        let mut l = sample();
+        let id = |l: &Ledger, key: &str| resolve(l, key).unwrap();
+        let b2 = id(&l, "TASK-B2");
+        l.pending_decisions.push(PolicyDecision { task_id: task_ko(b2), decisions: vec!["review".into()], sse_verdict_ko: None });
+        let blocked = blocked(&l, None);
+        let b2_blocked = blocked.iter().find(|b| b.task.id == b2).unwrap();
+        assert!(b2_blocked.task.held && b2_blocked.hold.is_some());
+        let b3 = blocked.iter().find(|b| b.task.key.as_deref() == Some("TASK-B3")).unwrap();
+        let actionable: Vec<_> = b3.actionable.iter().map(|n| n.key.clone().unwrap()).collect();
+        assert_eq!(actionable, ["TASK-A2", "TASK-B1", "TASK-B2"]);
+
+        // A1 → A2 → A3 → A1
+        let a3 = id(&l, "TASK-A3");
+        l.tasks[0].predecessors = vec![Predecessor::Task(a3)];
+        let err = critical_path(&l, id(&l, "GATE-X")).unwrap_err().to_string();
+        assert_eq!(err, "dependency_cycle: TASK-A3 -> TASK-A1 -> TASK-A2 -> TASK-A3");
+    }
+}
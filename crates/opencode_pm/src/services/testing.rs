@@ -0,0 +1,52 @@
+//! Fixtures shared by the service tests.
+
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::models::{Gate, GateStatus, Predecessor, Project, Task, TaskStatus};
+use crate::services::ledger::Ledger;
+
+/// An empty ledger for project "p".
+pub fn ledger() -> Ledger {
+    Ledger::new(Project { id: Uuid::new_v4(), name: "p".into(), created_at: OffsetDateTime::now_utc() })
+}
+
+/// An `Implementation` task outside any queue; set other fields with struct update syntax.
+pub fn task(key: Option<&str>, status: TaskStatus, predecessors: Vec<Predecessor>) -> Task {
+    Task {
+        id: Uuid::new_v4(),
+        queue_id: Uuid::nil(),
+        kind: "Implementation".into(),
+        ko_refs: vec![],
+        created_at: OffsetDateTime::now_utc(),
+        key: key.map(str::to_string),
+        title: None,
+        predecessors,
+        status,
+        deliverables: vec![],
+        validation: None,
+        priority: 0,
+        ready_since: None,
+        lease: None,
+    }
+}
+
+/// A waiting gate on `trigger` with no checks.
+pub fn gate(project_id: Uuid, key: Option<&str>, trigger: Vec<Uuid>) -> Gate {
+    Gate {
+        id: Uuid::new_v4(),
+        project_id,
+        key: key.map(str::to_string),
+        title: None,
+        trigger,
+        automated: vec![],
+        human: vec![],
+        actions: vec![],
+        post_gate: vec![],
+        status: GateStatus::Waiting,
+        validations: vec![],
+        report_ko: None,
+        decision: None,
+        created_at: OffsetDateTime::now_utc(),
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Write `data` to `path`, creating its directory, and fsync the file.
+pub async fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut f = fs::File::create(path).await?;
+    f.write_all(data).await?;
+    f.sync_all().await?;
+    Ok(())
+}
+
+/// Make a rename or new entry in `dir` durable.
+pub async fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir).await?.sync_all().await?;
+    Ok(())
+}
+
+/// Write through a temporary `<name>.tmp-<uuid>` sibling and rename it into place, so a
+/// crash leaves the old or the new content.
+pub async fn replace_file(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()));
+    write_synced(&tmp, data).await?;
+    fs::rename(&tmp, path).await?;
+    if let Some(parent) = path.parent() {
+        sync_dir(parent).await?;
+    }
+    Ok(())
+}
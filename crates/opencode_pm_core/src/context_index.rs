@@ -0,0 +1,735 @@
+use crate::embedding::{cosine, EmbedError, Embedder};
+use crate::knowledge::KnowledgeStore;
+use crate::originality::CorpusText;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reference to a knowledge object, optionally ranked against a query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KoRef {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Workspace-relative path for file-backed KOs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+impl KoRef {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), title: None, path: None, score: None, snippet: None }
+    }
+}
+
+/// Workspace locations indexed as file KOs, relative to the workspace root.
+const FILE_SOURCES: &[(&str, &[&str])] = &[
+    ("docs", &["md"]),
+    ("contracts/schemas", &["json"]),
+    ("schemas", &["json"]),
+    ("policies", &["md", "yml", "yaml", "toml"]),
+];
+/// Files larger than this are indexed by name only.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const SNIPPET_CHARS: usize = 160;
+/// `refresh_if_stale` rescans at most this often.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Leading characters of an entry (after its title) that get embedded.
+const EMBED_CHARS: usize = 2000;
+const EMBED_BATCH: usize = 64;
+/// Share of the hybrid score taken by vector similarity; the rest is max-normalized BM25.
+const VECTOR_WEIGHT: f32 = 0.5;
+
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "that", "this", "from", "are", "was", "not", "but", "all", "can", "has", "have",
+    "into", "use", "its", "our", "will", "json", "md", "rs",
+];
+
+/// Lowercased alphanumeric terms; camelCase and snake_case words are split.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut cur = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if c.is_uppercase() && prev_lower && !cur.is_empty() {
+                out.push(std::mem::take(&mut cur));
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            cur.extend(c.to_lowercase());
+        }
+        out.push(cur);
+    }
+    out.retain(|t| t.len() > 1 && !STOPWORDS.contains(&t.as_str()));
+    out
+}
+
+/// A document a `ContextSource` contributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceDoc {
+    pub ko_id: String,
+    pub title: String,
+    pub text: String,
+}
+
+/// Documents that live outside the workspace tree, e.g. sealed task runlogs or SpecBundle heads.
+pub trait ContextSource: Send + Sync {
+    /// Prefix of this source's entry keys, e.g. `runlog`.
+    fn name(&self) -> &str;
+    /// (key, change stamp) of every document; called on each refresh, so keep it cheap.
+    fn list(&self) -> Vec<(String, String)>;
+    /// Called only for keys whose stamp changed.
+    fn load(&self, key: &str) -> Option<SourceDoc>;
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    ko_id: String,
+    title: String,
+    path: Option<String>,
+    text: String,
+    terms: HashMap<String, u32>,
+    len: u32,
+    /// Filled in by `embed_pending`; entries are rebuilt (and lose it) when their source changes.
+    vector: Option<Vec<f32>>,
+    /// The embedder refused this text; ranked lexically only until the text changes.
+    embed_refused: bool,
+}
+
+impl Entry {
+    fn new(ko_id: String, title: String, path: Option<String>, text: String) -> Self {
+        let mut terms = HashMap::new();
+        let tokens: Vec<String> = tokenize(&format!("{title}\n{}\n{text}", path.as_deref().unwrap_or("")));
+        for t in &tokens {
+            *terms.entry(t.clone()).or_insert(0) += 1;
+        }
+        Self { ko_id, title, path, text, terms, len: tokens.len() as u32, vector: None, embed_refused: false }
+    }
+
+    fn embed_text(&self) -> String {
+        format!("{}\n{}", self.title, self.text.chars().take(EMBED_CHARS).collect::<String>())
+    }
+
+    /// First line that contains the most query terms, trimmed to `SNIPPET_CHARS`.
+    fn snippet(&self, query: &HashSet<String>) -> Option<String> {
+        let (_, line) = self
+            .text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .enumerate()
+            // Most hits first, earliest line on ties
+            .max_by_key(|(i, l)| (tokenize(l).iter().filter(|t| query.contains(*t)).count(), std::cmp::Reverse(*i)))?;
+        let mut s: String = line.chars().take(SNIPPET_CHARS).collect();
+        if line.chars().count() > SNIPPET_CHARS {
+            s.push('…');
+        }
+        Some(s)
+    }
+}
+
+/// Outcome of one `embed_pending` pass.
+#[derive(Debug, Default)]
+pub struct EmbedReport {
+    pub embedded: usize,
+    /// Entries the embedder refused; they are not retried until their text changes.
+    pub refused: usize,
+    /// Last whole-batch failure; those entries are retried on the next pass.
+    pub error: Option<EmbedError>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Entry key (`file:<path>`, `ko:<chunk id>` or `<source>:<key>`) → entry
+    entries: BTreeMap<String, Entry>,
+    /// Change stamps used for incremental refresh: file (mtime, size), KO indexed_at.
+    stamps: HashMap<String, String>,
+    last_refresh: Option<Instant>,
+    /// Bumped by every refresh that changes an entry.
+    generation: u64,
+}
+
+/// What `context.fetch` ranks against.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ContextQuery {
+    /// File the caller is working on, workspace-relative or absolute.
+    pub path: Option<String>,
+    /// Free-text task description or search terms.
+    pub task: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// BM25 index over workspace files, ingested KOs and any attached sources, refreshed
+/// incrementally. With an embedder attached, `hybrid_search` blends in vector similarity.
+pub struct ContextIndex {
+    root: PathBuf,
+    knowledge: Option<Arc<KnowledgeStore>>,
+    sources: Vec<Arc<dyn ContextSource>>,
+    embedder: Option<Arc<dyn Embedder>>,
+    state: RwLock<State>,
+}
+
+fn stamp(meta: &std::fs::Metadata) -> String {
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{mtime}:{}", meta.len())
+}
+
+fn walk(dir: &Path, exts: &[&str], out: &mut Vec<PathBuf>) {
+    let Ok(rd) = std::fs::read_dir(dir) else { return };
+    for item in rd.flatten() {
+        let path = item.path();
+        let name = item.file_name();
+        if name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, exts, out);
+        } else if path.extension().and_then(|e| e.to_str()).is_some_and(|e| exts.contains(&e)) {
+            out.push(path);
+        }
+    }
+}
+
+/// `docs/adr/0001-x.md` → `ko:docs/adr/0001-x`; other files keep their extension.
+fn file_ko_id(rel: &str) -> String {
+    format!("ko:{}", rel.strip_suffix(".md").unwrap_or(rel))
+}
+
+fn file_title(rel: &str, text: &str) -> String {
+    text.lines()
+        .find_map(|l| l.strip_prefix("# "))
+        .map(|t| t.trim().to_string())
+        .unwrap_or_else(|| rel.rsplit('/').next().unwrap_or(rel).to_string())
+}
+
+impl ContextIndex {
+    pub fn new(root: impl Into<PathBuf>, knowledge: Option<Arc<KnowledgeStore>>) -> Self {
+        Self { root: root.into(), knowledge, sources: Vec::new(), embedder: None, state: RwLock::new(State::default()) }
+    }
+
+    pub fn with_source(mut self, source: Arc<dyn ContextSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
+    pub fn embedder_id(&self) -> Option<String> {
+        self.embedder.as_ref().map(|e| e.id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.read().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn refresh_if_stale(&self) -> usize {
+        let due = self.state.read().last_refresh.is_none_or(|t| t.elapsed() >= REFRESH_INTERVAL);
+        if due { self.refresh() } else { 0 }
+    }
+
+    /// `refresh_if_stale` on the blocking pool, for async callers.
+    pub async fn refresh_if_stale_async(self: &Arc<Self>) -> usize {
+        let index = self.clone();
+        tokio::task::spawn_blocking(move || index.refresh_if_stale()).await.unwrap_or(0)
+    }
+
+    /// Re-read files and KOs whose stamp changed and drop the ones that disappeared.
+    /// Returns the number of entries added, updated or removed.
+    pub fn refresh(&self) -> usize {
+        let mut seen: HashMap<String, String> = HashMap::new();
+        let mut fresh: Vec<(String, Vec<(String, Entry)>)> = Vec::new();
+        let known = self.state.read().stamps.clone();
+
+        for (dir, exts) in FILE_SOURCES {
+            let mut files = Vec::new();
+            walk(&self.root.join(dir), exts, &mut files);
+            for path in files {
+                let Ok(meta) = path.metadata() else { continue };
+                let Ok(rel) = path.strip_prefix(&self.root) else { continue };
+                let rel = rel.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/");
+                let key = format!("file:{rel}");
+                let st = stamp(&meta);
+                if known.get(&key) != Some(&st) {
+                    let text = if meta.len() <= MAX_FILE_BYTES {
+                        std::fs::read_to_string(&path).unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    let entry = Entry::new(file_ko_id(&rel), file_title(&rel, &text), Some(rel.clone()), text);
+                    fresh.push((key.clone(), vec![(key.clone(), entry)]));
+                }
+                seen.insert(key, st);
+            }
+        }
+
+        if let Some(knowledge) = &self.knowledge {
+            for doc in knowledge.docs() {
+                let key = format!("ko:{}", doc.ko_id);
+                let st = format!("{}:{}", doc.revision, doc.indexed_at);
+                if known.get(&key) != Some(&st) {
+                    let entries = knowledge
+                        .chunks(&doc.ko_id)
+                        .into_iter()
+                        .map(|c| {
+                            let title = match c.heading_path.last() {
+                                Some(h) => format!("{} › {h}", doc.title),
+                                None => doc.title.clone(),
+                            };
+                            (format!("ko:{}", c.id), Entry::new(doc.ko_id.clone(), title, None, c.text))
+                        })
+                        .collect();
+                    fresh.push((key.clone(), entries));
+                }
+                seen.insert(key, st);
+            }
+        }
+
+        for source in &self.sources {
+            for (key, st) in source.list() {
+                let entry_key = format!("{}:{key}", source.name());
+                if known.get(&entry_key) != Some(&st) {
+                    // Unloadable now (e.g. removed since `list`): not seen, so dropped, and retried next refresh
+                    let Some(doc) = source.load(&key) else { continue };
+                    let entry = Entry::new(doc.ko_id, doc.title, None, doc.text);
+                    fresh.push((entry_key.clone(), vec![(entry_key.clone(), entry)]));
+                }
+                seen.insert(entry_key, st);
+            }
+        }
+
+        let mut state = self.state.write();
+        let removed: Vec<String> = state.stamps.keys().filter(|k| !seen.contains_key(*k)).cloned().collect();
+        let changed = removed.len() + fresh.len();
+        for key in removed.iter().chain(fresh.iter().map(|(k, _)| k)) {
+            // A KO's chunks share its key prefix (`ko:<ko_id>#n`)
+            let chunk_prefix = format!("{key}#");
+            state.entries.retain(|k, _| k != key && !k.starts_with(&chunk_prefix));
+        }
+        for (_, entries) in fresh {
+            state.entries.extend(entries);
+        }
+        state.stamps = seen;
+        state.last_refresh = Some(Instant::now());
+        if changed > 0 {
+            state.generation += 1;
+        }
+        changed
+    }
+
+    /// Changes whenever a refresh changes what is indexed, so derived data can tell it is stale.
+    pub fn generation(&self) -> u64 {
+        self.state.read().generation
+    }
+
+    /// Ingested KOs, chunk by chunk. Workspace files and attached sources are the project's
+    /// own material and left out, so quoting them is not mistaken for copying.
+    pub fn ingested_texts(&self) -> Vec<CorpusText> {
+        let state = self.state.read();
+        state
+            .entries
+            .iter()
+            .filter_map(|(key, e)| Some((key.strip_prefix("ko:")?, e)))
+            .map(|(chunk, e)| CorpusText {
+                source: chunk.to_string(),
+                ko_id: e.ko_id.clone(),
+                text: e.text.clone(),
+            })
+            .collect()
+    }
+
+    /// Full indexed text of a KO: all chunks in order for ingested KOs, else the file or source
+    /// document.
+    pub fn content(&self, ko_id: &str) -> Option<String> {
+        if let Some(knowledge) = &self.knowledge {
+            let mut chunks = knowledge.chunks(ko_id);
+            if !chunks.is_empty() {
+                chunks.sort_by_key(|c| c.ordinal);
+                return Some(chunks.into_iter().map(|c| c.text).collect::<Vec<_>>().join("\n\n"));
+            }
+        }
+        let state = self.state.read();
+        state.entries.iter().find(|(k, e)| e.ko_id == ko_id && !k.starts_with("ko:")).map(|(_, e)| e.text.clone())
+    }
+
+    /// Embed entries that have no vector yet, batch by batch. A batch that fails is left for
+    /// the next pass without stopping the others; an input the embedder refuses is marked and
+    /// the rest of its batch embedded without it.
+    pub async fn embed_pending(&self) -> EmbedReport {
+        let mut report = EmbedReport::default();
+        let Some(embedder) = &self.embedder else { return report };
+        let pending: Vec<(String, String)> = {
+            let state = self.state.read();
+            state
+                .entries
+                .iter()
+                .filter(|(_, e)| e.vector.is_none() && !e.embed_refused)
+                .map(|(k, e)| (k.clone(), e.embed_text()))
+                .collect()
+        };
+        for batch in pending.chunks(EMBED_BATCH) {
+            let mut batch = batch.to_vec();
+            while !batch.is_empty() {
+                let texts: Vec<String> = batch.iter().map(|(_, t)| t.clone()).collect();
+                let vectors = match embedder.embed(&texts).await {
+                    Ok(v) if v.len() == texts.len() => v,
+                    Ok(v) => {
+                        let message = format!("returned {} vectors for {} texts", v.len(), texts.len());
+                        report.error = Some(EmbedError { embedder: embedder.id(), message, input: None });
+                        break;
+                    }
+                    Err(EmbedError { input: Some(i), .. }) if i < batch.len() => {
+                        let (key, _) = batch.remove(i);
+                        if let Some(e) = self.state.write().entries.get_mut(&key) {
+                            e.embed_refused = true;
+                        }
+                        report.refused += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        report.error = Some(e);
+                        break;
+                    }
+                };
+                let mut state = self.state.write();
+                for ((key, text), v) in batch.iter().zip(vectors) {
+                    // Skip entries a concurrent refresh replaced while we were embedding
+                    if let Some(e) = state.entries.get_mut(key) {
+                        if e.vector.is_none() && e.embed_text() == *text {
+                            e.vector = Some(v);
+                            report.embedded += 1;
+                        }
+                    }
+                }
+                break;
+            }
+        }
+        report
+    }
+
+    /// Workspace-relative form of `path`, plus the start of the file when it exists inside the
+    /// workspace. Anything resolving outside it (absolute paths, `..`, symlinks) is ranked by
+    /// name only and never read.
+    fn resolve_path(&self, path: &str) -> (String, Option<String>) {
+        let abs = if Path::new(path).is_absolute() { PathBuf::from(path) } else { self.root.join(path) };
+        let rel = abs
+            .strip_prefix(&self.root)
+            .map(|p| p.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/"))
+            .unwrap_or_else(|_| path.to_string());
+        let inside = match (abs.canonicalize(), self.root.canonicalize()) {
+            (Ok(file), Ok(root)) => file.starts_with(root),
+            _ => false,
+        };
+        let head = if inside { std::fs::read_to_string(&abs).ok().map(|t| t.chars().take(4000).collect()) } else { None };
+        (rel, head)
+    }
+
+    /// Rank indexed KOs against a path and/or task by BM25; one ref per KO, best chunk wins.
+    pub fn search(&self, q: &ContextQuery) -> Vec<KoRef> {
+        self.rank(q, None)
+    }
+
+    /// Like `search`, but blends BM25 with embedding similarity so related wording matches
+    /// too. Only the query is embedded; entries still waiting for `embed_pending` rank on BM25
+    /// alone. Without an embedder this is `search`.
+    pub async fn hybrid_search(&self, q: &ContextQuery) -> Result<Vec<KoRef>, EmbedError> {
+        let Some(embedder) = &self.embedder else { return Ok(self.search(q)) };
+        let mut text = q.task.clone().unwrap_or_default();
+        if let Some(p) = &q.path {
+            let (rel, head) = self.resolve_path(p);
+            text = format!("{rel}\n{}\n{text}", head.unwrap_or_default());
+        }
+        if text.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let qv = embedder.embed(&[text]).await?.pop().unwrap_or_default();
+        Ok(self.rank(q, Some((&qv, embedder.min_similarity()))))
+    }
+
+    /// `qvec` is the query embedding and the embedder's minimum similarity.
+    fn rank(&self, q: &ContextQuery, qvec: Option<(&[f32], f32)>) -> Vec<KoRef> {
+        let mut terms = Vec::new();
+        let mut rel_path = None;
+        if let Some(p) = &q.path {
+            let (rel, head) = self.resolve_path(p);
+            terms.extend(tokenize(&rel));
+            terms.extend(tokenize(head.as_deref().unwrap_or("")));
+            rel_path = Some(rel);
+        }
+        if let Some(task) = &q.task {
+            terms.extend(tokenize(task));
+        }
+        let query: HashSet<String> = terms.into_iter().collect();
+        if query.is_empty() && qvec.is_none() {
+            return Vec::new();
+        }
+
+        let state = self.state.read();
+        let n = state.entries.len() as f32;
+        let avg_len = state.entries.values().map(|e| e.len as f32).sum::<f32>() / n.max(1.0);
+        let df = |t: &str| state.entries.values().filter(|e| e.terms.contains_key(t)).count() as f32;
+        let idf: HashMap<&str, f32> = query
+            .iter()
+            .map(|t| (t.as_str(), ((n - df(t) + 0.5) / (df(t) + 0.5) + 1.0).ln()))
+            .collect();
+        let dir = rel_path.as_deref().and_then(|p| p.rsplit_once('/')).map(|(d, _)| d);
+
+        let (k1, b) = (1.2_f32, 0.75_f32);
+        let lexical: Vec<(f32, &Entry)> = state
+            .entries
+            .values()
+            // the file itself is not context for itself
+            .filter(|e| rel_path.is_none() || e.path != rel_path)
+            .map(|e| {
+                let score = query
+                    .iter()
+                    .filter_map(|t| {
+                        let tf = *e.terms.get(t)? as f32;
+                        Some(idf[t.as_str()] * tf * (k1 + 1.0) / (tf + k1 * (1.0 - b + b * e.len as f32 / avg_len.max(1.0))))
+                    })
+                    .sum();
+                (score, e)
+            })
+            .collect();
+        let max_lexical = lexical.iter().map(|(s, _)| *s).fold(0.0_f32, f32::max);
+
+        let mut best: HashMap<&str, (f32, &Entry)> = HashMap::new();
+        for (lex, e) in lexical {
+            let mut score = match qvec {
+                Some((qv, min_sim)) => {
+                    let sim = e.vector.as_deref().map_or(0.0, |ev| cosine(qv, ev).max(0.0));
+                    if lex <= 0.0 && sim < min_sim {
+                        continue;
+                    }
+                    let lex_norm = if max_lexical > 0.0 { lex / max_lexical } else { 0.0 };
+                    VECTOR_WEIGHT * sim + (1.0 - VECTOR_WEIGHT) * lex_norm
+                }
+                None => lex,
+            };
+            if score <= 0.0 {
+                continue;
+            }
+            // Neighbours in the same directory are usually related
+            if let (Some(d), Some(p)) = (dir, e.path.as_deref()) {
+                if p.starts_with(&format!("{d}/")) {
+                    score *= 1.25;
+                }
+            }
+            let slot = best.entry(e.ko_id.as_str()).or_insert((0.0, e));
+            if score > slot.0 {
+                *slot = (score, e);
+            }
+        }
+
+        let mut ranked: Vec<(f32, &Entry)> = best.into_values().collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.ko_id.cmp(&b.1.ko_id)));
+        ranked
+            .into_iter()
+            .take(q.limit.unwrap_or(10))
+            .map(|(score, e)| KoRef {
+                id: e.ko_id.clone(),
+                title: Some(e.title.clone()),
+                path: e.path.clone(),
+                score: Some((score * 1000.0).round() / 1000.0),
+                snippet: e.snippet(&query),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::HashEmbedder;
+    use crate::knowledge::{KnowledgeChunk, KnowledgeDoc};
+
+    fn write(root: &Path, rel: &str, text: &str) {
+        let p = root.join(rel);
+        std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+        std::fs::write(p, text).unwrap();
+    }
+
+    fn workspace() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "docs/adr/0001-service-registry.md", "# Service registry\nServices register with the registry and dispatch VOS messages.");
+        write(tmp.path(), "docs/adr/0002-queue-leasing.md", "# Queue leasing\nWorkers lease tasks from the queue; leases expire.");
+        write(tmp.path(), "contracts/schemas/task.runlog.schema.json", r#"{"title": "Task runlog", "properties": {"attempt": {}}}"#);
+        write(tmp.path(), "src/other.txt", "not indexed");
+        tmp
+    }
+
+    #[test]
+    fn test_tokenize_splits_identifiers() {
+        assert_eq!(tokenize("ServiceRegistry queue_engine v2"), vec!["service", "registry", "queue", "engine", "v2"]);
+    }
+
+    #[test]
+    fn test_search_by_task_and_path() {
+        let ws = workspace();
+        let index = ContextIndex::new(ws.path(), None);
+        assert_eq!(index.refresh(), 3);
+
+        let refs = index.search(&ContextQuery { task: Some("how do queue leases expire".into()), ..Default::default() });
+        assert_eq!(refs[0].id, "ko:docs/adr/0002-queue-leasing");
+        assert_eq!(refs[0].title.as_deref(), Some("Queue leasing"));
+        assert!(refs[0].snippet.as_deref().unwrap().contains("leases expire"));
+
+        let refs = index.search(&ContextQuery { path: Some("docs/adr/0001-service-registry.md".into()), ..Default::default() });
+        assert!(refs.iter().all(|r| r.id != "ko:docs/adr/0001-service-registry"));
+        assert!(index.search(&ContextQuery::default()).is_empty());
+    }
+
+    #[test]
+    fn test_path_outside_workspace_is_not_read() {
+        let ws = workspace();
+        let outside = tempfile::tempdir().unwrap();
+        write(outside.path(), "secret.md", "queue leases expire");
+        let index = ContextIndex::new(ws.path(), None);
+        index.refresh();
+        for path in [outside.path().join("secret.md").display().to_string(), "../secret.md".into()] {
+            assert_eq!(index.resolve_path(&path).1, None);
+        }
+        assert!(index.resolve_path("docs/adr/0002-queue-leasing.md").1.is_some());
+    }
+
+    struct Runlogs(Vec<(String, String)>);
+
+    impl ContextSource for Runlogs {
+        fn name(&self) -> &str {
+            "runlog"
+        }
+        fn list(&self) -> Vec<(String, String)> {
+            self.0.iter().map(|(k, _)| (k.clone(), "1".into())).collect()
+        }
+        fn load(&self, key: &str) -> Option<SourceDoc> {
+            let (_, text) = self.0.iter().find(|(k, _)| k == key)?;
+            Some(SourceDoc { ko_id: format!("ko:runlog/{key}"), title: format!("Runlog {key}"), text: text.clone() })
+        }
+    }
+
+    #[test]
+    fn test_sources_are_indexed() {
+        let ws = workspace();
+        let runlogs = Runlogs(vec![("t1/1".into(), "retried the flaky migration step".into())]);
+        let index = ContextIndex::new(ws.path(), None).with_source(Arc::new(runlogs));
+        assert_eq!(index.refresh(), 4);
+        let refs = index.search(&ContextQuery { task: Some("flaky migration".into()), ..Default::default() });
+        assert_eq!(refs[0].id, "ko:runlog/t1/1");
+        assert_eq!(index.content("ko:runlog/t1/1").as_deref(), Some("retried the flaky migration step"));
+        assert_eq!(index.refresh(), 0);
+    }
+
+    #[test]
+    fn test_incremental_refresh() {
+        let ws = workspace();
+        let index = ContextIndex::new(ws.path(), None);
+        index.refresh();
+        let generation = index.generation();
+        assert_eq!(index.refresh(), 0);
+        assert_eq!(index.generation(), generation);
+
+        write(ws.path(), "docs/adr/0002-queue-leasing.md", "# Queue leasing\nLeases are renewed by heartbeats.");
+        std::fs::remove_file(ws.path().join("contracts/schemas/task.runlog.schema.json")).unwrap();
+        assert_eq!(index.refresh(), 2);
+        assert_eq!((index.len(), index.generation()), (2, generation + 1));
+        let refs = index.search(&ContextQuery { task: Some("heartbeats".into()), ..Default::default() });
+        assert_eq!(refs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_matches_related_wording() {
+        let ws = workspace();
+        let index = ContextIndex::new(ws.path(), None).with_embedder(Arc::new(HashEmbedder::default()));
+        index.refresh();
+        let q = ContextQuery { task: Some("expiry".into()), ..Default::default() };
+        assert!(index.search(&q).is_empty());
+        // Nothing is embedded on the query path
+        assert!(index.hybrid_search(&q).await.unwrap().is_empty());
+
+        assert_eq!(index.embed_pending().await.embedded, 3);
+        let refs = index.hybrid_search(&q).await.unwrap();
+        assert_eq!(refs[0].id, "ko:docs/adr/0002-queue-leasing");
+        assert_eq!(index.embed_pending().await.embedded, 0);
+    }
+
+    /// Refuses any text mentioning "registry".
+    struct Picky;
+
+    #[async_trait::async_trait]
+    impl Embedder for Picky {
+        fn id(&self) -> String {
+            "picky".into()
+        }
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+            match texts.iter().position(|t| t.contains("registry")) {
+                Some(i) => Err(EmbedError { embedder: self.id(), message: "refused".into(), input: Some(i) }),
+                None => HashEmbedder::default().embed(texts).await,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refused_input_does_not_block_the_rest() {
+        let ws = workspace();
+        let index = ContextIndex::new(ws.path(), None).with_embedder(Arc::new(Picky));
+        index.refresh();
+        let report = index.embed_pending().await;
+        assert_eq!((report.embedded, report.refused, report.error.is_none()), (2, 1, true));
+        let again = index.embed_pending().await;
+        assert_eq!((again.embedded, again.refused), (0, 0));
+    }
+
+    #[test]
+    fn test_knowledge_chunks_rank_per_ko() {
+        let ws = workspace();
+        let knowledge = Arc::new(KnowledgeStore::open(ws.path().join(".knowledge")).unwrap());
+        let chunk = |n: usize, text: &str| KnowledgeChunk {
+            id: format!("ko:specbundle/x#{n}"),
+            ko_id: "ko:specbundle/x".into(),
+            ordinal: n,
+            part: 1,
+            heading_path: vec!["Leasing".into()],
+            text: text.into(),
+            links: vec![],
+        };
+        let doc = KnowledgeDoc {
+            ko_id: "ko:specbundle/x".into(),
+            revision: 1,
+            title: "Queue Engine Spec".into(),
+            tags: vec![],
+            headings: vec![],
+            links: vec![],
+            indexed_at: "2025-09-18T12:00:00Z".into(),
+        };
+        knowledge
+            .upsert(doc, vec![chunk(1, "visibility timeout and lease renewal"), chunk(2, "lease renewal lease renewal")])
+            .unwrap();
+        let index = ContextIndex::new(ws.path(), Some(knowledge));
+        index.refresh();
+        let refs = index.search(&ContextQuery { task: Some("lease renewal".into()), ..Default::default() });
+        let ids: Vec<_> = refs.iter().filter(|r| r.id == "ko:specbundle/x").collect();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(ids[0].title.as_deref(), Some("Queue Engine Spec › Leasing"));
+        // Only the ingested chunks, not the workspace's own files
+        let sources: Vec<_> = index.ingested_texts().into_iter().map(|t| t.source).collect();
+        assert_eq!(sources, ["ko:specbundle/x#1", "ko:specbundle/x#2"]);
+    }
+}
@@ -1,12 +1,44 @@
+use crate::context_index::{ContextIndex, ContextQuery, KoRef};
 use crate::laio_service::{LaioService, VosError, VosMessage};
 use async_trait::async_trait;
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 use uuid::Uuid;
 
-/// Minimal ContextService that returns related KO refs for a file/task.
+/// ContextService: ranked KO refs for a file/task from the workspace index.
 /// (OpenCode plugin will call via vos_dispatch.)
-pub struct ContextService;
+pub struct ContextService {
+    index: Arc<ContextIndex>,
+}
+
+/// Payload of `context.search`.
+#[derive(Debug, Deserialize)]
+pub struct ContextSearchReq {
+    pub query: String,
+    pub top_k: Option<usize>,
+}
+
+impl ContextService {
+    pub fn new(index: Arc<ContextIndex>) -> Self {
+        Self { index }
+    }
+
+    /// Hybrid ranking when an embedder is attached; falls back to BM25 alone if embedding
+    /// fails so a provider outage does not take context lookups down. Returns the refs and
+    /// the ranking that produced them.
+    async fn rank(&self, query: &ContextQuery) -> (Vec<KoRef>, String) {
+        // Picks up edited, added and removed files before answering
+        self.index.refresh_if_stale_async().await;
+        match self.index.embedder_id() {
+            Some(id) => match self.index.hybrid_search(query).await {
+                Ok(refs) => (refs, format!("hybrid:{id}")),
+                Err(e) => (self.index.search(query), format!("lexical ({e})")),
+            },
+            None => (self.index.search(query), "lexical".into()),
+        }
+    }
+}
 
 #[async_trait]
 impl LaioService for ContextService {
@@ -15,27 +47,48 @@ impl LaioService for ContextService {
     }
 
     fn capabilities(&self) -> Vec<String> {
-        vec!["context.fetch".into()]
+        vec!["context.fetch".into(), "context.search".into()]
     }
 
     async fn handle_message(&self, message: VosMessage) -> Result<VosMessage, VosError> {
-        let path = message
-            .payload
-            .get("path")
-            .and_then(|v| v.as_str())
-            .unwrap_or(".");
-        let result = json!({
-            "path": path,
-            "ko_refs": [
-                {"id":"ko://docs/adr/0001-service-registry"},
-                {"id":"ko://schemas/opencode_pm.openapi.json"}
-            ]
-        });
-        Ok(VosMessage {
-            target: "context_service".into(),
-            op: "context.fetch.ok".into(),
-            payload: result,
-        })
+        match message.op.as_str() {
+            "context.search" => {
+                let req: ContextSearchReq = serde_json::from_value(message.payload)
+                    .map_err(|e| VosError::BadRequest(format!("context.search: {e}")))?;
+                if req.query.trim().is_empty() {
+                    return Err(VosError::BadRequest("context.search needs a query".to_string()));
+                }
+                let query = ContextQuery { path: None, task: Some(req.query), limit: req.top_k };
+                let (ko_refs, ranking) = self.rank(&query).await;
+                Ok(VosMessage {
+                    target: "context_service".into(),
+                    op: "context.search.ok".into(),
+                    payload: json!({
+                        "query": query.task,
+                        "ranking": ranking,
+                        "ko_refs": ko_refs,
+                    }),
+                })
+            }
+            _ => {
+                let query: ContextQuery = serde_json::from_value(message.payload)
+                    .map_err(|e| VosError::BadRequest(format!("context.fetch: {e}")))?;
+                if query.path.is_none() && query.task.is_none() {
+                    return Err(VosError::BadRequest("context.fetch needs a path or a task".to_string()));
+                }
+                let (ko_refs, ranking) = self.rank(&query).await;
+                Ok(VosMessage {
+                    target: "context_service".into(),
+                    op: "context.fetch.ok".into(),
+                    payload: json!({
+                        "path": query.path,
+                        "task": query.task,
+                        "ranking": ranking,
+                        "ko_refs": ko_refs,
+                    }),
+                })
+            }
+        }
     }
 }
 
@@ -56,7 +109,7 @@ impl LaioService for PmService {
         match message.op.as_str() {
             "specbundle.create" => {
                 // Stub: generate a KO ref for the SpecBundle
-                let ko_ref = format!("ko://specbundle/{}", Uuid::new_v4());
+                let ko_ref = format!("ko:specbundle/{}", Uuid::new_v4());
                 Ok(VosMessage {
                     target: "opencode_pm".into(),
                     op: "specbundle.created".into(),
@@ -71,9 +124,9 @@ impl LaioService for PmService {
     }
 }
 
-pub fn default_registry() -> Arc<crate::laio_service::ServiceRegistry> {
+pub fn default_registry(index: Arc<ContextIndex>) -> Arc<crate::laio_service::ServiceRegistry> {
     let reg = Arc::new(crate::laio_service::ServiceRegistry::new());
-    reg.register(Arc::new(ContextService));
+    reg.register(Arc::new(ContextService::new(index)));
     reg.register(Arc::new(PmService));
     reg
 }
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+
+use crate::context_index::tokenize;
+
+#[derive(Debug, thiserror::Error)]
+#[error("embedder {embedder}: {message}")]
+pub struct EmbedError {
+    pub embedder: String,
+    pub message: String,
+    /// Set when the embedder refused one input (e.g. Guardian found a secret in it) rather
+    /// than failing as a whole; that input will fail the same way every time.
+    pub input: Option<usize>,
+}
+
+/// Turns text into fixed-size vectors for semantic ranking.
+/// Implementations: [`HashEmbedder`] here, provider-backed ones in the service binary.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Identifies the model; stored vectors are recomputed when it changes.
+    fn id(&self) -> String;
+
+    /// Cosine similarity below which a match with no shared query term is treated as noise.
+    fn min_similarity(&self) -> f32 {
+        0.25
+    }
+
+    /// One vector per input, in order.
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError>;
+}
+
+/// Deterministic feature-hashing embedder: word terms plus character trigrams hashed into
+/// `dim` signed buckets. No model files or network; related wordings ("lease", "leasing")
+/// still land close through shared trigrams. Used by default and in tests.
+pub struct HashEmbedder {
+    dim: usize,
+}
+
+impl HashEmbedder {
+    pub fn new(dim: usize) -> Self {
+        Self { dim: dim.max(8) }
+    }
+
+    pub fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut v = vec![0.0_f32; self.dim];
+        let mut add = |feature: &str, weight: f32| {
+            let h = fnv1a(feature.as_bytes());
+            let sign = if h >> 63 == 0 { 1.0 } else { -1.0 };
+            v[(h % self.dim as u64) as usize] += sign * weight;
+        };
+        for term in tokenize(text) {
+            add(&term, 1.0);
+            let padded: Vec<char> = format!("^{term}$").chars().collect();
+            for w in padded.windows(3) {
+                add(&w.iter().collect::<String>(), 0.5);
+            }
+        }
+        normalize(&mut v);
+        v
+    }
+}
+
+impl Default for HashEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+#[async_trait]
+impl Embedder for HashEmbedder {
+    fn id(&self) -> String {
+        format!("hash-{}", self.dim)
+    }
+
+    // Sparse features give low absolute similarities even for related text
+    fn min_similarity(&self) -> f32 {
+        0.12
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbedError> {
+        Ok(texts.iter().map(|t| self.embed_one(t)).collect())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+pub fn normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Cosine similarity; 0 when either vector is zero or the sizes differ.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let (na, nb) = (a.iter().map(|x| x * x).sum::<f32>().sqrt(), b.iter().map(|x| x * x).sum::<f32>().sqrt());
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        dot / (na * nb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_embedder_is_deterministic_and_morphology_aware() {
+        let e = HashEmbedder::default();
+        let a = e.embed_one("queue leasing");
+        assert_eq!(a, e.embed_one("queue leasing"));
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-5);
+        let near = cosine(&a, &e.embed_one("lease the queues"));
+        let far = cosine(&a, &e.embed_one("render html attachments"));
+        assert!(near > far, "{near} <= {far}");
+        assert_eq!(cosine(&[0.0; 4], &[1.0; 4]), 0.0);
+    }
+}
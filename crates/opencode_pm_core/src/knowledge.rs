@@ -0,0 +1,136 @@
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::util::replace_file;
+
+/// A heading extracted from an ingested document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Heading {
+    pub level: u8,
+    pub text: String,
+}
+
+/// One retrievable slice of a KO, usually a heading section of a markdown part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeChunk {
+    /// `<ko_id>#<ordinal>`
+    pub id: String,
+    pub ko_id: String,
+    pub ordinal: usize,
+    /// 1-based part the chunk came from.
+    pub part: usize,
+    /// Enclosing headings, outermost first.
+    pub heading_path: Vec<String>,
+    pub text: String,
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeDoc {
+    pub ko_id: String,
+    pub revision: u32,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub headings: Vec<Heading>,
+    pub links: Vec<String>,
+    /// RFC 3339 timestamp.
+    pub indexed_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    doc: KnowledgeDoc,
+    chunks: Vec<KnowledgeChunk>,
+}
+
+/// Ingested KOs, one JSON file per KO under `root`, fully loaded in memory.
+/// Writers replace a KO's document and chunks as a unit.
+pub struct KnowledgeStore {
+    root: PathBuf,
+    entries: RwLock<BTreeMap<String, Entry>>,
+    skipped: Vec<(PathBuf, String)>,
+}
+
+fn file_name(ko_id: &str) -> String {
+    let safe: String = ko_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{safe}.json")
+}
+
+impl KnowledgeStore {
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        let mut entries = BTreeMap::new();
+        let mut skipped = Vec::new();
+        for item in std::fs::read_dir(&root)? {
+            let path = item?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            // One unreadable file loses that KO only; re-ingesting it rewrites the file
+            let parsed = std::fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| serde_json::from_slice::<Entry>(&raw).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(entry) => {
+                    entries.insert(entry.doc.ko_id.clone(), entry);
+                }
+                Err(e) => skipped.push((path, e)),
+            }
+        }
+        Ok(Self { root, entries: RwLock::new(entries), skipped })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Files `open` could not read, with the reason.
+    pub fn skipped(&self) -> &[(PathBuf, String)] {
+        &self.skipped
+    }
+
+    /// Replace everything known about `doc.ko_id`, unless a newer revision is already stored.
+    /// Returns false when the write was skipped.
+    pub fn upsert(&self, doc: KnowledgeDoc, chunks: Vec<KnowledgeChunk>) -> io::Result<bool> {
+        // Held across the write so a slower, older ingest cannot land after a newer one
+        let mut entries = self.entries.write();
+        if entries.get(&doc.ko_id).is_some_and(|e| e.doc.revision > doc.revision) {
+            return Ok(false);
+        }
+        let entry = Entry { doc, chunks };
+        replace_file(&self.root.join(file_name(&entry.doc.ko_id)), &serde_json::to_vec_pretty(&entry)?)?;
+        entries.insert(entry.doc.ko_id.clone(), entry);
+        Ok(true)
+    }
+
+    pub fn remove(&self, ko_id: &str) -> io::Result<bool> {
+        match std::fs::remove_file(self.root.join(file_name(ko_id))) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        Ok(self.entries.write().remove(ko_id).is_some())
+    }
+
+    pub fn doc(&self, ko_id: &str) -> Option<KnowledgeDoc> {
+        self.entries.read().get(ko_id).map(|e| e.doc.clone())
+    }
+
+    pub fn docs(&self) -> Vec<KnowledgeDoc> {
+        self.entries.read().values().map(|e| e.doc.clone()).collect()
+    }
+
+    pub fn chunks(&self, ko_id: &str) -> Vec<KnowledgeChunk> {
+        self.entries.read().get(ko_id).map(|e| e.chunks.clone()).unwrap_or_default()
+    }
+
+    pub fn all_chunks(&self) -> Vec<KnowledgeChunk> {
+        self.entries.read().values().flat_map(|e| e.chunks.iter().cloned()).collect()
+    }
+}
@@ -0,0 +1,388 @@
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+use crate::ko_resolver::KoUri;
+
+/// How `from` relates to `to`. Edges point from the newer object to what it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    DerivedFrom,
+    References,
+    ProducedBy,
+    Supersedes,
+}
+
+impl EdgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EdgeKind::DerivedFrom => "derived_from",
+            EdgeKind::References => "references",
+            EdgeKind::ProducedBy => "produced_by",
+            EdgeKind::Supersedes => "supersedes",
+        }
+    }
+}
+
+/// One typed link plus who recorded it, when, and through which operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KoEdge {
+    pub from: String,
+    pub kind: EdgeKind,
+    pub to: String,
+    pub actor: String,
+    /// Operation that created the edge, e.g. `specbundle.create`.
+    pub via: String,
+    /// RFC 3339 timestamp.
+    pub at: String,
+}
+
+impl KoEdge {
+    fn key(&self) -> (String, EdgeKind, String) {
+        (self.from.clone(), self.kind, self.to.clone())
+    }
+}
+
+/// `ko:ns/x` and `ko://ns/x` name the same node, stored as `ko:ns/x`; anything else is kept
+/// verbatim.
+pub fn node_id(id: &str) -> String {
+    KoUri::canonical(id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// What a KO came from (follows edges forwards).
+    Ancestors,
+    /// What came from a KO (follows edges backwards).
+    Descendants,
+    Both,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: String,
+    /// Hops from the query root.
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Subgraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<KoEdge>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(Default)]
+struct State {
+    edges: Vec<KoEdge>,
+    keys: HashSet<(String, EdgeKind, String)>,
+    /// node → indexes into `edges`
+    outgoing: HashMap<String, Vec<usize>>,
+    incoming: HashMap<String, Vec<usize>>,
+}
+
+impl State {
+    fn insert(&mut self, edge: KoEdge) -> bool {
+        if !self.keys.insert(edge.key()) {
+            return false;
+        }
+        let i = self.edges.len();
+        self.outgoing.entry(edge.from.clone()).or_default().push(i);
+        self.incoming.entry(edge.to.clone()).or_default().push(i);
+        self.edges.push(edge);
+        true
+    }
+}
+
+/// KO link graph kept in memory and persisted as an append-only JSONL file of edges.
+pub struct KoGraph {
+    path: PathBuf,
+    state: RwLock<State>,
+    /// Serializes appends so the file write happens outside the `state` lock.
+    append: Mutex<()>,
+    skipped: Vec<usize>,
+}
+
+impl KoGraph {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut state = State::default();
+        let mut skipped = Vec::new();
+        let raw = match std::fs::read(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut start = 0;
+        let mut n = 0;
+        while start < raw.len() {
+            n += 1;
+            let end = raw[start..].iter().position(|&b| b == b'\n').map(|i| start + i);
+            let line = &raw[start..end.unwrap_or(raw.len())];
+            let parsed = serde_json::from_slice::<KoEdge>(line);
+            match (parsed, end) {
+                (Ok(mut edge), _) => {
+                    // Edges written before ids were canonical may use `ko://`
+                    edge.from = node_id(&edge.from);
+                    edge.to = node_id(&edge.to);
+                    state.insert(edge);
+                    if end.is_none() {
+                        std::fs::OpenOptions::new().append(true).open(&path)?.write_all(b"\n")?;
+                    }
+                }
+                // A crash mid-append leaves a partial last line; cut it so the next append
+                // starts on a line of its own
+                (Err(_), None) => {
+                    let f = std::fs::OpenOptions::new().write(true).open(&path)?;
+                    f.set_len(start as u64)?;
+                    f.sync_data()?;
+                    skipped.push(n);
+                }
+                (Err(_), Some(_)) if line.iter().all(u8::is_ascii_whitespace) => {}
+                (Err(_), Some(_)) => skipped.push(n),
+            }
+            start = end.map_or(raw.len(), |e| e + 1);
+        }
+        Ok(Self { path, state: RwLock::new(state), append: Mutex::new(()), skipped })
+    }
+
+    /// 1-based line numbers that did not parse when the file was opened. A torn last line
+    /// is also cut from the file.
+    pub fn skipped(&self) -> &[usize] {
+        &self.skipped
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.read().edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record edges; ids are normalized and duplicates (same from, kind, to) are skipped.
+    /// Returns how many were new. Edges become visible once they are on disk, and readers
+    /// are not held up by the write; this blocks, so call it off the async threads.
+    pub fn add(&self, edges: impl IntoIterator<Item = KoEdge>) -> io::Result<usize> {
+        let _append = self.append.lock();
+        let mut new = Vec::new();
+        {
+            let state = self.state.read();
+            let mut keys = HashSet::new();
+            for mut edge in edges {
+                edge.from = node_id(&edge.from);
+                edge.to = node_id(&edge.to);
+                if edge.from != edge.to && !state.keys.contains(&edge.key()) && keys.insert(edge.key()) {
+                    new.push(edge);
+                }
+            }
+        }
+        if new.is_empty() {
+            return Ok(0);
+        }
+        let mut lines = Vec::new();
+        for edge in &new {
+            lines.extend(serde_json::to_vec(edge)?);
+            lines.push(b'\n');
+        }
+        let mut f = std::fs::OpenOptions::new().create(true).append(true).open(&self.path)?;
+        f.write_all(&lines)?;
+        f.sync_data()?;
+        let added = new.len();
+        let mut state = self.state.write();
+        for edge in new {
+            state.insert(edge);
+        }
+        Ok(added)
+    }
+
+    /// Breadth-first walk from `root`. `kinds` limits the edge kinds followed (all when
+    /// empty); `max_depth` limits hops (unbounded when None). The root is not in `nodes`.
+    pub fn walk(&self, root: &str, direction: Direction, kinds: &[EdgeKind], max_depth: Option<usize>) -> Subgraph {
+        let root = node_id(root);
+        let state = self.state.read();
+        let follows = |e: &KoEdge| kinds.is_empty() || kinds.contains(&e.kind);
+        let mut seen: HashSet<String> = HashSet::from([root.clone()]);
+        let mut edge_seen: BTreeSet<usize> = BTreeSet::new();
+        let mut out = Subgraph::default();
+        let mut queue = VecDeque::from([(root, 0usize)]);
+        while let Some((id, depth)) = queue.pop_front() {
+            if max_depth.is_some_and(|m| depth >= m) {
+                continue;
+            }
+            let mut next: Vec<(usize, &String)> = Vec::new();
+            if direction != Direction::Descendants {
+                for &i in state.outgoing.get(&id).into_iter().flatten() {
+                    next.push((i, &state.edges[i].to));
+                }
+            }
+            if direction != Direction::Ancestors {
+                for &i in state.incoming.get(&id).into_iter().flatten() {
+                    next.push((i, &state.edges[i].from));
+                }
+            }
+            for (i, other) in next {
+                if !follows(&state.edges[i]) {
+                    continue;
+                }
+                edge_seen.insert(i);
+                if seen.insert(other.clone()) {
+                    out.nodes.push(GraphNode { id: other.clone(), depth: depth + 1 });
+                    queue.push_back((other.clone(), depth + 1));
+                }
+            }
+        }
+        out.edges = edge_seen.into_iter().map(|i| state.edges[i].clone()).collect();
+        out
+    }
+
+    /// Every node and edge.
+    pub fn all(&self) -> Subgraph {
+        let state = self.state.read();
+        let ids: BTreeSet<&String> = state.edges.iter().flat_map(|e| [&e.from, &e.to]).collect();
+        Subgraph {
+            nodes: ids.into_iter().map(|id| GraphNode { id: id.clone(), depth: 0 }).collect(),
+            edges: state.edges.clone(),
+        }
+    }
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn mermaid_label(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "#quot;"))
+}
+
+/// Render edges as a Graphviz digraph or a Mermaid flowchart.
+pub fn render(edges: &[KoEdge], format: GraphFormat) -> String {
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => {
+            out.push_str("digraph ko {\n  rankdir=LR;\n  node [shape=box];\n");
+            for e in edges {
+                let _ = writeln!(out, "  {} -> {} [label={}];", dot_quote(&e.from), dot_quote(&e.to), dot_quote(e.kind.as_str()));
+            }
+            out.push_str("}\n");
+        }
+        GraphFormat::Mermaid => {
+            // Mermaid node ids must be plain identifiers; KO ids become labels
+            let mut ids: HashMap<&str, String> = HashMap::new();
+            out.push_str("graph LR\n");
+            for e in edges {
+                for id in [&e.from, &e.to] {
+                    if !ids.contains_key(id.as_str()) {
+                        let n = format!("n{}", ids.len());
+                        let _ = writeln!(out, "  {n}[{}]", mermaid_label(id));
+                        ids.insert(id, n);
+                    }
+                }
+                let _ = writeln!(out, "  {} -->|{}| {}", ids[e.from.as_str()], e.kind.as_str(), ids[e.to.as_str()]);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: &str, kind: EdgeKind, to: &str) -> KoEdge {
+        KoEdge {
+            from: from.into(),
+            kind,
+            to: to.into(),
+            actor: "alice".into(),
+            via: "test".into(),
+            at: "2025-09-18T12:00:00Z".into(),
+        }
+    }
+
+    fn sample(dir: &Path) -> KoGraph {
+        let g = KoGraph::open(dir.join("edges.jsonl")).unwrap();
+        let added = g
+            .add([
+                edge("ko:specbundle/spec", EdgeKind::ProducedBy, "ko://session/claude/s1"),
+                edge("ko://task/t1", EdgeKind::References, "ko://specbundle/spec"),
+                edge("ko://runlog/t1-1", EdgeKind::ProducedBy, "ko://task/t1"),
+                edge("ko://specbundle/spec2", EdgeKind::DerivedFrom, "ko:specbundle/spec"),
+                // duplicate of the second edge in the other spelling
+                edge("ko:task/t1", EdgeKind::References, "ko:specbundle/spec"),
+            ])
+            .unwrap();
+        assert_eq!(added, 4);
+        g
+    }
+
+    #[test]
+    fn test_walks_and_reopen() {
+        let tmp = tempfile::tempdir().unwrap();
+        let g = sample(tmp.path());
+        let ids = |s: Subgraph| s.nodes.into_iter().map(|n| (n.id, n.depth)).collect::<Vec<_>>();
+
+        let desc = g.walk("ko:specbundle/spec", Direction::Descendants, &[], None);
+        assert_eq!(desc.edges.len(), 3);
+        assert_eq!(
+            ids(desc),
+            vec![("ko:task/t1".to_string(), 1), ("ko:specbundle/spec2".to_string(), 1), ("ko:runlog/t1-1".to_string(), 2)]
+        );
+        let derived = g.walk("ko://specbundle/spec", Direction::Descendants, &[EdgeKind::DerivedFrom], None);
+        assert_eq!(ids(derived), vec![("ko:specbundle/spec2".to_string(), 1)]);
+        let anc = g.walk("ko://runlog/t1-1", Direction::Ancestors, &[], Some(2));
+        assert_eq!(anc.nodes.len(), 2);
+
+        let reopened = KoGraph::open(tmp.path().join("edges.jsonl")).unwrap();
+        assert_eq!(reopened.len(), 4);
+    }
+
+    #[test]
+    fn test_open_skips_bad_lines_and_cuts_torn_tail() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("edges.jsonl");
+        drop(sample(tmp.path()));
+        let mut raw = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = raw.lines().collect();
+        let last = lines[3].to_string();
+        raw = format!("{}\n{}\nnot json\n{}", lines[..3].join("\n"), last, &last[..last.len() / 2]);
+        std::fs::write(&path, &raw).unwrap();
+
+        let g = KoGraph::open(&path).unwrap();
+        assert_eq!(g.len(), 4);
+        assert_eq!(g.skipped(), &[5, 6]);
+        assert!(std::fs::read_to_string(&path).unwrap().ends_with("not json\n"));
+        assert_eq!(g.add([edge("ko:task/t2", EdgeKind::References, "ko:task/t1")]).unwrap(), 1);
+        let reopened = KoGraph::open(&path).unwrap();
+        assert_eq!((reopened.len(), reopened.skipped()), (5, &[5][..]));
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let tmp = tempfile::tempdir().unwrap();
+        let g = sample(tmp.path());
+        let edges = g.all().edges;
+        let dot = render(&edges, GraphFormat::Dot);
+        assert!(dot.contains("\"ko:task/t1\" -> \"ko:specbundle/spec\" [label=\"references\"];"));
+        let mermaid = render(&edges, GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("graph LR\n  n0[\"ko:specbundle/spec\"]\n  n1[\"ko:session/claude/s1\"]\n  n0 -->|produced_by| n1\n"));
+    }
+}
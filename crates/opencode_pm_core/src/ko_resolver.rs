@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// A parsed KO URI. `ko:<namespace>/<path>` is the canonical spelling and the only one
+/// emitted; `ko://<namespace>/<path>` is accepted on input. An optional `#fragment` (e.g. a
+/// chunk ordinal) is kept apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KoUri {
+    pub namespace: String,
+    pub path: String,
+    pub fragment: Option<String>,
+}
+
+impl KoUri {
+    pub fn parse(s: &str) -> Result<Self, KoError> {
+        let invalid = |why: &str| KoError::InvalidUri(format!("'{s}': {why}"));
+        let rest = s
+            .strip_prefix("ko://")
+            .or_else(|| s.strip_prefix("ko:"))
+            .ok_or_else(|| invalid("expected ko:<namespace>/<path>"))?;
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((r, f)) => (r, Some(f.to_string())),
+            None => (rest, None),
+        };
+        let (namespace, path) = rest.split_once('/').ok_or_else(|| invalid("missing path"))?;
+        if namespace.is_empty() || !namespace.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(invalid("bad namespace"));
+        }
+        if path.is_empty() {
+            return Err(invalid("missing path"));
+        }
+        Ok(Self { namespace: namespace.to_ascii_lowercase(), path: path.to_string(), fragment })
+    }
+
+    /// `id` in canonical spelling; anything that is not a KO URI is returned verbatim.
+    pub fn canonical(id: &str) -> String {
+        Self::parse(id).map(|u| u.to_string()).unwrap_or_else(|_| id.to_string())
+    }
+}
+
+impl fmt::Display for KoUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ko:{}/{}", self.namespace, self.path)?;
+        if let Some(frag) = &self.fragment {
+            write!(f, "#{frag}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KoError {
+    #[error("invalid KO URI {0}")]
+    InvalidUri(String),
+    #[error("no handler for KO namespace '{0}'")]
+    UnknownNamespace(String),
+    #[error("KO not found: {0}")]
+    NotFound(String),
+    #[error("cannot resolve {id}: {message}")]
+    Handler { id: String, message: String },
+}
+
+/// Content and metadata behind a KO URI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedKo {
+    /// Canonical id, as the owning namespace spells it.
+    pub id: String,
+    pub namespace: String,
+    pub title: Option<String>,
+    pub media_type: String,
+    pub content: String,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Resolves the KOs of one namespace.
+#[async_trait]
+pub trait KoHandler: Send + Sync {
+    fn namespace(&self) -> &str;
+    async fn resolve(&self, uri: &KoUri) -> Result<ResolvedKo, KoError>;
+}
+
+#[derive(Default)]
+pub struct KoResolver {
+    handlers: RwLock<BTreeMap<String, Arc<dyn KoHandler>>>,
+}
+
+impl KoResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Later registrations for the same namespace replace earlier ones.
+    pub fn register(&self, handler: Arc<dyn KoHandler>) {
+        self.handlers.write().insert(handler.namespace().to_string(), handler);
+    }
+
+    pub fn namespaces(&self) -> Vec<String> {
+        self.handlers.read().keys().cloned().collect()
+    }
+
+    pub async fn resolve(&self, id: &str) -> Result<ResolvedKo, KoError> {
+        let uri = KoUri::parse(id)?;
+        let handler = self.handlers.read().get(&uri.namespace).cloned();
+        match handler {
+            Some(h) => h.resolve(&uri).await,
+            None => Err(KoError::UnknownNamespace(uri.namespace)),
+        }
+    }
+}
+
+pub fn media_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("yml" | "yaml") => "application/yaml",
+        Some("toml") => "application/toml",
+        _ => "text/plain",
+    }
+}
+
+/// `rel` joined under `dir`, refusing anything that could step outside it.
+fn contained(dir: &Path, rel: &str) -> Option<PathBuf> {
+    let rel = Path::new(rel);
+    rel.components().all(|c| matches!(c, Component::Normal(_))).then(|| dir.join(rel))
+}
+
+fn read_file(id: String, namespace: &str, root: &Path, path: &Path) -> Result<ResolvedKo, KoError> {
+    let content = std::fs::read_to_string(path).map_err(|e| KoError::Handler { id: id.clone(), message: e.to_string() })?;
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    let title = content.lines().find_map(|l| l.strip_prefix("# ")).map(|t| t.trim().to_string());
+    Ok(ResolvedKo {
+        id,
+        namespace: namespace.to_string(),
+        title,
+        media_type: media_type(path).to_string(),
+        content,
+        metadata: serde_json::json!({
+            "path": rel.iter().map(|s| s.to_string_lossy()).collect::<Vec<_>>().join("/"),
+        }),
+    })
+}
+
+/// Workspace files under `<root>/<namespace>/`, e.g. `ko:docs/adr/0001-x` →
+/// `docs/adr/0001-x.md`. Paths without an extension are taken to be markdown.
+pub struct FileKoHandler {
+    namespace: String,
+    root: PathBuf,
+}
+
+impl FileKoHandler {
+    pub fn new(namespace: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        Self { namespace: namespace.into(), root: root.into() }
+    }
+}
+
+#[async_trait]
+impl KoHandler for FileKoHandler {
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> Result<ResolvedKo, KoError> {
+        let id = format!("ko:{}/{}", self.namespace, uri.path);
+        let dir = self.root.join(&self.namespace);
+        let path = contained(&dir, &uri.path).ok_or_else(|| KoError::InvalidUri(format!("'{id}': path escapes namespace")))?;
+        let candidates = if path.extension().is_some() { vec![path.clone(), path.with_extension("md")] } else { vec![path.with_extension("md")] };
+        match candidates.into_iter().find(|p| p.is_file()) {
+            Some(p) => read_file(id, &self.namespace, &self.root, &p),
+            None => Err(KoError::NotFound(id)),
+        }
+    }
+}
+
+/// Directories searched by [`SchemaKoHandler`], relative to the workspace root.
+const SCHEMA_DIRS: &[&str] = &["contracts/schemas", "schemas"];
+
+/// JSON Schemas by their `$id` (`ko:schemas/context.pack.json` is the schema whose `$id`
+/// says so, in either spelling), falling back to file names in `contracts/schemas/` and `schemas/`.
+pub struct SchemaKoHandler {
+    root: PathBuf,
+}
+
+impl SchemaKoHandler {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn find(&self, uri: &KoUri) -> Option<PathBuf> {
+        let mut by_name = None;
+        for dir in SCHEMA_DIRS {
+            let dir = self.root.join(dir);
+            let Ok(rd) = std::fs::read_dir(&dir) else { continue };
+            for path in rd.flatten().map(|e| e.path()).filter(|p| media_type(p) == "application/json") {
+                let declared = std::fs::read(&path)
+                    .ok()
+                    .and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok())
+                    .and_then(|v| v.get("$id").and_then(|i| i.as_str()).and_then(|i| KoUri::parse(i).ok()));
+                if declared.is_some_and(|d| d.namespace == uri.namespace && d.path == uri.path) {
+                    return Some(path);
+                }
+            }
+            if by_name.is_none() {
+                let stem = uri.path.strip_suffix(".json").unwrap_or(&uri.path);
+                by_name = [uri.path.clone(), format!("{stem}.schema.json")]
+                    .iter()
+                    .filter_map(|name| contained(&dir, name))
+                    .find(|p| p.is_file());
+            }
+        }
+        by_name
+    }
+}
+
+#[async_trait]
+impl KoHandler for SchemaKoHandler {
+    fn namespace(&self) -> &str {
+        "schemas"
+    }
+
+    async fn resolve(&self, uri: &KoUri) -> Result<ResolvedKo, KoError> {
+        let id = format!("ko:schemas/{}", uri.path);
+        match self.find(uri) {
+            Some(p) => read_file(id, "schemas", &self.root, &p),
+            None => Err(KoError::NotFound(id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_both_spellings() {
+        let a = KoUri::parse("ko://specbundle/abc#3").unwrap();
+        let b = KoUri::parse("ko:specbundle/abc#3").unwrap();
+        assert_eq!(a, b);
+        assert_eq!((a.namespace.as_str(), a.path.as_str(), a.fragment.as_deref()), ("specbundle", "abc", Some("3")));
+        assert_eq!(a.to_string(), "ko:specbundle/abc#3");
+        assert_eq!(KoUri::canonical("ko://Task/t1"), "ko:task/t1");
+        assert_eq!(KoUri::parse("ko://docs/adr/0001-x").unwrap().path, "adr/0001-x");
+        for bad in ["specbundle/abc", "ko://docs", "ko:///x", "ko://do cs/x"] {
+            assert!(matches!(KoUri::parse(bad), Err(KoError::InvalidUri(_))), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_and_schema_handlers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("docs/adr")).unwrap();
+        std::fs::create_dir_all(root.join("contracts/schemas")).unwrap();
+        std::fs::write(root.join("docs/adr/0001-registry.md"), "# Registry\nbody").unwrap();
+        std::fs::write(root.join("contracts/schemas/context.pack.schema.json"), r#"{"$id": "ko://schemas/context.pack.json"}"#).unwrap();
+
+        let resolver = KoResolver::new();
+        resolver.register(Arc::new(FileKoHandler::new("docs", root)));
+        resolver.register(Arc::new(SchemaKoHandler::new(root)));
+
+        let doc = resolver.resolve("ko:docs/adr/0001-registry").await.unwrap();
+        assert_eq!((doc.id.as_str(), doc.title.as_deref()), ("ko:docs/adr/0001-registry", Some("Registry")));
+        assert_eq!(doc.metadata["path"], "docs/adr/0001-registry.md");
+
+        let schema = resolver.resolve("ko://schemas/context.pack.json").await.unwrap();
+        assert_eq!(schema.media_type, "application/json");
+        assert!(resolver.resolve("ko://schemas/context.pack.schema.json").await.is_ok());
+
+        assert!(matches!(resolver.resolve("ko://docs/../secrets").await, Err(KoError::InvalidUri(_))));
+        assert!(matches!(resolver.resolve("ko://docs/missing").await, Err(KoError::NotFound(_))));
+        assert!(matches!(resolver.resolve("ko://runlog/x").await, Err(KoError::UnknownNamespace(_))));
+    }
+}
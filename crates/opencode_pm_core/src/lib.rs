@@ -1,2 +1,11 @@
+pub mod context_index;
 pub mod context_service;
+pub mod embedding;
+pub mod knowledge;
+pub mod ko_graph;
+pub mod ko_resolver;
 pub mod laio_service;
+pub mod originality;
+pub mod schema;
+pub mod tes_plan;
+pub mod util;
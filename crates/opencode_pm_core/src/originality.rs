@@ -0,0 +1,181 @@
+//! Near-duplicate detection: texts are reduced to word shingles, shingle sets to MinHash
+//! signatures, and two signatures estimate the Jaccard similarity of their texts.
+
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+
+/// Words per shingle.
+pub const DEFAULT_SHINGLE_SIZE: usize = 5;
+/// Hash functions per signature; the estimate's error is about `1/sqrt(SIGNATURE_LEN)`.
+pub const SIGNATURE_LEN: usize = 128;
+/// Long texts are fingerprinted in windows of this many words, overlapping by half, so a
+/// copied paragraph is not drowned out by the rest of the document it came from.
+pub const WINDOW_WORDS: usize = 200;
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes of the lowercased alphanumeric words; punctuation and layout do not count.
+fn words(text: &str) -> Vec<u64> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(|w| fnv1a(w.to_lowercase().as_bytes())).collect()
+}
+
+fn shingle_words(words: &[u64], k: usize) -> HashSet<u64> {
+    words.windows(k.max(1)).map(|w| w.iter().fold(0u64, |h, x| splitmix(h ^ x))).collect()
+}
+
+/// Hashes of every run of `k` consecutive words. Empty when the text is shorter than `k`.
+pub fn shingles(text: &str, k: usize) -> HashSet<u64> {
+    shingle_words(&words(text), k)
+}
+
+fn signature(shingles: &HashSet<u64>) -> Vec<u64> {
+    (0..SIGNATURE_LEN as u64).map(|i| shingles.iter().map(|s| splitmix(s ^ splitmix(i))).min().unwrap_or(u64::MAX)).collect()
+}
+
+/// MinHash signatures of a text, one per window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    windows: Vec<Vec<u64>>,
+}
+
+impl Fingerprint {
+    /// `None` for texts too short to hold a single shingle.
+    pub fn of(text: &str, k: usize) -> Option<Self> {
+        let words = words(text);
+        if words.len() < k.max(1) {
+            return None;
+        }
+        let step = WINDOW_WORDS / 2;
+        let mut starts: Vec<usize> = (0..words.len().saturating_sub(WINDOW_WORDS) + 1).step_by(step).collect();
+        // The last window ends with the text
+        if starts.last().is_some_and(|&last| last + WINDOW_WORDS < words.len()) {
+            starts.push(words.len() - WINDOW_WORDS);
+        }
+        let windows = starts.into_iter().map(|s| signature(&shingle_words(&words[s..(s + WINDOW_WORDS).min(words.len())], k))).collect();
+        Some(Self { windows })
+    }
+
+    /// Estimated Jaccard similarity (0.0–1.0) of the most similar pair of windows.
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        let pair = |a: &Vec<u64>, b: &Vec<u64>| a.iter().zip(b).filter(|(x, y)| x == y).count();
+        let best = self.windows.iter().flat_map(|a| other.windows.iter().map(move |b| pair(a, b))).max().unwrap_or(0);
+        best as f32 / SIGNATURE_LEN as f32
+    }
+}
+
+/// One text of a corpus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusText {
+    /// What a match names, e.g. a chunk id or a file KO.
+    pub source: String,
+    /// KO the text belongs to, so a KO can be checked against everything but itself.
+    pub ko_id: String,
+    pub text: String,
+}
+
+struct Indexed {
+    ko_id: String,
+    text_hash: u64,
+    fingerprint: Option<Fingerprint>,
+}
+
+/// Fingerprints of a corpus, recomputed only for texts that changed.
+pub struct FingerprintIndex {
+    shingle_size: usize,
+    entries: RwLock<HashMap<String, Indexed>>,
+}
+
+impl FingerprintIndex {
+    pub fn new(shingle_size: usize) -> Self {
+        Self { shingle_size, entries: RwLock::new(HashMap::new()) }
+    }
+
+    pub fn shingle_size(&self) -> usize {
+        self.shingle_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().is_empty()
+    }
+
+    /// Make the index hold exactly `corpus`.
+    pub fn sync(&self, corpus: Vec<CorpusText>) {
+        let mut entries = self.entries.write();
+        let mut next = HashMap::with_capacity(corpus.len());
+        for t in corpus {
+            let text_hash = fnv1a(t.text.as_bytes());
+            let entry = match entries.remove(&t.source) {
+                Some(e) if e.text_hash == text_hash => Indexed { ko_id: t.ko_id, ..e },
+                _ => Indexed { ko_id: t.ko_id, text_hash, fingerprint: Fingerprint::of(&t.text, self.shingle_size) },
+            };
+            next.insert(t.source, entry);
+        }
+        *entries = next;
+    }
+
+    /// Sources at least `min_similarity` similar to `fingerprint`, most similar first,
+    /// skipping texts of `exclude_ko`.
+    pub fn matches(&self, fingerprint: &Fingerprint, min_similarity: f32, exclude_ko: Option<&str>) -> Vec<(String, f32)> {
+        let entries = self.entries.read();
+        let mut out: Vec<(String, f32)> = entries
+            .iter()
+            .filter(|(_, e)| exclude_ko != Some(e.ko_id.as_str()))
+            .filter_map(|(source, e)| Some((source.clone(), fingerprint.similarity(e.fingerprint.as_ref()?))))
+            .filter(|(_, s)| *s >= min_similarity)
+            .collect();
+        out.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "The scheduler hands out ready tasks by priority, then by the time they became ready, \
+        so that work is picked up in the order it became available and nothing starves.";
+
+    #[test]
+    fn test_similarity_tracks_shared_text() {
+        let k = DEFAULT_SHINGLE_SIZE;
+        let a = Fingerprint::of(TEXT, k).unwrap();
+        // Case and punctuation do not matter
+        assert_eq!(a.similarity(&Fingerprint::of(&TEXT.to_uppercase().replace(',', ";"), k).unwrap()), 1.0);
+        let edited = TEXT.replace("priority", "urgency");
+        let s = a.similarity(&Fingerprint::of(&edited, k).unwrap());
+        assert!(s > 0.5 && s < 1.0, "{s}");
+        let other = Fingerprint::of("Gates wait for their trigger tasks and a reviewer before anything after them runs at all.", k).unwrap();
+        assert!(a.similarity(&other) < 0.1);
+        assert!(Fingerprint::of("too short", k).is_none());
+
+        // A paragraph lifted from a long document is still found
+        let filler: String = (0..600).map(|i| format!("word{i} ")).collect();
+        let long = format!("{filler}{TEXT} {filler}");
+        assert!(a.similarity(&Fingerprint::of(&long, k).unwrap()) > 0.15);
+    }
+
+    #[test]
+    fn test_index_matches_and_excludes() {
+        let index = FingerprintIndex::new(DEFAULT_SHINGLE_SIZE);
+        let text = |source: &str, ko_id: &str, text: &str| CorpusText { source: source.into(), ko_id: ko_id.into(), text: text.into() };
+        index.sync(vec![text("ko:a#1", "ko:a", TEXT), text("ko://docs/b.md", "ko://docs/b.md", "unrelated words entirely here today")]);
+        let fp = Fingerprint::of(TEXT, DEFAULT_SHINGLE_SIZE).unwrap();
+        assert_eq!(index.matches(&fp, 0.15, None), vec![("ko:a#1".to_string(), 1.0)]);
+        assert!(index.matches(&fp, 0.15, Some("ko:a")).is_empty());
+        index.sync(vec![]);
+        assert!(index.is_empty());
+    }
+}
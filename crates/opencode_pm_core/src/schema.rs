@@ -0,0 +1,17 @@
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+/// Validate `instance` against a JSON Schema document (draft taken from its `$schema`).
+/// Errors are `<instance path>: <message>`, or a single compile error for a bad schema.
+pub fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<String>> {
+    let compiled = JSONSchema::compile(schema).map_err(|e| vec![format!("invalid schema: {e}")])?;
+    let result = compiled.validate(instance).map_err(|errors| {
+        errors
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                format!("{}: {e}", if path.is_empty() { "/" } else { &path })
+            })
+            .collect()
+    });
+    result
+}
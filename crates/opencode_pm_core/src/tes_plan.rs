@@ -0,0 +1,490 @@
+//! TES-2025 plan documents: the `TASK-ID` / `GATE-ID` blocks of `docs/TES-2025-SCHEDULING.md`
+//! (§6.2–6.4), grouped under `#### Section X: ...` headings.
+//!
+//! Both the bulleted form (`**TASK-A1: Name**` followed by `- Type: ...`) and the plain form
+//! (`TASK-A1: Name` followed by `Type: ...`) are accepted. Fenced code blocks are templates
+//! and examples, and are skipped. References are plan keys; a `.completed` suffix is
+//! accepted and dropped, since completion is the only event a task or gate can wait on.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A problem found in a plan document, pointing at its 1-based line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+impl PlanIssue {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for PlanIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// One `Predecessors` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanRef {
+    Key(String),
+    /// `Either: TASK-B1 or TASK-B2`
+    Either(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskBlock {
+    pub key: String,
+    pub title: Option<String>,
+    /// `Type`
+    pub kind: Option<String>,
+    pub status: Option<String>,
+    pub predecessors: Vec<PlanRef>,
+    pub successors: Vec<String>,
+    pub deliverables: Vec<String>,
+    pub validation: Option<String>,
+    /// Line of the block heading, and of each field by lower-case name.
+    pub line: usize,
+    pub field_lines: HashMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GateBlock {
+    pub key: String,
+    pub title: Option<String>,
+    pub trigger: Vec<String>,
+    /// `Validation Requirements` → `Automated`
+    pub automated: Vec<String>,
+    /// `Validation Requirements` → `Human`
+    pub human: Vec<String>,
+    /// `Actions` other than `Post-Gate`, as (name, text), e.g. ("IP Review", "...").
+    pub actions: Vec<(String, String)>,
+    /// `Actions` → `Post-Gate`
+    pub post_gate: Vec<String>,
+    pub status: Option<String>,
+    pub line: usize,
+    pub field_lines: HashMap<String, usize>,
+}
+
+macro_rules! line_of {
+    ($t:ty) => {
+        impl $t {
+            /// Line of `field` when the block has it, else of the block heading.
+            pub fn line_of(&self, field: &str) -> usize {
+                self.field_lines.get(field).copied().unwrap_or(self.line)
+            }
+        }
+    };
+}
+line_of!(TaskBlock);
+line_of!(GateBlock);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanBlock {
+    Task(TaskBlock),
+    Gate(GateBlock),
+}
+
+impl PlanBlock {
+    pub fn key(&self) -> &str {
+        match self {
+            PlanBlock::Task(t) => &t.key,
+            PlanBlock::Gate(g) => &g.key,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            PlanBlock::Task(t) => t.line,
+            PlanBlock::Gate(g) => g.line,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanSection {
+    /// Heading text after `Section `, e.g. `A: Foundation & Infrastructure`; `None` for
+    /// blocks before the first section heading.
+    pub name: Option<String>,
+    pub blocks: Vec<PlanBlock>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plan {
+    /// The document's `# ` heading.
+    pub title: Option<String>,
+    pub sections: Vec<PlanSection>,
+}
+
+impl Plan {
+    pub fn blocks(&self) -> impl Iterator<Item = &PlanBlock> {
+        self.sections.iter().flat_map(|s| s.blocks.iter())
+    }
+}
+
+fn is_plan_key(s: &str) -> bool {
+    let rest = s.strip_prefix("TASK-").or_else(|| s.strip_prefix("GATE-"));
+    rest.is_some_and(|r| !r.is_empty() && r.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+}
+
+/// `**TASK-A1: Name**`, `### GATE-X: Name`, `TASK-A1: Name` or a bare key.
+fn block_start(line: &str) -> Option<(String, Option<String>)> {
+    let s = line.trim_start_matches('#').trim();
+    let s = s.strip_prefix("**").map(|s| s.trim_end().trim_end_matches("**")).unwrap_or(s);
+    let (key, title) = match s.split_once(':') {
+        Some((k, t)) => (k.trim(), Some(t.trim().trim_end_matches("**").trim())),
+        None => (s.trim(), None),
+    };
+    let key = key.trim_end_matches("**");
+    if !(key.starts_with("TASK-") || key.starts_with("GATE-")) || !is_plan_key(key) {
+        return None;
+    }
+    Some((key.to_string(), title.filter(|t| !t.is_empty()).map(str::to_string)))
+}
+
+struct Field<'a> {
+    indent: usize,
+    bullet: bool,
+    name: &'a str,
+    value: &'a str,
+}
+
+fn field(raw: &str) -> Option<Field<'_>> {
+    let indent = raw.len() - raw.trim_start().len();
+    let s = raw.trim();
+    let (bullet, s) = match s.strip_prefix("- ").or_else(|| s.strip_prefix("* ")) {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, s),
+    };
+    let (name, value) = s.split_once(':')?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '/' | '-')) {
+        return None;
+    }
+    Some(Field { indent, bullet, name, value: value.trim() })
+}
+
+/// `None`, `[a, b]` or `a, b`.
+fn list(value: &str) -> Vec<String> {
+    let v = value.trim();
+    if v.is_empty() || v.eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    let v = v.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(v);
+    v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+fn reference(item: &str, line: usize, issues: &mut Vec<PlanIssue>) -> Option<String> {
+    let key = item.strip_suffix(".completed").unwrap_or(item).trim();
+    if is_plan_key(key) && !key.contains('.') {
+        return Some(key.to_string());
+    }
+    match key.rsplit_once('.') {
+        Some((k, event)) if is_plan_key(k) => {
+            issues.push(PlanIssue::new(line, format!("'{item}': only .completed events can be waited on, not .{event}")))
+        }
+        _ => issues.push(PlanIssue::new(line, format!("'{item}' is not a TASK- or GATE- id"))),
+    }
+    None
+}
+
+fn predecessors(value: &str, line: usize, issues: &mut Vec<PlanIssue>) -> Vec<PlanRef> {
+    let mut out = Vec::new();
+    for item in list(value) {
+        let either = item.strip_prefix("Either:").or_else(|| item.strip_prefix("either:"));
+        match either {
+            Some(members) => {
+                let keys: Vec<String> =
+                    members.split(" or ").filter_map(|m| reference(m.trim(), line, issues)).collect();
+                if keys.is_empty() {
+                    issues.push(PlanIssue::new(line, "empty Either entry"));
+                } else {
+                    out.push(PlanRef::Either(keys));
+                }
+            }
+            None => out.extend(reference(&item, line, issues).map(PlanRef::Key)),
+        }
+    }
+    out
+}
+
+fn text(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+struct Open {
+    block: PlanBlock,
+    /// `validation requirements` or `actions` while their nested items follow.
+    parent: Option<String>,
+}
+
+impl Open {
+    /// The nested-field parent `f` belongs to, if any.
+    fn nested_under(&self, f: &Field<'_>) -> Option<&str> {
+        self.parent.as_deref().filter(|_| f.indent > 0)
+    }
+
+    fn knows(&self, parent: Option<&str>, name: &str) -> bool {
+        match (&self.block, parent) {
+            (PlanBlock::Task(_), None) => {
+                matches!(name, "type" | "status" | "predecessors" | "successors" | "deliverables" | "validation")
+            }
+            (PlanBlock::Gate(_), None) => matches!(name, "trigger" | "status" | "validation requirements" | "actions"),
+            (PlanBlock::Gate(_), Some("validation requirements")) => matches!(name, "automated" | "human"),
+            (PlanBlock::Gate(_), Some("actions")) => true,
+            _ => false,
+        }
+    }
+
+    /// Apply one field line; false when the line is not part of this block.
+    fn apply(&mut self, f: &Field<'_>, line: usize, issues: &mut Vec<PlanIssue>) -> bool {
+        let name = f.name.to_ascii_lowercase();
+        let parent = self.nested_under(f).map(str::to_string);
+        if !self.knows(parent.as_deref(), &name) {
+            // A plain `Word: text` line after a plain-form block is prose, not a field
+            if !f.bullet && f.indent == 0 {
+                return false;
+            }
+            issues.push(PlanIssue::new(line, format!("unknown field '{}' in {}", f.name, self.block.key())));
+            return true;
+        }
+        let slot = match &parent {
+            Some(p) => format!("{p}/{name}"),
+            None => name.clone(),
+        };
+        let lines = match &mut self.block {
+            PlanBlock::Task(t) => &mut t.field_lines,
+            PlanBlock::Gate(g) => &mut g.field_lines,
+        };
+        // Actions are free-form and may repeat
+        if let Some(first) = lines.insert(slot, line).filter(|_| parent.as_deref() != Some("actions")) {
+            issues.push(PlanIssue::new(line, format!("duplicate field '{}' (first on line {first})", f.name)));
+        }
+        match (&mut self.block, parent.as_deref(), name.as_str()) {
+            (PlanBlock::Task(t), _, "type") => t.kind = text(f.value),
+            (PlanBlock::Task(t), _, "status") => t.status = text(f.value),
+            (PlanBlock::Task(t), _, "predecessors") => t.predecessors = predecessors(f.value, line, issues),
+            (PlanBlock::Task(t), _, "successors") => {
+                t.successors = list(f.value).iter().filter_map(|s| reference(s, line, issues)).collect()
+            }
+            (PlanBlock::Task(t), _, "deliverables") => t.deliverables = list(f.value),
+            (PlanBlock::Task(t), _, _) => t.validation = text(f.value),
+            (PlanBlock::Gate(g), None, "trigger") => {
+                g.trigger = list(f.value).iter().filter_map(|s| reference(s, line, issues)).collect()
+            }
+            (PlanBlock::Gate(g), None, "status") => g.status = text(f.value),
+            (PlanBlock::Gate(g), Some(_), "automated") => g.automated = list(f.value),
+            (PlanBlock::Gate(g), Some(_), "human") => g.human = list(f.value),
+            (PlanBlock::Gate(g), Some(_), "post-gate") => g.post_gate = list(f.value),
+            (PlanBlock::Gate(g), Some(_), _) => g.actions.push((f.name.to_string(), f.value.to_string())),
+            (PlanBlock::Gate(_), None, _) => {}
+        }
+        if parent.is_none() {
+            self.parent = Some(name).filter(|n| matches!(n.as_str(), "validation requirements" | "actions"));
+        }
+        true
+    }
+}
+
+/// Parse a plan document. Blocks with problems are still returned, as far as they could be
+/// read; the issues say what to fix.
+pub fn parse(markdown: &str) -> (Plan, Vec<PlanIssue>) {
+    let mut plan = Plan::default();
+    let mut issues = Vec::new();
+    let mut open: Option<Open> = None;
+    let mut in_fence = false;
+
+    fn close(plan: &mut Plan, open: &mut Option<Open>) {
+        if let Some(o) = open.take() {
+            if plan.sections.is_empty() {
+                plan.sections.push(PlanSection::default());
+            }
+            if let Some(s) = plan.sections.last_mut() {
+                s.blocks.push(o.block);
+            }
+        }
+    }
+
+    for (i, raw) in markdown.lines().enumerate() {
+        let n = i + 1;
+        let trimmed = raw.trim();
+        if trimmed.starts_with("```") {
+            close(&mut plan, &mut open);
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if trimmed.is_empty() {
+            close(&mut plan, &mut open);
+            continue;
+        }
+        if let Some((key, title)) = block_start(trimmed) {
+            close(&mut plan, &mut open);
+            let block = match key.starts_with("TASK-") {
+                true => PlanBlock::Task(TaskBlock { key, title, line: n, ..Default::default() }),
+                false => PlanBlock::Gate(GateBlock { key, title, line: n, ..Default::default() }),
+            };
+            open = Some(Open { block, parent: None });
+            continue;
+        }
+        if let Some(heading) = trimmed.strip_prefix('#') {
+            close(&mut plan, &mut open);
+            let level = 1 + heading.chars().take_while(|c| *c == '#').count();
+            let heading = heading.trim_start_matches('#').trim();
+            if level == 1 && plan.title.is_none() {
+                plan.title = Some(heading.to_string());
+            } else if let Some(name) = heading.strip_prefix("Section ") {
+                plan.sections.push(PlanSection { name: Some(name.trim().to_string()), blocks: Vec::new() });
+            }
+            continue;
+        }
+        let Some(o) = open.as_mut() else { continue };
+        let handled = match field(raw) {
+            Some(f) => o.apply(&f, n, &mut issues),
+            None if trimmed.starts_with("- ") => {
+                issues.push(PlanIssue::new(n, format!("expected 'Field: value' in {}", o.block.key())));
+                true
+            }
+            None => false,
+        };
+        if !handled {
+            close(&mut plan, &mut open);
+        }
+    }
+    close(&mut plan, &mut open);
+    if in_fence {
+        issues.push(PlanIssue::new(markdown.lines().count(), "unterminated code fence"));
+    }
+    (plan, issues)
+}
+
+fn render_list(items: &[String]) -> String {
+    match items.is_empty() {
+        true => "None".to_string(),
+        false => format!("[{}]", items.join(", ")),
+    }
+}
+
+fn render_heading(out: &mut String, key: &str, title: &Option<String>) {
+    match title {
+        Some(t) => out.push_str(&format!("**{key}: {t}**\n")),
+        None => out.push_str(&format!("**{key}**\n")),
+    }
+}
+
+/// Render a plan in the bulleted form `parse` reads back.
+pub fn render(plan: &Plan) -> String {
+    let mut out = String::new();
+    if let Some(title) = &plan.title {
+        out.push_str(&format!("# {title}\n\n"));
+    }
+    for section in &plan.sections {
+        if let Some(name) = &section.name {
+            out.push_str(&format!("#### Section {name}\n\n"));
+        }
+        for block in &section.blocks {
+            match block {
+                PlanBlock::Task(t) => {
+                    render_heading(&mut out, &t.key, &t.title);
+                    if let Some(kind) = &t.kind {
+                        out.push_str(&format!("- Type: {kind}\n"));
+                    }
+                    if let Some(status) = &t.status {
+                        out.push_str(&format!("- Status: {status}\n"));
+                    }
+                    let preds: Vec<String> = t
+                        .predecessors
+                        .iter()
+                        .map(|p| match p {
+                            PlanRef::Key(k) => k.clone(),
+                            PlanRef::Either(ks) => format!("Either: {}", ks.join(" or ")),
+                        })
+                        .collect();
+                    out.push_str(&format!("- Predecessors: {}\n", render_list(&preds)));
+                    out.push_str(&format!("- Successors: {}\n", render_list(&t.successors)));
+                    out.push_str(&format!("- Deliverables: {}\n", render_list(&t.deliverables)));
+                    if let Some(v) = &t.validation {
+                        out.push_str(&format!("- Validation: {v}\n"));
+                    }
+                }
+                PlanBlock::Gate(g) => {
+                    render_heading(&mut out, &g.key, &g.title);
+                    let trigger: Vec<String> = g.trigger.iter().map(|k| format!("{k}.completed")).collect();
+                    out.push_str(&format!("- Trigger: {}\n", render_list(&trigger)));
+                    if let Some(status) = &g.status {
+                        out.push_str(&format!("- Status: {status}\n"));
+                    }
+                    if !g.automated.is_empty() || !g.human.is_empty() {
+                        out.push_str("- Validation Requirements:\n");
+                        if !g.automated.is_empty() {
+                            out.push_str(&format!("  - Automated: {}\n", g.automated.join(", ")));
+                        }
+                        if !g.human.is_empty() {
+                            out.push_str(&format!("  - Human: {}\n", g.human.join(", ")));
+                        }
+                    }
+                    if !g.actions.is_empty() || !g.post_gate.is_empty() {
+                        out.push_str("- Actions:\n");
+                        for (name, value) in &g.actions {
+                            out.push_str(&format!("  - {name}: {value}\n"));
+                        }
+                        if !g.post_gate.is_empty() {
+                            out.push_str(&format!("  - Post-Gate: {}\n", g.post_gate.join(", ")));
+                        }
+                    }
+                }
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_scheduling_doc_and_round_trips() {
+        let (plan, issues) = parse(include_str!("../../../docs/TES-2025-SCHEDULING.md"));
+        assert!(issues.is_empty(), "{issues:?}");
+        let names: Vec<_> = plan.sections.iter().filter_map(|s| s.name.as_deref()).collect();
+        assert_eq!(names.len(), 5);
+        assert_eq!(names[0], "A: Foundation & Infrastructure");
+        assert_eq!(plan.blocks().filter(|b| matches!(b, PlanBlock::Task(_))).count(), 14);
+        assert_eq!(plan.blocks().filter(|b| matches!(b, PlanBlock::Gate(_))).count(), 5);
+
+        let b3 = plan.blocks().find(|b| b.key() == "TASK-B3").unwrap();
+        let PlanBlock::Task(b3) = b3 else { panic!() };
+        assert_eq!(b3.predecessors, vec![PlanRef::Either(vec!["TASK-B1".into(), "TASK-B2".into()])]);
+        assert_eq!(b3.line_of("predecessors"), 110);
+        let PlanBlock::Gate(gate) = plan.blocks().find(|b| b.key() == "GATE-A-READY").unwrap() else { panic!() };
+        assert_eq!(gate.trigger, ["TASK-A2", "TASK-A3", "TASK-A4"]);
+        assert_eq!(gate.automated.len(), 3);
+        assert_eq!(gate.actions.len(), 4);
+        assert_eq!(gate.post_gate, ["Project analysis", "repo cleanup", "commit to git"]);
+
+        let rendered = render(&plan);
+        let (again, issues) = parse(&rendered);
+        assert!(issues.is_empty(), "{issues:?}");
+        assert_eq!(render(&again), rendered);
+    }
+
+    #[test]
+    fn test_reports_line_numbered_issues() {
+        let doc = "# Plan\n\nTASK-X1: Plain form\nType: Testing\nPredecessors: [TASK-W1.failed, foo]\n\n\
+                   **GATE-X: Check**\n- Trigger: [TASK-X1]\n- Owner: someone\n- Trigger: [TASK-X1]\n";
+        let (plan, issues) = parse(doc);
+        assert_eq!(plan.blocks().count(), 2);
+        let lines: Vec<usize> = issues.iter().map(|i| i.line).collect();
+        assert_eq!(lines, [5, 5, 9, 10], "{issues:?}");
+        assert!(issues[0].message.contains(".failed"));
+        assert!(issues[3].to_string().starts_with("line 10: duplicate field"));
+    }
+}
@@ -0,0 +1,19 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Write through a temporary `<name>.tmp-<uuid>` sibling, fsync it and rename it into place,
+/// so a crash leaves the old or the new content.
+pub fn replace_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()));
+    let mut f = fs::File::create(&tmp)?;
+    f.write_all(data)?;
+    f.sync_all()?;
+    fs::rename(&tmp, path)?;
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}